@@ -0,0 +1,109 @@
+// Compares loading a `.tab` through YAML with loading its binary cache.
+//
+//     cargo bench --bench tab_cache [path/to/arrangement.tab]
+//
+// Without a path a synthetic chart with many difficulty levels is used.
+
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tabs_app::file::song::{StringTab, Tab, TabChord, TabNote, TabNoteChart, TabSection};
+use tabs_app::file::tab_cache::{decode_tab, encode_tab};
+
+const LEVELS: i32 = 20;
+const NOTES_PER_LEVEL: usize = 2_000;
+const MEASURE_TIME: Duration = Duration::from_secs(2);
+
+fn synthetic_tab() -> Tab {
+    let sections = (0..12)
+        .map(|index| TabSection {
+            name: format!("Section {index}"),
+            start_time: index as f32 * 20.0,
+            end_time: (index + 1) as f32 * 20.0,
+        })
+        .collect();
+    let chords = (0..8)
+        .map(|index| TabChord {
+            name: format!("Chord {index}"),
+            fingers: vec![1, 3, 4, -1, -1, -1],
+            frets: vec![index, index + 2, index + 2, -1, -1, -1],
+        })
+        .collect();
+    let note_charts = (0..LEVELS)
+        .map(|difficulty| TabNoteChart {
+            difficulty,
+            notes: (0..NOTES_PER_LEVEL)
+                .map(|index| TabNote {
+                    time: index as f32 * 0.12,
+                    techniques: Vec::new(),
+                    chord_index: -1,
+                    string: (index % 6) as i32,
+                    fret: (index % 15) as i32,
+                    anchor_fret: 1,
+                    sustain: 0.0,
+                    slide_to: -1,
+                    slide_unpitch_to: -1,
+                    vibrato: 0,
+                    max_bend: 0.0,
+                    slap: -1,
+                    pluck: -1,
+                    tap: 0,
+                })
+                .collect(),
+        })
+        .collect();
+    Tab::Strings(StringTab {
+        sections,
+        chords,
+        note_charts,
+    })
+}
+
+// Runs `f` repeatedly for about `MEASURE_TIME` and returns the mean time per run.
+fn measure(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < MEASURE_TIME {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    // `cargo bench` passes its own flags along.
+    let source = match env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(path) => fs::read(&path).unwrap_or_else(|err| panic!("failed to read {path}: {err}")),
+        None => synthetic_tab()
+            .to_yaml()
+            .expect("synthetic tab serializes")
+            .into_bytes(),
+    };
+    let tab = Tab::from_yaml(&source).expect("tab parses");
+    let cache = encode_tab(&tab, &source).expect("tab encodes");
+
+    let yaml = measure(|| {
+        black_box(Tab::from_yaml(black_box(&source)).unwrap());
+    });
+    let cached = measure(|| {
+        black_box(decode_tab(black_box(&cache), black_box(&source)).unwrap());
+    });
+    let hashed = measure(|| {
+        black_box(blake3::hash(black_box(&source)));
+    });
+
+    println!(
+        "tab: {} KiB of YAML, {} KiB cached",
+        source.len() / 1024,
+        cache.len() / 1024
+    );
+    println!("yaml parse:    {yaml:>12.2?}");
+    println!("cache decode:  {cached:>12.2?} (of which hashing {hashed:.2?})");
+    println!(
+        "speedup:       {:>11.1}x",
+        yaml.as_secs_f64() / cached.as_secs_f64()
+    );
+}
@@ -0,0 +1,279 @@
+pub mod stretch;
+pub use stretch::{
+    TimeStretchControl, TimeStretchDecoder, Wsola, MAX_STRETCH_RATE, MIN_STRETCH_RATE,
+};
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use kira::sound::streaming::{Decoder, StreamingSoundData, StreamingSoundHandle};
+use kira::sound::{FromFileError, PlaybackState};
+use kira::{AudioManager, AudioManagerSettings, DefaultBackend, PlaySoundError, Tween};
+use thiserror::Error;
+
+use crate::file::package::open_song_file;
+
+// Opus is missing because symphonia has no decoder for it.
+pub const AUDIO_EXTENSIONS: [&str; 4] = ["wav", "ogg", "flac", "mp3"];
+
+#[derive(Resource)]
+pub struct StreamingAudio {
+    manager: AudioManager<DefaultBackend>,
+}
+
+impl FromWorld for StreamingAudio {
+    fn from_world(_world: &mut World) -> Self {
+        let manager =
+            AudioManager::new(AudioManagerSettings::default()).expect("Failed to init audio");
+        Self { manager }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StreamingAudioError {
+    #[error("failed to load streaming audio from {path}: {source}")]
+    Load {
+        path: PathBuf,
+        #[source]
+        source: FromFileError,
+    },
+    #[error("failed to start streaming playback: {0}")]
+    Play(#[from] PlaySoundError<FromFileError>),
+}
+
+impl StreamingAudio {
+    const FALLBACK_DURATION_SECS: f64 = 3600.0;
+
+    fn duration_from_codec_params(
+        params: &symphonia::core::codecs::CodecParameters,
+    ) -> Option<f64> {
+        let sample_rate = params.sample_rate?;
+        let n_frames = params.n_frames?;
+        Some(n_frames as f64 / sample_rate as f64)
+    }
+
+    fn estimate_duration_secs(path: &Path) -> Option<f64> {
+        use symphonia::core::formats::FormatOptions;
+        use symphonia::core::io::MediaSourceStream;
+        use symphonia::core::meta::MetadataOptions;
+        use symphonia::core::probe::Hint;
+        use symphonia::default::get_probe;
+
+        let file = open_song_file(path).ok()?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let probed = get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .ok()?;
+        let mut format = probed.format;
+        let track = format.default_track()?;
+        let track_id = track.id;
+        let params = track.codec_params.clone();
+        if let Some(secs) = Self::duration_from_codec_params(&params) {
+            return Some(secs);
+        }
+
+        // MP3s without a Xing or VBRI header, and some Ogg and FLAC streams, don't state
+        // their length up front. Walking the packets finds it without decoding any audio.
+        let mut end_ts = 0;
+        while let Ok(packet) = format.next_packet() {
+            if packet.track_id() == track_id {
+                end_ts = end_ts.max(packet.ts() + packet.dur());
+            }
+        }
+        if end_ts == 0 {
+            return None;
+        }
+        match params.time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(end_ts);
+                Some(time.seconds as f64 + time.frac)
+            }
+            None => Some(end_ts as f64 / params.sample_rate? as f64),
+        }
+    }
+
+    fn prepare_stream_data(
+        path: &Path,
+    ) -> Result<(StreamingSoundData<FromFileError>, TimeStretchControl), StreamingAudioError> {
+        let control = TimeStretchControl::default();
+        let (mut decoder, n_frames) =
+            TimeStretchDecoder::open(path, control.clone()).map_err(|source| {
+                StreamingAudioError::Load {
+                    path: path.to_path_buf(),
+                    source,
+                }
+            })?;
+
+        let sample_rate = decoder.sample_rate() as f64;
+        let decoder_duration = n_frames.map(|frames| frames as f64 / sample_rate);
+        let duration_secs = match decoder_duration.filter(|secs| secs.is_finite() && *secs >= 1.0) {
+            Some(secs) => {
+                info!("Streaming duration (decoder reported): {:.3}s", secs);
+                secs
+            }
+            None => match Self::estimate_duration_secs(path) {
+                Some(secs) if secs.is_finite() && secs > 0.0 => {
+                    info!(
+                        "Estimated duration via metadata for {}: {:.3}s",
+                        path.display(),
+                        secs
+                    );
+                    secs
+                }
+                _ => {
+                    warn!(
+                        "Falling back to default streaming duration of {:.0}s for {}",
+                        Self::FALLBACK_DURATION_SECS,
+                        path.display()
+                    );
+                    Self::FALLBACK_DURATION_SECS
+                }
+            },
+        };
+        decoder.set_duration(duration_secs);
+
+        Ok((StreamingSoundData::from_decoder(decoder), control))
+    }
+
+    pub fn drain_backend_errors(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        while let Some(err) = self.manager.backend_mut().pop_error() {
+            error!("Audio backend error: {err}");
+        }
+    }
+
+    pub fn play_from_path(&mut self, path: &Path) -> Result<SongStream, StreamingAudioError> {
+        let (data, stretch) = Self::prepare_stream_data(path)?;
+        let handle = self.manager.play(data)?;
+        Ok(SongStream {
+            handle,
+            stretch,
+            resample_rate: 1.0,
+        })
+    }
+}
+
+// Positions and seeks are in song seconds, the handle itself only knows the stretched output.
+pub struct SongStream {
+    pub handle: StreamingSoundHandle<FromFileError>,
+    stretch: TimeStretchControl,
+    resample_rate: f32,
+}
+
+impl SongStream {
+    pub fn position(&self) -> f64 {
+        self.stretch.song_time(self.handle.position())
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.stretch.rate() * self.resample_rate
+    }
+
+    // Without pitch preservation kira resamples the output and the stretcher passes it through.
+    pub fn set_rate(&mut self, rate: f32, preserve_pitch: bool) {
+        let (stretch_rate, resample_rate) = if preserve_pitch {
+            (rate, 1.0)
+        } else {
+            (1.0, rate)
+        };
+        self.stretch.set_rate(stretch_rate);
+        if resample_rate != self.resample_rate {
+            self.handle
+                .set_playback_rate(resample_rate as f64, Tween::default());
+            self.resample_rate = resample_rate;
+        }
+    }
+
+    pub fn seek_to(&mut self, song_seconds: f64) {
+        let output = self
+            .stretch
+            .request_seek(song_seconds, self.handle.position());
+        self.handle.seek_to(output);
+    }
+
+    pub fn has_finished(&self) -> bool {
+        if self.handle.state() == PlaybackState::Stopped {
+            return true;
+        }
+        let duration = self.stretch.duration();
+        duration > 0.0 && self.position() >= duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DURATION_TOLERANCE: f64 = 0.001;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/audio")
+            .join(name)
+    }
+
+    fn declared_frames(path: &Path) -> Option<u64> {
+        use symphonia::core::formats::FormatOptions;
+        use symphonia::core::io::MediaSourceStream;
+        use symphonia::core::meta::MetadataOptions;
+        use symphonia::core::probe::Hint;
+
+        let mss = MediaSourceStream::new(
+            Box::new(std::fs::File::open(path).unwrap()),
+            Default::default(),
+        );
+        let mut hint = Hint::new();
+        hint.with_extension(path.extension().unwrap().to_str().unwrap());
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .unwrap();
+        probed.format.default_track().unwrap().codec_params.n_frames
+    }
+
+    #[test]
+    fn durations_are_estimated_for_every_fixture_format() {
+        // (file, seconds, whether the container states its length)
+        let cases = [
+            ("short.wav", 0.75, true),
+            ("declared.flac", 2.5, true),
+            ("undeclared.flac", 2.5, false),
+            // Twelve 1152 sample frames at 44.1 kHz, without a Xing header.
+            ("silence.mp3", 12.0 * 1152.0 / 44_100.0, false),
+        ];
+        for (name, expected, declared) in cases {
+            let path = fixture(name);
+            let extension = path.extension().unwrap().to_str().unwrap();
+            assert!(AUDIO_EXTENSIONS.contains(&extension), "{name}");
+            assert_eq!(declared_frames(&path).is_some(), declared, "{name}");
+
+            let secs = StreamingAudio::estimate_duration_secs(&path).unwrap();
+            assert!(
+                (secs - expected).abs() < DURATION_TOLERANCE,
+                "{name}: {secs} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn unreadable_audio_has_no_duration() {
+        assert_eq!(
+            StreamingAudio::estimate_duration_secs(&fixture("missing.flac")),
+            None
+        );
+    }
+}
@@ -0,0 +1,635 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use kira::sound::streaming::Decoder;
+use kira::sound::FromFileError;
+use kira::Frame;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder as CodecDecoder, DecoderOptions};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::file::package::{open_song_file, SongFile};
+
+pub const MIN_STRETCH_RATE: f32 = 0.25;
+pub const MAX_STRETCH_RATE: f32 = 2.0;
+
+// kira only knows the decoder by its output frames, and the rate can change at any point,
+// so the stream is given room to run for as long as a session could possibly last.
+const OUTPUT_FRAME_LIMIT: usize = u32::MAX as usize;
+// Matches kira's streaming ring buffer; frames already queued still play after a seek.
+const KIRA_STREAM_BUFFER_FRAMES: f64 = 16_384.0;
+const MAX_ANCHORS: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+struct StretchAnchor {
+    output: f64,
+    song: f64,
+    rate: f64,
+}
+
+#[derive(Debug)]
+struct StretchShared {
+    sample_rate: u32,
+    duration: f64,
+    rate: f32,
+    pending_seek: Option<f64>,
+    anchors: Vec<StretchAnchor>,
+}
+
+// The decoder records which song time each span of output came from, so kira's output
+// position can be mapped back to the song.
+#[derive(Clone, Debug)]
+pub struct TimeStretchControl {
+    shared: Arc<Mutex<StretchShared>>,
+}
+
+impl Default for TimeStretchControl {
+    fn default() -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(StretchShared {
+                sample_rate: 0,
+                duration: 0.0,
+                rate: 1.0,
+                pending_seek: None,
+                anchors: vec![StretchAnchor {
+                    output: 0.0,
+                    song: 0.0,
+                    rate: 1.0,
+                }],
+            })),
+        }
+    }
+}
+
+impl TimeStretchControl {
+    fn lock(&self) -> MutexGuard<'_, StretchShared> {
+        self.shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.lock().rate
+    }
+
+    pub fn set_rate(&self, rate: f32) {
+        self.lock().rate = rate.clamp(MIN_STRETCH_RATE, MAX_STRETCH_RATE);
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.lock().duration
+    }
+
+    pub fn song_time(&self, output_seconds: f64) -> f64 {
+        let shared = self.lock();
+        let anchor = shared
+            .anchors
+            .iter()
+            .rev()
+            .find(|anchor| anchor.output <= output_seconds)
+            .or(shared.anchors.first())
+            .copied();
+        match anchor {
+            Some(anchor) => anchor.song + (output_seconds - anchor.output) * anchor.rate,
+            None => output_seconds,
+        }
+    }
+
+    // The output position to seek to lies past anything buffered, so old and new output never
+    // share positions.
+    pub fn request_seek(&self, song_seconds: f64, output_seconds: f64) -> f64 {
+        let mut shared = self.lock();
+        shared.pending_seek = Some(song_seconds.max(0.0));
+        let buffered = if shared.sample_rate > 0 {
+            KIRA_STREAM_BUFFER_FRAMES / shared.sample_rate as f64
+        } else {
+            1.0
+        };
+        let latest = shared
+            .anchors
+            .last()
+            .map(|anchor| anchor.output)
+            .unwrap_or(0.0);
+        output_seconds.max(latest) + buffered + 0.1
+    }
+
+    fn take_pending_seek(&self) -> Option<f64> {
+        self.lock().pending_seek.take()
+    }
+
+    fn push_anchor(&self, anchor: StretchAnchor) {
+        let mut shared = self.lock();
+        shared
+            .anchors
+            .retain(|existing| existing.output < anchor.output);
+        shared.anchors.push(anchor);
+        if shared.anchors.len() > MAX_ANCHORS {
+            shared.anchors.remove(0);
+        }
+    }
+}
+
+// WSOLA: Hann windowed segments overlap by half at a fixed output hop while the input advances
+// by `hop * rate`. Each segment may shift by up to a quarter segment to line up with the
+// previous one's waveform, which keeps the pitch without phase smearing.
+pub struct Wsola {
+    segment_len: usize,
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    input: Vec<Frame>,
+    input_start: usize,
+    overlap: Vec<Frame>,
+    position: f64,
+    previous: Option<usize>,
+}
+
+impl Wsola {
+    pub fn new(sample_rate: u32) -> Self {
+        // Roughly 40ms segments, large enough to hold a couple of periods of a low E.
+        let segment_len = (sample_rate as usize / 25).next_power_of_two().max(64);
+        let window = (0..segment_len)
+            .map(|n| {
+                let phase = std::f32::consts::TAU * n as f32 / segment_len as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+        Self {
+            segment_len,
+            hop: segment_len / 2,
+            tolerance: segment_len / 4,
+            window,
+            input: Vec::new(),
+            input_start: 0,
+            overlap: vec![Frame::ZERO; segment_len],
+            position: 0.0,
+            previous: None,
+        }
+    }
+
+    pub fn reset(&mut self, position: usize) {
+        self.input.clear();
+        self.input_start = position;
+        self.overlap.fill(Frame::ZERO);
+        self.position = position as f64;
+        self.previous = None;
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    pub fn input_end(&self) -> usize {
+        self.input_start + self.input.len()
+    }
+
+    fn required_input_end(&self) -> usize {
+        let nominal = self.position.round() as usize;
+        let search_end = nominal + self.tolerance + self.segment_len;
+        match self.previous {
+            Some(previous) => search_end.max(previous + self.hop + self.segment_len),
+            None => search_end,
+        }
+    }
+
+    pub fn input_needed(&self) -> usize {
+        self.required_input_end().saturating_sub(self.input_end())
+    }
+
+    pub fn push_input(&mut self, frames: &[Frame]) {
+        self.input.extend_from_slice(frames);
+    }
+
+    pub fn process(&mut self, rate: f32, output: &mut Vec<Frame>) -> bool {
+        if self.input_needed() > 0 {
+            return false;
+        }
+
+        let nominal = self.position.round() as usize;
+        let start = match self.previous {
+            Some(previous) if (rate - 1.0).abs() > f32::EPSILON => {
+                self.best_segment_start(nominal, previous + self.hop)
+            }
+            Some(previous) => previous + self.hop,
+            None => nominal.max(self.input_start),
+        };
+
+        let offset = start - self.input_start;
+        for (n, (sum, weight)) in self.overlap.iter_mut().zip(&self.window).enumerate() {
+            *sum += self.input[offset + n] * *weight;
+        }
+        output.extend_from_slice(&self.overlap[..self.hop]);
+        self.overlap.copy_within(self.hop.., 0);
+        let tail = self.segment_len - self.hop;
+        self.overlap[tail..].fill(Frame::ZERO);
+
+        self.previous = Some(start);
+        self.position += self.hop as f64 * rate as f64;
+        self.discard_consumed_input();
+        true
+    }
+
+    fn mono(&self, index: usize) -> f32 {
+        let frame = self.input[index - self.input_start];
+        (frame.left + frame.right) * 0.5
+    }
+
+    fn similarity(&self, candidate: usize, natural: usize, stride: usize) -> f32 {
+        let mut correlation = 0.0;
+        let mut energy = 0.0;
+        for n in (0..self.hop).step_by(stride) {
+            let sample = self.mono(candidate + n);
+            correlation += sample * self.mono(natural + n);
+            energy += sample * sample;
+        }
+        if energy <= f32::EPSILON {
+            return 0.0;
+        }
+        correlation / energy.sqrt()
+    }
+
+    fn best_segment_start(&self, nominal: usize, natural: usize) -> usize {
+        const COARSE_STRIDE: usize = 4;
+
+        let low = nominal.saturating_sub(self.tolerance).max(self.input_start);
+        let high = nominal + self.tolerance;
+        let mut best = (nominal.max(low), f32::MIN);
+
+        // Coarse pass over the whole tolerance window, then refine around the winner.
+        for candidate in (low..=high).step_by(COARSE_STRIDE) {
+            let score = self.similarity(candidate, natural, COARSE_STRIDE);
+            if score > best.1 {
+                best = (candidate, score);
+            }
+        }
+        let refine_low = best.0.saturating_sub(COARSE_STRIDE - 1).max(low);
+        let refine_high = (best.0 + COARSE_STRIDE - 1).min(high);
+        let mut refined = (best.0, f32::MIN);
+        for candidate in refine_low..=refine_high {
+            let score = self.similarity(candidate, natural, 1);
+            if score > refined.1 {
+                refined = (candidate, score);
+            }
+        }
+        refined.0
+    }
+
+    fn discard_consumed_input(&mut self) {
+        let nominal = self.position.round() as usize;
+        let mut keep_from = nominal.saturating_sub(self.tolerance);
+        if let Some(previous) = self.previous {
+            keep_from = keep_from.min(previous + self.hop);
+        }
+        let drop = keep_from
+            .saturating_sub(self.input_start)
+            .min(self.input.len());
+        self.input.drain(..drop);
+        self.input_start += drop;
+    }
+}
+
+impl MediaSource for SongFile {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        SongFile::byte_len(self)
+    }
+}
+
+pub struct TimeStretchDecoder {
+    format_reader: Box<dyn FormatReader>,
+    decoder: Box<dyn CodecDecoder>,
+    track_id: u32,
+    sample_rate: u32,
+    control: TimeStretchControl,
+    wsola: Wsola,
+    rate: f32,
+    output_index: usize,
+    source: Vec<Frame>,
+    skip_frames: usize,
+    end_of_stream: bool,
+}
+
+impl TimeStretchDecoder {
+    // The frame count is None when the container doesn't declare it.
+    pub fn open(
+        path: &Path,
+        control: TimeStretchControl,
+    ) -> Result<(Self, Option<u64>), FromFileError> {
+        let file = open_song_file(path)?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(ext);
+        }
+        let format_reader = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )?
+            .format;
+        let track = format_reader
+            .default_track()
+            .ok_or(FromFileError::NoDefaultTrack)?;
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or(FromFileError::UnknownSampleRate)?;
+        let n_frames = track.codec_params.n_frames;
+        let track_id = track.id;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        {
+            let mut shared = control.lock();
+            shared.sample_rate = sample_rate;
+        }
+        let rate = control.rate();
+        Ok((
+            Self {
+                format_reader,
+                decoder,
+                track_id,
+                sample_rate,
+                control,
+                wsola: Wsola::new(sample_rate),
+                rate,
+                output_index: 0,
+                source: Vec::new(),
+                skip_frames: 0,
+                end_of_stream: false,
+            },
+            n_frames,
+        ))
+    }
+
+    pub fn set_duration(&mut self, seconds: f64) {
+        self.control.lock().duration = seconds;
+    }
+
+    fn seconds(&self, frames: f64) -> f64 {
+        frames / self.sample_rate as f64
+    }
+
+    fn record_anchor(&self) {
+        self.control.push_anchor(StretchAnchor {
+            output: self.seconds(self.output_index as f64),
+            song: self.seconds(self.wsola.position()),
+            rate: self.rate as f64,
+        });
+    }
+
+    fn restart(&mut self, song_frame: usize) -> Result<(), FromFileError> {
+        self.source.clear();
+        self.skip_frames = 0;
+        self.end_of_stream = false;
+        match self.format_reader.seek(
+            SeekMode::Accurate,
+            SeekTo::TimeStamp {
+                ts: song_frame as u64,
+                track_id: self.track_id,
+            },
+        ) {
+            Ok(seeked) => {
+                self.skip_frames = (song_frame as u64).saturating_sub(seeked.actual_ts) as usize;
+            }
+            // Seeking past the end just leaves silence to stretch.
+            Err(SymphoniaError::SeekError(_) | SymphoniaError::IoError(_)) => {
+                self.end_of_stream = true;
+            }
+            Err(err) => return Err(err.into()),
+        }
+        self.decoder.reset();
+        self.rate = self.control.rate();
+        self.wsola.reset(song_frame);
+        self.record_anchor();
+        Ok(())
+    }
+
+    fn decode_packet(&mut self) -> Result<(), FromFileError> {
+        let packet = loop {
+            match self.format_reader.next_packet() {
+                Ok(packet) if packet.track_id() == self.track_id => break packet,
+                Ok(_) => continue,
+                Err(SymphoniaError::IoError(_) | SymphoniaError::ResetRequired) => {
+                    self.end_of_stream = true;
+                    return Ok(());
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        let decoded = match self.decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet is skipped rather than ending playback.
+            Err(SymphoniaError::DecodeError(_)) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let channels = decoded.spec().channels.count();
+        if channels == 0 {
+            return Err(FromFileError::UnsupportedChannelConfiguration);
+        }
+        let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+        samples.copy_interleaved_ref(decoded);
+        let frames = samples.samples().chunks_exact(channels).map(|frame| {
+            if channels == 1 {
+                Frame::from_mono(frame[0])
+            } else {
+                Frame::new(frame[0], frame[1])
+            }
+        });
+        let skip = self.skip_frames.min(samples.samples().len() / channels);
+        self.skip_frames -= skip;
+        self.source.extend(frames.skip(skip));
+        Ok(())
+    }
+
+    fn read_source(&mut self, count: usize) -> Result<Vec<Frame>, FromFileError> {
+        while self.source.len() < count && !self.end_of_stream {
+            self.decode_packet()?;
+        }
+        let available = count.min(self.source.len());
+        let mut frames: Vec<Frame> = self.source.drain(..available).collect();
+        frames.resize(count, Frame::ZERO);
+        Ok(frames)
+    }
+}
+
+impl Decoder for TimeStretchDecoder {
+    type Error = FromFileError;
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn num_frames(&self) -> usize {
+        OUTPUT_FRAME_LIMIT
+    }
+
+    fn decode(&mut self) -> Result<Vec<Frame>, Self::Error> {
+        let rate = self.control.rate();
+        if (rate - self.rate).abs() > f32::EPSILON {
+            self.rate = rate;
+            self.record_anchor();
+        }
+
+        let needed = self.wsola.input_needed();
+        if needed > 0 {
+            let frames = self.read_source(needed)?;
+            self.wsola.push_input(&frames);
+        }
+        let mut output = Vec::with_capacity(self.wsola.hop());
+        self.wsola.process(self.rate, &mut output);
+        self.output_index += output.len();
+        Ok(output)
+    }
+
+    fn seek(&mut self, index: usize) -> Result<usize, Self::Error> {
+        let song_seconds = match self.control.take_pending_seek() {
+            Some(seconds) => seconds,
+            None => self.control.song_time(self.seconds(index as f64)),
+        };
+        self.output_index = index;
+        let song_frame = (song_seconds.max(0.0) * self.sample_rate as f64).round() as usize;
+        self.restart(song_frame)?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::PitchDetector;
+
+    const SAMPLE_RATE: u32 = 44_100;
+    const TONE_HZ: f32 = 220.0;
+
+    fn sine(seconds: f32) -> Vec<Frame> {
+        let len = (SAMPLE_RATE as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                Frame::from_mono(0.5 * (std::f32::consts::TAU * TONE_HZ * t).sin())
+            })
+            .collect()
+    }
+
+    fn stretch(input: &[Frame], rate: f32) -> Vec<Frame> {
+        let mut wsola = Wsola::new(SAMPLE_RATE);
+        wsola.push_input(input);
+        let mut output = Vec::new();
+        while wsola.process(rate, &mut output) {}
+        output
+    }
+
+    fn dominant_frequency(output: &[Frame]) -> f32 {
+        let mut detector = PitchDetector::new(SAMPLE_RATE, 4096);
+        let middle = output.len() / 2;
+        let samples: Vec<f32> = output[middle..middle + 4096]
+            .iter()
+            .map(|frame| frame.left)
+            .collect();
+        detector
+            .detect_monophonic(&samples)
+            .expect("stretched tone has a pitch")
+            .frequency
+    }
+
+    fn write_wav(path: &Path, frames: &[Frame]) {
+        let data_len = frames.len() as u32 * 2;
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for frame in frames {
+            let sample = (frame.left * i16::MAX as f32) as i16;
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn slowing_down_stretches_length_and_keeps_pitch() {
+        let input = sine(1.0);
+        for rate in [0.5, 0.75, 1.0] {
+            let output = stretch(&input, rate);
+            let expected = input.len() as f32 / rate;
+            // The last segment and search window of input wait for a hop that never comes.
+            let wsola = Wsola::new(SAMPLE_RATE);
+            let held_back = (wsola.segment_len + wsola.tolerance) as f32 / rate + wsola.hop as f32;
+            let error = (output.len() as f32 - expected).abs();
+            assert!(error <= held_back, "rate {rate}: {} frames", output.len());
+
+            let frequency = dominant_frequency(&output);
+            let cents = 1200.0 * (frequency / TONE_HZ).log2();
+            assert!(cents.abs() < 10.0, "rate {rate}: {frequency} Hz");
+        }
+    }
+
+    #[test]
+    fn reset_moves_the_song_position() {
+        let mut wsola = Wsola::new(SAMPLE_RATE);
+        wsola.reset(SAMPLE_RATE as usize);
+        assert_eq!(wsola.position(), SAMPLE_RATE as f64);
+        assert_eq!(wsola.input_end(), SAMPLE_RATE as usize);
+
+        let mut output = Vec::new();
+        wsola.push_input(&sine(0.5));
+        assert!(wsola.process(0.5, &mut output));
+        assert_eq!(output.len(), wsola.hop());
+        assert_eq!(
+            wsola.position(),
+            SAMPLE_RATE as f64 + wsola.hop() as f64 * 0.5
+        );
+    }
+
+    #[test]
+    fn decoder_plays_a_wav_at_reduced_speed() {
+        let path = std::env::temp_dir().join(format!("tabs-stretch-{}.wav", std::process::id()));
+        write_wav(&path, &sine(2.0));
+        let control = TimeStretchControl::default();
+        let (mut decoder, n_frames) = TimeStretchDecoder::open(&path, control.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(n_frames, Some(2 * SAMPLE_RATE as u64));
+
+        control.set_rate(0.75);
+        let mut output = Vec::new();
+        while output.len() < 4 * SAMPLE_RATE as usize {
+            output.extend(decoder.decode().unwrap());
+        }
+
+        // Past the end of the song the decoder keeps producing silence.
+        let audible = output
+            .iter()
+            .rposition(|frame| frame.left.abs() > 0.01)
+            .unwrap();
+        let seconds = audible as f32 / SAMPLE_RATE as f32;
+        assert!((seconds - 2.0 / 0.75).abs() < 0.05, "{seconds}s");
+        let cents = 1200.0 * (dominant_frequency(&output[..audible]) / TONE_HZ).log2();
+        assert!(cents.abs() < 10.0);
+
+        let song = control.song_time(seconds as f64);
+        assert!((song - 2.0).abs() < 0.05, "{song}s");
+    }
+}
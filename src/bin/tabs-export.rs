@@ -0,0 +1,118 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tabs_app::convert::{export_ascii_tab_file, export_music_xml_file, ConvertError, ImportedSong};
+use tabs_app::file::package::{pack_song_folder, PACKAGE_EXTENSION};
+
+const USAGE: &str = "\
+Usage: tabs-export <song-folder> <output> [options]
+
+Writes the string arrangements of a song folder to another format, picked from the
+output extension.
+
+Supported outputs: MusicXML (.musicxml, .xml), ASCII tab (.txt), song package
+(.tabspack, the whole folder in one file)
+
+Options:
+  --arrangement <name>   Only export this arrangement";
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    arrangement: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut arrangement = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--arrangement" => {
+                arrangement = Some(args.next().ok_or("--arrangement needs a value")?);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input, output] = <[PathBuf; 2]>::try_from(positional)
+        .map_err(|_| "expected a song folder and an output file".to_string())?;
+    Ok(Options {
+        input,
+        output,
+        arrangement,
+    })
+}
+
+fn export(song: &ImportedSong, output: &Path) -> Result<(), ConvertError> {
+    let extension = output
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "musicxml" | "xml" => export_music_xml_file(song, output),
+        "txt" => export_ascii_tab_file(song, output),
+        _ => Err(ConvertError::UnsupportedFormat(
+            output.display().to_string(),
+        )),
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let is_package = options
+        .output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(PACKAGE_EXTENSION));
+    if is_package {
+        if options.arrangement.is_some() {
+            eprintln!("error: --arrangement cannot be used with a song package");
+            return ExitCode::from(2);
+        }
+        if let Err(err) = pack_song_folder(&options.input, &options.output) {
+            eprintln!("error: failed to pack {}: {err}", options.input.display());
+            return ExitCode::FAILURE;
+        }
+        println!("wrote {}", options.output.display());
+        return ExitCode::SUCCESS;
+    }
+
+    let mut song = match ImportedSong::read_from_folder(&options.input) {
+        Ok(song) => song,
+        Err(err) => {
+            eprintln!("error: failed to read {}: {err}", options.input.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(name) = &options.arrangement {
+        song.arrangements
+            .retain(|arrangement| arrangement.key.eq_ignore_ascii_case(name));
+        if song.arrangements.is_empty() {
+            eprintln!("error: no arrangement named '{name}'");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Err(err) = export(&song, &options.output) {
+        eprintln!(
+            "error: failed to export {}: {err}",
+            options.output.display()
+        );
+        return ExitCode::FAILURE;
+    }
+    println!("wrote {}", options.output.display());
+    ExitCode::SUCCESS
+}
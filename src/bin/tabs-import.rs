@@ -0,0 +1,275 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tabs_app::audio::AUDIO_EXTENSIONS;
+use tabs_app::convert::{
+    import_ascii_tab_file, import_file, import_midi_file, AsciiTabOptions, MidiImportOptions,
+};
+use tabs_app::file::song::{SongArrangementMetadata, TabsInstrument};
+
+const USAGE: &str = "\
+Usage: tabs-import <input> <song-folder> [options]
+
+Converts a tab file into a song folder the game can load.
+
+Supported inputs: Guitar Pro (.gp3, .gp4, .gp5), MusicXML (.musicxml, .xml),
+MIDI (.mid, .midi), Rocksmith arrangement or vocals XML (.xml), a folder of
+Rocksmith XML files to import together, or ASCII tab (.txt)
+
+Options:
+  --audio <file>    Copy the song audio into the folder as song.<extension>
+                    (wav, ogg, flac or mp3)
+  --title <text>    Override the song title
+  --artist <text>   Override the artist
+  --album <text>    Override the album
+  --year <number>   Set the release year
+
+MIDI options:
+  --track <number>          Import only this track, counting from 0
+  --instrument <name>       Finger the notes for guitar or bass
+  --strings <number>        Number of strings
+  --tuning <offsets>        Semitone offset of each string from standard, lowest
+                            first, e.g. -2,0,0,0,0,0 for drop D
+  --capo <fret>             Capo fret
+
+ASCII tab options:
+  --bpm <number>            Tempo, overriding any BPM line in the file
+  --time <beats/unit>       Time signature of every bar, e.g. 3/4";
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    audio: Option<PathBuf>,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    year: Option<i32>,
+    track: Option<usize>,
+    instrument: Option<TabsInstrument>,
+    strings: Option<i32>,
+    tuning: Option<Vec<i32>>,
+    capo: Option<i32>,
+    bpm: Option<f64>,
+    time_signature: Option<(u32, u32)>,
+}
+
+impl Options {
+    // Only set when a fretting option was given, so the importer can pick per track.
+    fn arrangement(&self) -> Option<SongArrangementMetadata> {
+        if self.instrument.is_none()
+            && self.strings.is_none()
+            && self.tuning.is_none()
+            && self.capo.is_none()
+        {
+            return None;
+        }
+        let instrument = self.instrument.unwrap_or(TabsInstrument::Guitar);
+        let string_count = self
+            .strings
+            .or(self.tuning.as_ref().map(|tuning| tuning.len() as i32));
+        Some(SongArrangementMetadata {
+            name: String::new(),
+            file: String::new(),
+            capo_fret: self.capo,
+            instrument,
+            string_count,
+            string_semitone_offset: self.tuning.clone(),
+            techniques: Vec::new(),
+        })
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {name} '{value}'"))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        input: PathBuf::new(),
+        output: PathBuf::new(),
+        audio: None,
+        title: None,
+        artist: None,
+        album: None,
+        year: None,
+        track: None,
+        instrument: None,
+        strings: None,
+        tuning: None,
+        capo: None,
+        bpm: None,
+        time_signature: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--audio" => options.audio = Some(PathBuf::from(value("--audio")?)),
+            "--title" => options.title = Some(value("--title")?),
+            "--artist" => options.artist = Some(value("--artist")?),
+            "--album" => options.album = Some(value("--album")?),
+            "--year" => options.year = Some(parse_number("year", &value("--year")?)?),
+            "--track" => options.track = Some(parse_number("track", &value("--track")?)?),
+            "--strings" => {
+                options.strings = Some(parse_number("string count", &value("--strings")?)?)
+            }
+            "--capo" => options.capo = Some(parse_number("capo fret", &value("--capo")?)?),
+            "--instrument" => {
+                options.instrument = Some(match value("--instrument")?.to_lowercase().as_str() {
+                    "guitar" => TabsInstrument::Guitar,
+                    "bass" => TabsInstrument::Bass,
+                    other => return Err(format!("unknown instrument '{other}'")),
+                })
+            }
+            "--tuning" => {
+                let tuning = value("--tuning")?;
+                options.tuning = Some(
+                    tuning
+                        .split(',')
+                        .map(|offset| parse_number("tuning offset", offset.trim()))
+                        .collect::<Result<_, _>>()?,
+                );
+            }
+            "--bpm" => options.bpm = Some(parse_number("tempo", &value("--bpm")?)?),
+            "--time" => {
+                let time = value("--time")?;
+                let (beats, unit) = time
+                    .split_once('/')
+                    .ok_or(format!("invalid time signature '{time}'"))?;
+                options.time_signature = Some((
+                    parse_number("time signature", beats.trim())?,
+                    parse_number("time signature", unit.trim())?,
+                ));
+            }
+            "-h" | "--help" => return Err(String::new()),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input, output] = <[PathBuf; 2]>::try_from(positional)
+        .map_err(|_| "expected an input file and a song folder".to_string())?;
+    options.input = input;
+    options.output = output;
+    Ok(options)
+}
+
+// The audio keeps its format, so the file it is copied to keeps its extension.
+fn audio_file_name(audio: &Path) -> Result<String, String> {
+    let extension = audio
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "{} is not a supported audio format ({})",
+            audio.display(),
+            AUDIO_EXTENSIONS.join(", ")
+        ));
+    }
+    Ok(format!("song.{extension}"))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let extension = options
+        .input
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let imported = match extension.as_str() {
+        "mid" | "midi" => {
+            let midi = MidiImportOptions {
+                track: options.track,
+                arrangement: options.arrangement(),
+            };
+            import_midi_file(&options.input, &midi)
+        }
+        "txt" => {
+            let ascii = AsciiTabOptions {
+                bpm: options.bpm,
+                time_signature: options.time_signature,
+            };
+            import_ascii_tab_file(&options.input, &ascii)
+        }
+        _ => import_file(&options.input),
+    };
+    let mut song = match imported {
+        Ok(song) => song,
+        Err(err) => {
+            eprintln!("error: failed to import {}: {err}", options.input.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let metadata = &mut song.metadata;
+    if let Some(title) = options.title {
+        metadata.title = title;
+    }
+    if metadata.title.is_empty() {
+        metadata.title = options
+            .input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+    }
+    if let Some(artist) = options.artist {
+        metadata.artist = artist;
+    }
+    if let Some(album) = options.album {
+        metadata.album = album;
+    }
+    if let Some(year) = options.year {
+        metadata.year = year;
+    }
+    let audio_target = match options.audio.as_deref().map(audio_file_name).transpose() {
+        Ok(file) => file,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(file) = &audio_target {
+        metadata.audio = file.clone();
+    }
+
+    let written = match song.write_to_folder(&options.output) {
+        Ok(written) => written,
+        Err(err) => {
+            eprintln!("error: failed to write {}: {err}", options.output.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    for path in &written {
+        println!("wrote {}", path.display());
+    }
+
+    if let (Some(audio), Some(file)) = (&options.audio, &audio_target) {
+        let target = options.output.join(file);
+        if let Err(err) = fs::copy(audio, &target) {
+            eprintln!("error: failed to copy {}: {err}", audio.display());
+            return ExitCode::FAILURE;
+        }
+        println!("wrote {}", target.display());
+    }
+
+    for arrangement in &song.arrangements {
+        println!("arrangement '{}'", arrangement.key);
+    }
+    ExitCode::SUCCESS
+}
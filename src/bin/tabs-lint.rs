@@ -0,0 +1,164 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use tabs_app::file::library::{LibraryRoot, LibraryRoots};
+use tabs_app::file::lint::{lint_song_folder, LintSeverity, SongLintReport};
+use tabs_app::file::{AppConfig, Song};
+
+const USAGE: &str = "\
+Usage: tabs-lint [song-directory] [options]
+
+Checks every song folder under the song directory for missing files and broken
+charts. A relative song directory is inside assets/. Without one, the song
+directory and library roots in tabs.cfg are checked.
+
+Exits with an error status when any song has errors; warnings alone pass.
+
+Options:
+  --json   Print the report as JSON";
+
+const CONFIG_FILE: &str = "tabs.cfg";
+
+struct Options {
+    song_directory: Option<String>,
+    json: bool,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut json = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => return Err(String::new()),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() > 1 {
+        return Err("expected at most one song directory".to_string());
+    }
+    Ok(Options {
+        song_directory: positional.pop(),
+        json,
+    })
+}
+
+fn configured_roots() -> Result<Vec<LibraryRoot>, String> {
+    let content = fs::read_to_string(CONFIG_FILE)
+        .map_err(|err| format!("failed to read {CONFIG_FILE}: {err}"))?;
+    let config: AppConfig = serde_yaml::from_str(&content)
+        .map_err(|err| format!("failed to parse {CONFIG_FILE}: {err}"))?;
+    Ok(LibraryRoots::from_config(&config).iter().cloned().collect())
+}
+
+fn argument_root(song_directory: &str) -> Result<Vec<LibraryRoot>, String> {
+    LibraryRoot::from_setting(song_directory, "lint")
+        .map(|root| vec![root])
+        .ok_or_else(|| format!("no user data directory for {song_directory}"))
+}
+
+fn print_human(reports: &[SongLintReport]) {
+    for report in reports.iter().filter(|report| !report.issues.is_empty()) {
+        println!("{}", report.folder.display());
+        for issue in &report.issues {
+            let severity = match issue.severity {
+                LintSeverity::Error => "error",
+                LintSeverity::Warning => "warning",
+            };
+            match &issue.arrangement {
+                Some(arrangement) => println!("  {severity} [{arrangement}]: {}", issue.message),
+                None => println!("  {severity}: {}", issue.message),
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let roots = match options.song_directory.as_deref() {
+        Some(song_directory) => argument_root(song_directory),
+        None => configured_roots(),
+    };
+    let roots = match roots {
+        Ok(roots) => roots,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (roots, missing): (Vec<_>, Vec<_>) = roots
+        .into_iter()
+        .partition(|root| root.song_folder().is_dir());
+    for root in &missing {
+        eprintln!(
+            "warning: {} is not a directory",
+            root.song_folder().display()
+        );
+    }
+    if roots.is_empty() {
+        eprintln!("error: no song directory to check");
+        return ExitCode::FAILURE;
+    }
+
+    let mut folders: Vec<PathBuf> = roots
+        .iter()
+        .flat_map(|root| {
+            Song::get_all_songs(root)
+                .into_iter()
+                .filter_map(|metadata| Some(root.directory.join(metadata.parent()?)))
+        })
+        .collect();
+    folders.sort();
+    let reports: Vec<SongLintReport> = folders
+        .iter()
+        .map(|folder| lint_song_folder(folder))
+        .collect();
+
+    if options.json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("error: failed to write JSON: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        print_human(&reports);
+    }
+
+    let errors: usize = reports
+        .iter()
+        .map(|report| report.count(LintSeverity::Error))
+        .sum();
+    let warnings: usize = reports
+        .iter()
+        .map(|report| report.count(LintSeverity::Warning))
+        .sum();
+    if !options.json {
+        println!(
+            "{} song(s) checked: {errors} error(s), {warnings} warning(s)",
+            reports.len()
+        );
+    }
+
+    if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
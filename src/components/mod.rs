@@ -1,6 +1,14 @@
+pub mod section_navigator;
 pub mod string_timeline;
+pub mod vocal_timeline;
 
+pub use section_navigator::SectionNavigatorPlugin;
 pub use string_timeline::{
-    clamp_block_duration, default_block_duration, timeline_block_duration, timeline_window_seconds,
-    visible_block_count, StringTimelineFeed, StringTimelinePlugin, TimelineNote,
+    clamp_block_duration, default_block_duration, timeline_block_duration,
+    timeline_content_inset_px, timeline_window_seconds, visible_block_count, SectionTrend,
+    StringTimelineFeed, StringTimelinePlugin, TimelineChord, TimelineNote, TimelineSectionStatus,
+};
+pub use vocal_timeline::{
+    current_line_index, lyric_lines, LyricDisplay, LyricLine, LyricSyllable, VocalTimelineFeed,
+    VocalTimelinePlugin,
 };
@@ -0,0 +1,270 @@
+use bevy::prelude::*;
+use bevy::text::TextLayout;
+use bevy::ui::UiTargetCamera;
+
+use crate::components::string_timeline::StringTimelineFeed;
+use crate::scenes::gameplay::SeekRequest;
+use crate::scenes::MainCamera;
+use crate::states::{AppState, GameState};
+
+pub const SECTION_NAVIGATOR_HEIGHT_PX: f32 = 24.0;
+const SECTION_FONT_SIZE: f32 = 12.0;
+const SECTION_GAP_PX: f32 = 1.0;
+const CURSOR_WIDTH_PX: f32 = 2.0;
+
+const STRIP_COLOR: Color = Color::srgba(0.03, 0.03, 0.05, 0.95);
+const SECTION_COLOR: Color = Color::srgba(0.16, 0.16, 0.22, 1.0);
+const CURRENT_SECTION_COLOR: Color = Color::srgba(0.24, 0.4, 0.75, 1.0);
+const PLAYED_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.12);
+const SECTION_TEXT_COLOR: Color = Color::srgb(0.85, 0.85, 0.9);
+const CURSOR_COLOR: Color = Color::srgb_u8(255, 90, 90);
+
+pub struct SectionNavigatorPlugin;
+
+impl Plugin for SectionNavigatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SectionNavigatorView>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                setup_section_navigator,
+            )
+            .add_systems(OnEnter(GameState::Loading), teardown_section_navigator)
+            .add_systems(OnExit(AppState::Gameplay), teardown_section_navigator)
+            .add_systems(
+                Update,
+                update_section_navigator.run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct SectionNavigatorView {
+    root: Option<Entity>,
+    played: Option<Entity>,
+    cursor: Option<Entity>,
+    segments: Vec<SectionSegment>,
+    built_for: Option<(u32, u32)>,
+    current: Option<usize>,
+}
+
+struct SectionSegment {
+    entity: Entity,
+    start_time: f32,
+    end_time: f32,
+}
+
+#[derive(Component)]
+struct SectionNavigatorRoot;
+
+fn setup_section_navigator(
+    mut commands: Commands,
+    mut view: ResMut<SectionNavigatorView>,
+    main_camera: Res<MainCamera>,
+) {
+    *view = SectionNavigatorView::default();
+
+    let root = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(SECTION_NAVIGATOR_HEIGHT_PX),
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(STRIP_COLOR),
+            UiTargetCamera(main_camera.ui_camera),
+            Visibility::Hidden,
+            ZIndex(1),
+            SectionNavigatorRoot,
+        ))
+        .id();
+
+    let played = commands
+        .spawn((
+            Node {
+                width: Val::Percent(0.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                ..default()
+            },
+            BackgroundColor(PLAYED_COLOR),
+            Pickable::IGNORE,
+            ZIndex(1),
+        ))
+        .id();
+
+    let cursor = commands
+        .spawn((
+            Node {
+                width: Val::Px(CURSOR_WIDTH_PX),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Px(0.0),
+                margin: UiRect::left(Val::Px(-CURSOR_WIDTH_PX / 2.0)),
+                ..default()
+            },
+            BackgroundColor(CURSOR_COLOR),
+            Pickable::IGNORE,
+            ZIndex(2),
+        ))
+        .id();
+
+    commands.entity(root).add_child(played).add_child(cursor);
+    view.root = Some(root);
+    view.played = Some(played);
+    view.cursor = Some(cursor);
+}
+
+fn teardown_section_navigator(mut commands: Commands, mut view: ResMut<SectionNavigatorView>) {
+    if let Some(root) = view.root.take() {
+        commands.entity(root).try_despawn();
+    }
+    *view = SectionNavigatorView::default();
+}
+
+fn update_section_navigator(
+    mut commands: Commands,
+    feed: Res<StringTimelineFeed>,
+    mut view: ResMut<SectionNavigatorView>,
+    mut visibility_query: Query<&mut Visibility, With<SectionNavigatorRoot>>,
+    mut node_query: Query<&mut Node>,
+    mut background_query: Query<&mut BackgroundColor>,
+) {
+    let Some(root) = view.root else {
+        return;
+    };
+
+    let shown = feed.navigator_height() > 0.0;
+    if let Ok(mut visibility) = visibility_query.get_mut(root) {
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    if !shown {
+        return;
+    }
+
+    let song_length = feed.song_length;
+    let built_for = Some((feed.sections_generation, song_length.to_bits()));
+    if view.built_for != built_for {
+        for segment in view.segments.drain(..) {
+            commands.entity(segment.entity).try_despawn();
+        }
+        view.segments = feed
+            .sections
+            .iter()
+            .map(|section| {
+                let entity = spawn_section_segment(
+                    &mut commands,
+                    &section.name,
+                    section.start_time,
+                    section.end_time,
+                    song_length,
+                );
+                commands.entity(root).add_child(entity);
+                SectionSegment {
+                    entity,
+                    start_time: section.start_time,
+                    end_time: section.end_time,
+                }
+            })
+            .collect();
+        view.built_for = built_for;
+        view.current = None;
+    }
+
+    let time = feed.current_time;
+    let current = view
+        .segments
+        .iter()
+        .position(|segment| time >= segment.start_time && time < segment.end_time);
+    if current != view.current {
+        for (index, segment) in view.segments.iter().enumerate() {
+            if let Ok(mut background) = background_query.get_mut(segment.entity) {
+                background.0 = if Some(index) == current {
+                    CURRENT_SECTION_COLOR
+                } else {
+                    SECTION_COLOR
+                };
+            }
+        }
+        view.current = current;
+    }
+
+    let progress = Val::Percent((time / song_length).clamp(0.0, 1.0) * 100.0);
+    if let Some(mut node) = view
+        .played
+        .and_then(|entity| node_query.get_mut(entity).ok())
+    {
+        if node.width != progress {
+            node.width = progress;
+        }
+    }
+    if let Some(mut node) = view
+        .cursor
+        .and_then(|entity| node_query.get_mut(entity).ok())
+    {
+        if node.left != progress {
+            node.left = progress;
+        }
+    }
+}
+
+fn spawn_section_segment(
+    commands: &mut Commands,
+    name: &str,
+    start_time: f32,
+    end_time: f32,
+    song_length: f32,
+) -> Entity {
+    let to_percent = |time: f32| (time / song_length).clamp(0.0, 1.0) * 100.0;
+    let left = to_percent(start_time);
+    let width = (to_percent(end_time) - left).max(0.0);
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(width),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(left),
+                top: Val::Px(0.0),
+                border: UiRect::right(Val::Px(SECTION_GAP_PX)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(SECTION_COLOR),
+            BorderColor::all(STRIP_COLOR),
+        ))
+        .with_children(|segment| {
+            segment.spawn((
+                Text::new(name),
+                TextFont {
+                    font_size: SECTION_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(SECTION_TEXT_COLOR),
+                TextLayout::new_with_no_wrap(),
+                Pickable::IGNORE,
+            ));
+        })
+        .observe(
+            move |_: On<Pointer<Click>>, mut seeks: MessageWriter<SeekRequest>| {
+                seeks.write(SeekRequest { time: start_time });
+            },
+        )
+        .id()
+}
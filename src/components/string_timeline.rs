@@ -2,11 +2,12 @@ use bevy::prelude::*;
 use bevy::ui::{ComputedNode, UiTargetCamera};
 use std::collections::{HashMap, HashSet};
 
+use crate::components::section_navigator::SECTION_NAVIGATOR_HEIGHT_PX;
 use crate::file::settings::Settings;
-use crate::file::song::Techniques;
+use crate::file::song::{TabSection, Techniques};
 use crate::file::theme::{fallback_instrument_key_palette, Themes};
 use crate::scenes::MainCamera;
-use crate::states::GameState;
+use crate::states::{AppState, GameState};
 
 const TIMELINE_WIDTH_PERCENT: f32 = 100.0;
 const TIMELINE_HEIGHT_PERCENT: f32 = 75.0;
@@ -19,6 +20,9 @@ const NOTE_DIAMETER_PX: f32 = 28.0;
 const NOTE_FONT_SIZE: f32 = 16.0;
 const BLOCK_GAP_PX: f32 = 16.0;
 const BLOCK_PADDING_PX: f32 = 12.0;
+const BLOCK_BORDER_PX: f32 = 1.0;
+const BLOCK_STACK_PADDING_PX: f32 = 48.0;
+const SECTION_LABEL_TOP_PX: f32 = 8.0;
 const OVERLAY_ALPHA: f32 = 0.60;
 const BLOCK_SHIFT_DURATION: f32 = 0.18;
 const FRET_VIEW_HEIGHT_PERCENT: f32 = 100.0 - TIMELINE_HEIGHT_PERCENT;
@@ -33,6 +37,13 @@ const SLIDE_LINE_HEIGHT_PX: f32 = 8.0;
 const SLIDE_ARROW_FONT_SIZE: f32 = 18.0;
 const SLIDE_TARGET_DIAMETER_PX: f32 = 22.0;
 const SMOOTHING_ALPHA: f32 = 0.3;
+const SECTION_LABEL_FONT_SIZE: f32 = 18.0;
+const CHORD_BOX_PADDING_PX: f32 = 4.0;
+const CHORD_REPEAT_WIDTH_PX: f32 = 10.0;
+const CHORD_NAME_FONT_SIZE: f32 = 13.0;
+const CHORD_NAME_WIDTH_PX: f32 = 96.0;
+const FINGER_FONT_SIZE: f32 = 16.0;
+const OPEN_STRING_OFFSET_PX: f32 = 22.0;
 
 pub struct StringTimelinePlugin;
 
@@ -41,9 +52,19 @@ impl Plugin for StringTimelinePlugin {
         app.init_resource::<StringTimelineFeed>()
             .init_resource::<TechniqueVisualizationRegistry>()
             .init_resource::<StringTimelineView>()
-            .add_systems(OnEnter(GameState::InGame), setup_timeline_ui)
-            .add_systems(OnExit(GameState::InGame), teardown_timeline_ui)
-            .add_systems(Update, update_timeline.run_if(in_state(GameState::InGame)));
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                setup_timeline_ui,
+            )
+            .add_systems(OnEnter(GameState::Loading), teardown_timeline_ui)
+            .add_systems(OnExit(AppState::Gameplay), teardown_timeline_ui)
+            .add_systems(
+                Update,
+                (update_timeline, update_section_label).run_if(in_state(GameState::InGame)),
+            );
     }
 }
 
@@ -53,15 +74,63 @@ pub struct StringTimelineFeed {
     pub window_start: f32,
     pub window_end: f32,
     pub notes: Vec<TimelineNote>,
+    pub chords: Vec<TimelineChord>,
     pub current_time: f32,
     pub block_duration: f32,
     pub block_duration_locked: bool,
+    pub section_status: Option<TimelineSectionStatus>,
+    pub seek_generation: u32,
+    pub header_inset: f32,
+    pub sections: Vec<TabSection>,
+    pub sections_generation: u32,
+    pub song_length: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionTrend {
+    Steady,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineSectionStatus {
+    pub name: String,
+    pub level: usize,
+    pub level_count: usize,
+    pub trend: SectionTrend,
 }
 
 impl StringTimelineFeed {
     pub fn window_length(&self) -> f32 {
         (self.window_end - self.window_start).max(f32::EPSILON)
     }
+
+    // Tells the view to rebuild its blocks instead of animating towards the new time.
+    pub fn mark_seek(&mut self, time: f32) {
+        self.current_time = time;
+        self.seek_generation = self.seek_generation.wrapping_add(1);
+    }
+
+    // Bumps the generation so the navigator rebuilds even when the section count is unchanged.
+    pub fn set_sections(&mut self, mut sections: Vec<TabSection>) {
+        sections.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        self.sections = sections;
+        self.sections_generation = self.sections_generation.wrapping_add(1);
+    }
+
+    pub fn navigator_height(&self) -> f32 {
+        if self.sections.is_empty() || self.song_length <= 0.0 {
+            0.0
+        } else {
+            SECTION_NAVIGATOR_HEIGHT_PX
+        }
+    }
+
+    // Everything docked above the blocks: the section navigator plus overlays like the lyric ribbon.
+    pub fn header_height(&self) -> f32 {
+        self.navigator_height() + self.header_inset.max(0.0)
+    }
 }
 
 impl Default for StringTimelineFeed {
@@ -72,9 +141,16 @@ impl Default for StringTimelineFeed {
             window_start: 0.0,
             window_end: block_duration * VISIBLE_BLOCKS as f32,
             notes: Vec::new(),
+            chords: Vec::new(),
             current_time: 0.0,
             block_duration,
             block_duration_locked: false,
+            section_status: None,
+            seek_generation: 0,
+            header_inset: 0.0,
+            sections: Vec::new(),
+            sections_generation: 0,
+            song_length: 0.0,
         }
     }
 }
@@ -89,6 +165,25 @@ pub struct TimelineNote {
     pub additional_frets: Vec<i32>,
     pub slide_target: Option<i32>,
     pub slide_unpitched_target: Option<i32>,
+    pub max_bend: f32,
+    pub chord_index: Option<usize>,
+}
+
+// One strum of a chord shape; `repeated` marks a strum of the same shape as the one before it.
+#[derive(Clone, Debug)]
+pub struct TimelineChord {
+    pub time: f32,
+    pub chord_index: usize,
+    pub name: String,
+    pub fingers: Vec<i32>,
+    pub frets: Vec<i32>,
+    pub repeated: bool,
+}
+
+impl TimelineChord {
+    fn matches(&self, note: &TimelineNote) -> bool {
+        note.chord_index == Some(self.chord_index) && note.time.to_bits() == self.time.to_bits()
+    }
 }
 
 impl TimelineNote {
@@ -152,6 +247,8 @@ enum FretMarkerRole {
     Primary,
     Additional(i32),
     SlideBar,
+    ChordFinger(usize),
+    ChordOpen(usize),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -196,6 +293,22 @@ impl FretMarkerKey {
             role: FretMarkerRole::SlideBar,
         }
     }
+
+    fn chord(note: &TimelineNote, role: FretMarkerRole) -> Self {
+        FretMarkerKey {
+            note: NoteKey::new(note),
+            role,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ChordKey {
+    time_bits: u32,
+    chord_index: usize,
+    repeated: bool,
+    low_string: usize,
+    high_string: usize,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -261,6 +374,8 @@ struct StringTimelineView {
     fret_marker_layer: Option<Entity>,
     fret_string_layer: Option<Entity>,
     fret_label: Option<Entity>,
+    section_label: Option<Entity>,
+    seek_generation: u32,
     fret_string_lines: Vec<Entity>,
     fret_fret_lines: Vec<Entity>,
     fret_markers: HashMap<FretMarkerKey, Entity>,
@@ -275,6 +390,8 @@ struct BlockView {
     overlay: Entity,
     fade_overlay: Entity,
     rows: Vec<BlockRow>,
+    chord_layer: Entity,
+    rendered_chords: HashMap<ChordKey, ChordView>,
     is_removing: bool,
     stored_height: Option<f32>,
 }
@@ -286,6 +403,11 @@ struct BlockRow {
     rendered_slide_segments: HashMap<SustainSegmentKey, SlideSegmentView>,
 }
 
+struct ChordView {
+    marker: Entity,
+    name_label: Option<Entity>,
+}
+
 struct SlideSegmentView {
     line: Entity,
     target_circle: Option<Entity>,
@@ -459,6 +581,8 @@ fn setup_timeline_ui(
     feed.current_time = 0.0;
     feed.string_count = 0;
     feed.notes.clear();
+    feed.chords.clear();
+    feed.section_status = None;
 
     let root = commands
         .spawn((
@@ -496,7 +620,7 @@ fn setup_timeline_ui(
                 flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::FlexStart,
                 align_items: AlignItems::Stretch,
-                padding: UiRect::horizontal(Val::Px(48.0)),
+                padding: UiRect::horizontal(Val::Px(BLOCK_STACK_PADDING_PX)),
 
                 row_gap: Val::Px(BLOCK_GAP_PX),
                 position_type: PositionType::Relative,
@@ -530,6 +654,25 @@ fn setup_timeline_ui(
 
     commands.entity(block_stack).add_child(indicator);
 
+    let section_label = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(12.0),
+                top: Val::Px(SECTION_LABEL_TOP_PX),
+                ..default()
+            },
+            Text::new(""),
+            TextFont {
+                font_size: SECTION_LABEL_FONT_SIZE,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.95)),
+            Visibility::Hidden,
+            ZIndex(3),
+        ))
+        .id();
+
     let fret_area = commands
         .spawn((
             Node {
@@ -669,7 +812,8 @@ fn setup_timeline_ui(
     commands
         .entity(timeline_area)
         .add_child(block_stack)
-        .add_child(indicator);
+        .add_child(indicator)
+        .add_child(section_label);
 
     view.root = Some(root);
     view.block_stack = Some(block_stack);
@@ -687,6 +831,7 @@ fn setup_timeline_ui(
     view.fret_marker_layer = Some(fret_marker_layer);
     view.fret_string_layer = Some(fret_string_layer);
     view.fret_label = Some(fret_label);
+    view.section_label = Some(section_label);
     view.fret_string_lines.clear();
     view.fret_fret_lines.clear();
     view.fret_markers.clear();
@@ -699,34 +844,17 @@ fn teardown_timeline_ui(mut commands: Commands, mut view: ResMut<StringTimelineV
     if let Some(root) = view.root.take() {
         commands.entity(root).despawn();
     }
+    // Fret lines and markers normally live under the root, so they may already be gone.
     for entity in view.fret_string_lines.drain(..) {
-        commands.entity(entity).despawn();
+        commands.entity(entity).try_despawn();
     }
     for (_, entity) in view.fret_markers.drain() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).try_despawn();
     }
     for entity in view.fret_fret_lines.drain(..) {
-        commands.entity(entity).despawn();
+        commands.entity(entity).try_despawn();
     }
-    view.block_stack = None;
-    view.indicator = None;
-    view.fret_area = None;
-    view.fret_neck = None;
-    view.fret_grid_layer = None;
-    view.fret_marker_layer = None;
-    view.fret_string_layer = None;
-    view.fret_label = None;
-    view.blocks.clear();
-    view.cached_string_count = 0;
-    view.base_block_index = 0;
-    view.string_colors.clear();
-    view.shift_animation = None;
-    view.fret_string_lines.clear();
-    view.fret_markers.clear();
-    view.fret_fret_lines.clear();
-    view.fret_current_signature.clear();
-    view.fret_current_range = None;
-    view.fret_zoom_animation = None;
+    *view = StringTimelineView::default();
 }
 
 fn update_timeline(
@@ -745,6 +873,13 @@ fn update_timeline(
         return;
     };
 
+    if view.seek_generation != feed.seek_generation {
+        view.seek_generation = feed.seek_generation;
+        clear_all_blocks(&mut commands, &mut view);
+        view.indicator_block_index = None;
+    }
+    apply_header_inset(&view, &feed, &mut node_query);
+
     let delta_seconds = time.delta_secs();
     progress_shift_animation(
         &mut commands,
@@ -868,6 +1003,33 @@ fn update_timeline(
     }
 }
 
+// The navigator and overlays such as the lyric ribbon reserve a strip above the blocks.
+fn apply_header_inset(
+    view: &StringTimelineView,
+    feed: &StringTimelineFeed,
+    node_query: &mut Query<&mut Node>,
+) {
+    let inset = Val::Px(feed.header_height());
+    if let Some(mut node) = view
+        .block_stack
+        .and_then(|entity| node_query.get_mut(entity).ok())
+    {
+        if node.padding.top != inset {
+            node.padding.top = inset;
+        }
+    }
+
+    let label_top = Val::Px(SECTION_LABEL_TOP_PX + feed.header_height());
+    if let Some(mut node) = view
+        .section_label
+        .and_then(|entity| node_query.get_mut(entity).ok())
+    {
+        if node.top != label_top {
+            node.top = label_top;
+        }
+    }
+}
+
 fn start_shift_animation(
     view: &mut StringTimelineView,
     target_base_index: i32,
@@ -1070,7 +1232,7 @@ fn spawn_block(
             flex_grow: 1.0,
             position_type: PositionType::Relative,
             padding: UiRect::all(Val::Px(BLOCK_PADDING_PX)),
-            border: UiRect::all(Val::Px(1.0)),
+            border: UiRect::all(Val::Px(BLOCK_BORDER_PX)),
             ..default()
         })
         .insert(BackgroundColor(Color::srgba(0.07, 0.07, 0.1, 0.85)))
@@ -1128,6 +1290,19 @@ fn spawn_block(
 
     commands.entity(block_root).add_child(rows_container);
 
+    let chord_layer = commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            left: Val::Px(0.0),
+            top: Val::Px(0.0),
+            ..default()
+        })
+        .id();
+
+    commands.entity(rows_container).add_child(chord_layer);
+
     let mut rows = Vec::new();
     for string_idx in 0..string_count {
         let row_entity = commands
@@ -1190,6 +1365,8 @@ fn spawn_block(
         overlay,
         fade_overlay,
         rows,
+        chord_layer,
+        rendered_chords: HashMap::new(),
         is_removing: false,
         stored_height: None,
     }
@@ -1238,7 +1415,13 @@ fn render_notes(
             let overlap_start = note_start.max(block_start);
             let overlap_end = note_end.min(block_end);
 
-            if note_start >= block_start && note_start < block_end {
+            // Repeated strums of a chord are drawn as a compact marker instead of note bubbles.
+            let collapsed = feed
+                .chords
+                .iter()
+                .any(|chord| chord.repeated && chord.matches(note));
+
+            if note_start >= block_start && note_start < block_end && !collapsed {
                 let progress = ((note_start - block_start) / block_duration).clamp(0.0, 1.0);
                 let left_percent = progress * 100.0;
                 let key = NoteKey::new(note);
@@ -1575,6 +1758,146 @@ fn render_notes(
                 }
             }
         }
+
+        render_chords(commands, block, feed, block_duration, block_is_past);
+    }
+}
+
+fn render_chords(
+    commands: &mut Commands,
+    block: &mut BlockView,
+    feed: &StringTimelineFeed,
+    block_duration: f32,
+    block_is_past: bool,
+) {
+    let block_start = block.index as f32 * block_duration;
+    let block_end = block_start + block_duration;
+    let string_count = block.rows.len();
+    let mut desired_keys = Vec::new();
+
+    for chord in &feed.chords {
+        if chord.time < block_start || chord.time >= block_end {
+            continue;
+        }
+
+        let mut strings = feed
+            .notes
+            .iter()
+            .filter(|note| chord.matches(note) && note.string_index < string_count)
+            .map(|note| note.string_index);
+        let Some(first_string) = strings.next() else {
+            continue;
+        };
+        let (low_string, high_string) = strings.fold((first_string, first_string), |acc, idx| {
+            (acc.0.min(idx), acc.1.max(idx))
+        });
+
+        let key = ChordKey {
+            time_bits: chord.time.to_bits(),
+            chord_index: chord.chord_index,
+            repeated: chord.repeated,
+            low_string,
+            high_string,
+        };
+        desired_keys.push(key);
+        if block.rendered_chords.contains_key(&key) {
+            continue;
+        }
+
+        let left_percent = ((chord.time - block_start) / block_duration).clamp(0.0, 1.0) * 100.0;
+        let row_percent = 100.0 / string_count as f32;
+        let (width, extent, background, border) = if chord.repeated {
+            (
+                CHORD_REPEAT_WIDTH_PX,
+                CHORD_REPEAT_WIDTH_PX / 2.0,
+                Color::srgba(0.85, 0.85, 0.92, 0.35),
+                Color::srgba(0.85, 0.85, 0.92, 0.8),
+            )
+        } else {
+            (
+                NOTE_DIAMETER_PX + CHORD_BOX_PADDING_PX * 2.0,
+                NOTE_DIAMETER_PX / 2.0 + CHORD_BOX_PADDING_PX,
+                Color::srgba(0.85, 0.85, 0.92, 0.12),
+                Color::srgba(0.85, 0.85, 0.92, 0.6),
+            )
+        };
+
+        // Spans from the centre of the lowest string row to the centre of the highest one,
+        // then grows outwards far enough to wrap the note bubbles.
+        let marker = commands
+            .spawn((
+                Node {
+                    width: Val::Px(width),
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(left_percent),
+                    top: Val::Percent((low_string as f32 + 0.5) * row_percent),
+                    bottom: Val::Percent(
+                        (string_count - high_string) as f32 * row_percent - row_percent / 2.0,
+                    ),
+                    margin: UiRect {
+                        left: Val::Px(-(width / 2.0)),
+                        right: Val::Px(0.0),
+                        top: Val::Px(-extent),
+                        bottom: Val::Px(-extent),
+                    },
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(background),
+                BorderColor::all(border),
+                BorderRadius::all(Val::Px(6.0)),
+            ))
+            .id();
+        commands.entity(block.chord_layer).add_child(marker);
+
+        let name_label = (!chord.repeated && !chord.name.is_empty()).then(|| {
+            let label = commands
+                .spawn(Node {
+                    width: Val::Px(CHORD_NAME_WIDTH_PX),
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(left_percent),
+                    top: Val::Px(-BLOCK_PADDING_PX),
+                    margin: UiRect::left(Val::Px(-(CHORD_NAME_WIDTH_PX / 2.0))),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(chord.name.clone()),
+                        TextFont {
+                            font_size: CHORD_NAME_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.92, 0.92, 0.96)),
+                    ));
+                })
+                .id();
+            commands.entity(block.chord_layer).add_child(label);
+            label
+        });
+
+        block
+            .rendered_chords
+            .insert(key, ChordView { marker, name_label });
+    }
+
+    if block_is_past {
+        return;
+    }
+
+    let stale: Vec<ChordKey> = block
+        .rendered_chords
+        .keys()
+        .filter(|key| !desired_keys.contains(key))
+        .copied()
+        .collect();
+    for key in stale {
+        if let Some(chord_view) = block.rendered_chords.remove(&key) {
+            commands.entity(chord_view.marker).despawn();
+            if let Some(label) = chord_view.name_label {
+                commands.entity(label).despawn();
+            }
+        }
     }
 }
 
@@ -1634,7 +1957,9 @@ fn update_fret_view(
         }
     }
 
-    let Some(range) = compute_fret_range(&active_notes) else {
+    let active_chord = find_active_chord(feed, &active_notes);
+
+    let Some(range) = compute_fret_range(&active_notes, active_chord) else {
         clear_fret_markers(commands, view);
         clear_fret_grid(commands, view);
         if !view.fret_current_signature.is_empty() {
@@ -1656,12 +1981,23 @@ fn update_fret_view(
     let mut occupied = HashSet::new();
     let string_colors = view.string_colors.clone();
 
+    if let Some(chord) = active_chord {
+        spawn_chord_fingering(
+            commands,
+            view,
+            chord,
+            active_notes[0],
+            &range,
+            &mut occupied,
+        );
+    }
+
     for note in &active_notes {
         if note.string_index >= feed.string_count {
             continue;
         }
 
-        let mut maybe_spawn_marker = |fret_value: i32, role: FretMarkerRole, primary: bool| {
+        let mut maybe_spawn_marker = |fret_value: i32, key: FretMarkerKey, primary: bool| {
             if fret_value < 0 {
                 return;
             }
@@ -1669,12 +2005,6 @@ fn update_fret_view(
                 return;
             }
 
-            let key = match role {
-                FretMarkerRole::Primary => FretMarkerKey::primary(note),
-                FretMarkerRole::Additional(extra) => FretMarkerKey::additional(note, extra),
-                FretMarkerRole::SlideBar => FretMarkerKey::slide_bar(note),
-            };
-
             let palette_color = if string_colors.is_empty() {
                 Color::srgb(0.55, 0.75, 0.95)
             } else {
@@ -1741,10 +2071,10 @@ fn update_fret_view(
             view.fret_markers.insert(key, marker_entity);
         };
 
-        maybe_spawn_marker(note.fret, FretMarkerRole::Primary, true);
+        maybe_spawn_marker(note.fret, FretMarkerKey::primary(note), true);
 
         for extra in &note.additional_frets {
-            maybe_spawn_marker(*extra, FretMarkerRole::Additional(*extra), false);
+            maybe_spawn_marker(*extra, FretMarkerKey::additional(note, *extra), false);
         }
 
         if note.is_slide() {
@@ -1808,11 +2138,14 @@ fn update_fret_view(
         }
     }
 
-    let label_text = if range.start == range.end {
+    let mut label_text = if range.start == range.end {
         format!("Fret {}", range.start)
     } else {
         format!("Frets {} - {}", range.start, range.end)
     };
+    if let Some(chord) = active_chord.filter(|chord| !chord.name.is_empty()) {
+        label_text = format!("{}   {}", chord.name, label_text);
+    }
     update_fret_label(view, text_query, &label_text);
 
     progress_fret_zoom_animation(view, delta_seconds, transform_query);
@@ -1994,10 +2327,143 @@ fn collect_active_notes<'a>(feed: &'a StringTimelineFeed) -> Vec<&'a TimelineNot
         .collect()
 }
 
-fn compute_fret_range(notes: &[&TimelineNote]) -> Option<FretRange> {
+fn find_active_chord<'a>(
+    feed: &'a StringTimelineFeed,
+    active_notes: &[&TimelineNote],
+) -> Option<&'a TimelineChord> {
+    active_notes
+        .iter()
+        .find_map(|note| feed.chords.iter().find(|chord| chord.matches(note)))
+}
+
+// Draws the whole chord shape with finger numbers, plus open/muted markers past the nut side.
+fn spawn_chord_fingering(
+    commands: &mut Commands,
+    view: &mut StringTimelineView,
+    chord: &TimelineChord,
+    key_note: &TimelineNote,
+    range: &FretRange,
+    occupied: &mut HashSet<(usize, i32, bool)>,
+) {
+    let Some(marker_layer) = view.fret_marker_layer else {
+        return;
+    };
+    let string_count = view.fret_string_lines.len();
+    let string_colors = view.string_colors.clone();
+
+    for (string_index, fret) in chord.frets.iter().copied().enumerate() {
+        if string_index >= string_count {
+            continue;
+        }
+        let top_percent = string_position_percent(string_index, string_count).clamp(0.0, 100.0);
+
+        if fret <= 0 {
+            let symbol = if fret == 0 { "O" } else { "X" };
+            let entity = commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(0.0),
+                        top: Val::Percent(top_percent),
+                        margin: UiRect {
+                            left: Val::Px(-OPEN_STRING_OFFSET_PX),
+                            right: Val::Px(0.0),
+                            top: Val::Px(-(FINGER_FONT_SIZE * 0.6)),
+                            bottom: Val::Px(0.0),
+                        },
+                        ..default()
+                    },
+                    Text::new(symbol),
+                    TextFont {
+                        font_size: FINGER_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.92, 0.92, 0.96)),
+                    FretMarker,
+                    ZIndex(3),
+                ))
+                .id();
+            commands.entity(marker_layer).add_child(entity);
+            view.fret_markers.insert(
+                FretMarkerKey::chord(key_note, FretMarkerRole::ChordOpen(string_index)),
+                entity,
+            );
+            continue;
+        }
+
+        if !occupied.insert((string_index, fret, true)) {
+            continue;
+        }
+
+        let marker_color = if string_colors.is_empty() {
+            Color::srgb(0.55, 0.75, 0.95)
+        } else {
+            string_colors[string_index % string_colors.len()]
+        };
+        let diameter = FRET_MARKER_DIAMETER_PX;
+        let left_percent = fret_left_percent(fret, range).clamp(0.0, 100.0);
+        let finger = chord.fingers.get(string_index).copied().unwrap_or(-1);
+
+        let marker = commands
+            .spawn((
+                Node {
+                    width: Val::Px(diameter),
+                    height: Val::Px(diameter),
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(left_percent),
+                    top: Val::Percent(top_percent),
+                    margin: UiRect {
+                        left: Val::Px(-(diameter / 2.0)),
+                        right: Val::Px(0.0),
+                        top: Val::Px(-(diameter / 2.0)),
+                        bottom: Val::Px(0.0),
+                    },
+                    border: UiRect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(marker_color),
+                BorderColor::all(Color::srgb(0.95, 0.95, 0.98)),
+                BorderRadius::all(Val::Px(diameter / 2.0)),
+                FretMarker,
+                ZIndex(3),
+            ))
+            .id();
+
+        if finger > 0 {
+            commands.entity(marker).with_children(|parent| {
+                parent.spawn((
+                    Text::new(finger.to_string()),
+                    TextFont {
+                        font_size: FINGER_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+            });
+        }
+
+        commands.entity(marker_layer).add_child(marker);
+        view.fret_markers.insert(
+            FretMarkerKey::chord(key_note, FretMarkerRole::ChordFinger(string_index)),
+            marker,
+        );
+    }
+}
+
+fn compute_fret_range(notes: &[&TimelineNote], chord: Option<&TimelineChord>) -> Option<FretRange> {
     let mut min_fret = i32::MAX;
     let mut max_fret = i32::MIN;
 
+    // Open strings sit at the nut, so only fretted positions widen the view.
+    for fret in chord.iter().flat_map(|chord| chord.frets.iter()) {
+        if *fret > 0 {
+            min_fret = min_fret.min(*fret);
+            max_fret = max_fret.max(*fret);
+        }
+    }
+
     for note in notes {
         if note.fret >= 0 {
             min_fret = min_fret.min(note.fret);
@@ -2024,6 +2490,40 @@ fn clear_fret_markers(commands: &mut Commands, view: &mut StringTimelineView) {
     }
 }
 
+fn update_section_label(
+    feed: Res<StringTimelineFeed>,
+    view: Res<StringTimelineView>,
+    mut label_query: Query<(&mut Text, &mut TextColor, &mut Visibility)>,
+) {
+    let Some(label_entity) = view.section_label else {
+        return;
+    };
+    let Ok((mut text, mut color, mut visibility)) = label_query.get_mut(label_entity) else {
+        return;
+    };
+
+    let Some(status) = feed.section_status.as_ref() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let content = format!(
+        "{} - Level {}/{}",
+        status.name,
+        status.level + 1,
+        status.level_count.max(1)
+    );
+    if text.0 != content {
+        text.0 = content;
+    }
+    color.0 = match status.trend {
+        SectionTrend::Steady => Color::srgb(0.9, 0.9, 0.95),
+        SectionTrend::Up => Color::srgb(0.45, 0.9, 0.5),
+        SectionTrend::Down => Color::srgb(0.95, 0.5, 0.45),
+    };
+    *visibility = Visibility::Inherited;
+}
+
 fn update_fret_label(view: &StringTimelineView, text_query: &mut Query<&mut Text>, content: &str) {
     let Some(label_entity) = view.fret_label else {
         return;
@@ -2217,6 +2717,11 @@ pub fn target_note_spacing_percent() -> f32 {
     TARGET_NOTE_SPACING_PERCENT
 }
 
+// Horizontal distance from the screen edge to where block time 0 starts.
+pub fn timeline_content_inset_px() -> f32 {
+    BLOCK_STACK_PADDING_PX + BLOCK_BORDER_PX + BLOCK_PADDING_PX
+}
+
 pub fn visible_block_count() -> usize {
     VISIBLE_BLOCKS
 }
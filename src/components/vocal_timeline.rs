@@ -0,0 +1,724 @@
+use bevy::prelude::*;
+use bevy::text::TextLayout;
+use bevy::ui::UiTargetCamera;
+
+use crate::components::string_timeline::{
+    clamp_block_duration, timeline_content_inset_px, StringTimelineFeed,
+};
+use crate::file::config::AppConfig;
+use crate::file::settings::{save_settings, Settings};
+use crate::file::song::VocalPhrase;
+use crate::file::theme::Themes;
+use crate::scenes::MainCamera;
+use crate::states::{AppState, GameState};
+
+const VISIBLE_LINES: usize = 3;
+const LINE_BREAK_GAP_SECONDS: f32 = 2.0;
+const LINE_HOLD_SECONDS: f32 = 0.5;
+const CURRENT_LINE_FONT_SIZE: f32 = 40.0;
+const UPCOMING_LINE_FONT_SIZE: f32 = 26.0;
+const WORD_GAP_PX: f32 = 12.0;
+const LINE_GAP_PX: f32 = 18.0;
+const RIBBON_HEIGHT_PX: f32 = 40.0;
+const RIBBON_FONT_SIZE: f32 = 22.0;
+const RIBBON_WORD_GAP_PX: f32 = 8.0;
+
+pub struct VocalTimelinePlugin;
+
+impl Plugin for VocalTimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VocalTimelineFeed>()
+            .init_resource::<VocalTimelineView>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                setup_vocal_ui,
+            )
+            .add_systems(OnEnter(GameState::Loading), teardown_vocal_ui)
+            .add_systems(OnExit(AppState::Gameplay), teardown_vocal_ui)
+            .add_systems(
+                Update,
+                (
+                    toggle_lyric_ribbon,
+                    update_vocal_view,
+                    update_lyric_ribbon,
+                    update_syllable_fills,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LyricSyllable {
+    pub time: f32,
+    pub length: f32,
+    pub text: String,
+    pub joins_next: bool,
+}
+
+impl LyricSyllable {
+    pub fn progress(&self, time: f32) -> f32 {
+        fill_progress(self.time, self.length, time)
+    }
+}
+
+fn fill_progress(start: f32, length: f32, time: f32) -> f32 {
+    if length <= f32::EPSILON {
+        return if time >= start { 1.0 } else { 0.0 };
+    }
+    ((time - start) / length).clamp(0.0, 1.0)
+}
+
+#[derive(Clone, Debug)]
+pub struct LyricLine {
+    pub start_time: f32,
+    pub end_time: f32,
+    pub syllables: Vec<LyricSyllable>,
+}
+
+impl LyricLine {
+    // Syllables grouped into words, honouring the `-` continuation marker.
+    pub fn words(&self) -> Vec<&[LyricSyllable]> {
+        let mut words = Vec::new();
+        let mut start = 0;
+        for (index, syllable) in self.syllables.iter().enumerate() {
+            if !syllable.joins_next {
+                words.push(&self.syllables[start..=index]);
+                start = index + 1;
+            }
+        }
+        if start < self.syllables.len() {
+            words.push(&self.syllables[start..]);
+        }
+        words
+    }
+}
+
+/// Groups vocal entries into sung lines. Entries follow the Rocksmith convention where a
+/// trailing `-` joins a syllable to the next one and a trailing `+` ends the line; long
+/// pauses also start a new line for charts that don't mark them.
+pub fn lyric_lines(phrases: &[VocalPhrase]) -> Vec<LyricLine> {
+    let mut sorted: Vec<&VocalPhrase> = phrases.iter().collect();
+    sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let mut lines = Vec::new();
+    let mut current: Vec<LyricSyllable> = Vec::new();
+    let mut previous_end = f32::NEG_INFINITY;
+
+    for phrase in sorted {
+        if !current.is_empty() && phrase.time - previous_end > LINE_BREAK_GAP_SECONDS {
+            lines.push(finish_line(std::mem::take(&mut current)));
+        }
+
+        let mut text = phrase.lyric.trim();
+        let ends_line = text.ends_with('+');
+        text = text.trim_end_matches('+');
+        let joins_next = text.ends_with('-');
+        text = text.trim_end_matches('-');
+
+        if !text.is_empty() {
+            current.push(LyricSyllable {
+                time: phrase.time,
+                length: phrase.length.max(0.0),
+                text: text.to_string(),
+                joins_next,
+            });
+        }
+        previous_end = phrase.time + phrase.length.max(0.0);
+
+        if ends_line && !current.is_empty() {
+            lines.push(finish_line(std::mem::take(&mut current)));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(finish_line(current));
+    }
+    lines
+}
+
+fn finish_line(mut syllables: Vec<LyricSyllable>) -> LyricLine {
+    if let Some(last) = syllables.last_mut() {
+        last.joins_next = false;
+    }
+    let start_time = syllables.first().map(|s| s.time).unwrap_or_default();
+    let end_time = syllables
+        .iter()
+        .map(|s| s.time + s.length)
+        .fold(start_time, f32::max);
+    LyricLine {
+        start_time,
+        end_time,
+        syllables,
+    }
+}
+
+/// Index of the line being sung at `time`, or of the next one while between lines.
+pub fn current_line_index(lines: &[LyricLine], time: f32) -> Option<usize> {
+    lines
+        .iter()
+        .position(|line| line.end_time + LINE_HOLD_SECONDS >= time)
+}
+
+/// How the lyrics are shown: full screen for a vocal arrangement, or as a ribbon above
+/// the string timeline when they come from a second tab.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LyricDisplay {
+    #[default]
+    Karaoke,
+    Ribbon,
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct VocalTimelineFeed {
+    pub lines: Vec<LyricLine>,
+    pub current_time: f32,
+    pub display: LyricDisplay,
+    pub generation: u32,
+}
+
+impl VocalTimelineFeed {
+    // The lines are rebuilt from the tab on the next update; the generation makes the views
+    // redraw even where the new lines start at the same times as the old ones.
+    pub fn reset_lines(&mut self) {
+        self.lines.clear();
+        self.generation = self.generation.wrapping_add(1);
+    }
+}
+
+#[derive(Resource, Default)]
+struct VocalTimelineView {
+    root: Option<Entity>,
+    shown_lines: (u32, Vec<u32>),
+    ribbon: Option<Entity>,
+    ribbon_track: Option<Entity>,
+    ribbon_playhead: Option<Entity>,
+    ribbon_block: Option<(u32, i32)>,
+    palette: VocalPalette,
+}
+
+#[derive(Clone, Copy)]
+struct VocalPalette {
+    sung: Color,
+    current: Color,
+    upcoming: Color,
+    panel: Color,
+}
+
+impl Default for VocalPalette {
+    fn default() -> Self {
+        VocalPalette {
+            sung: Color::srgb(0.235, 0.549, 1.0),
+            current: Color::WHITE,
+            upcoming: Color::srgb(0.6, 0.6, 0.65),
+            panel: Color::srgba(0.05, 0.05, 0.08, 0.85),
+        }
+    }
+}
+
+#[derive(Component)]
+struct VocalTimelineRoot;
+
+#[derive(Component)]
+struct LyricRibbonRoot;
+
+#[derive(Component)]
+struct SyllableFill {
+    time: f32,
+    length: f32,
+}
+
+fn resolve_vocal_palette(settings: &Settings, themes: &Themes) -> VocalPalette {
+    let Some(theme) = themes
+        .get(&settings.start_theme)
+        .or_else(|| themes.themes.values().next())
+    else {
+        return VocalPalette::default();
+    };
+    VocalPalette {
+        sung: theme.primary,
+        current: theme.text_primary,
+        upcoming: theme.text_secondary,
+        panel: theme.background_paper.with_alpha(0.85),
+    }
+}
+
+fn setup_vocal_ui(
+    mut commands: Commands,
+    mut view: ResMut<VocalTimelineView>,
+    mut feed: ResMut<VocalTimelineFeed>,
+    main_camera: Res<MainCamera>,
+    settings: Res<Settings>,
+    themes: Res<Themes>,
+) {
+    *feed = VocalTimelineFeed::default();
+    view.palette = resolve_vocal_palette(&settings, &themes);
+    view.shown_lines = Default::default();
+
+    let root = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(LINE_GAP_PX),
+                ..default()
+            },
+            UiTargetCamera(main_camera.ui_camera),
+            Visibility::Hidden,
+            Pickable::IGNORE,
+            VocalTimelineRoot,
+        ))
+        .id();
+    view.root = Some(root);
+
+    let ribbon = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(RIBBON_HEIGHT_PX),
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                padding: UiRect::horizontal(Val::Px(timeline_content_inset_px())),
+                ..default()
+            },
+            BackgroundColor(view.palette.panel),
+            UiTargetCamera(main_camera.ui_camera),
+            Visibility::Hidden,
+            Pickable::IGNORE,
+            ZIndex(1),
+            LyricRibbonRoot,
+        ))
+        .id();
+    let lane = commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Relative,
+            ..default()
+        })
+        .id();
+    let ribbon_track = commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            overflow: Overflow::clip(),
+            ..default()
+        })
+        .id();
+    let ribbon_playhead = commands
+        .spawn((
+            Node {
+                width: Val::Px(2.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Px(0.0),
+                margin: UiRect::left(Val::Px(-1.0)),
+                ..default()
+            },
+            BackgroundColor(view.palette.sung),
+        ))
+        .id();
+    commands
+        .entity(lane)
+        .add_child(ribbon_track)
+        .add_child(ribbon_playhead);
+    commands.entity(ribbon).add_child(lane);
+    view.ribbon = Some(ribbon);
+    view.ribbon_track = Some(ribbon_track);
+    view.ribbon_playhead = Some(ribbon_playhead);
+}
+
+fn teardown_vocal_ui(mut commands: Commands, mut view: ResMut<VocalTimelineView>) {
+    if let Some(root) = view.root.take() {
+        commands.entity(root).try_despawn();
+    }
+    if let Some(ribbon) = view.ribbon.take() {
+        commands.entity(ribbon).try_despawn();
+    }
+    *view = VocalTimelineView::default();
+}
+
+fn toggle_lyric_ribbon(
+    keys: Res<ButtonInput<KeyCode>>,
+    feed: Res<VocalTimelineFeed>,
+    mut settings: ResMut<Settings>,
+    config: Res<AppConfig>,
+) {
+    if feed.display != LyricDisplay::Ribbon || !keys.just_pressed(KeyCode::KeyV) {
+        return;
+    }
+    settings.show_lyric_ribbon = !settings.show_lyric_ribbon;
+    save_settings(&settings, &config);
+}
+
+fn update_vocal_view(
+    mut commands: Commands,
+    feed: Res<VocalTimelineFeed>,
+    mut view: ResMut<VocalTimelineView>,
+    mut visibility_query: Query<&mut Visibility, With<VocalTimelineRoot>>,
+) {
+    let Some(root) = view.root else {
+        return;
+    };
+
+    let line_index = match feed.display {
+        LyricDisplay::Karaoke => current_line_index(&feed.lines, feed.current_time),
+        LyricDisplay::Ribbon => None,
+    };
+    let visible: &[LyricLine] = match line_index {
+        Some(index) => {
+            let end = (index + VISIBLE_LINES).min(feed.lines.len());
+            &feed.lines[index..end]
+        }
+        None => &[],
+    };
+
+    if let Ok(mut visibility) = visibility_query.get_mut(root) {
+        let desired = if visible.is_empty() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        visibility.set_if_neq(desired);
+    }
+
+    let shown = (
+        feed.generation,
+        visible
+            .iter()
+            .map(|line| line.start_time.to_bits())
+            .collect::<Vec<u32>>(),
+    );
+    if shown != view.shown_lines {
+        commands.entity(root).despawn_related::<Children>();
+        for (index, line) in visible.iter().enumerate() {
+            let line_entity = spawn_lyric_line(&mut commands, line, index == 0, view.palette);
+            commands.entity(root).add_child(line_entity);
+        }
+        view.shown_lines = shown;
+    }
+}
+
+// The ribbon shows the words of the block the string timeline is currently playing, laid
+// out on the same horizontal time scale so each word sits above the notes it goes with.
+fn update_lyric_ribbon(
+    mut commands: Commands,
+    feed: Res<VocalTimelineFeed>,
+    mut timeline: ResMut<StringTimelineFeed>,
+    settings: Res<Settings>,
+    mut view: ResMut<VocalTimelineView>,
+    mut visibility_query: Query<&mut Visibility, With<LyricRibbonRoot>>,
+    mut node_query: Query<&mut Node, Without<SyllableFill>>,
+) {
+    let (Some(ribbon), Some(track)) = (view.ribbon, view.ribbon_track) else {
+        return;
+    };
+
+    let shown = feed.display == LyricDisplay::Ribbon
+        && settings.show_lyric_ribbon
+        && !feed.lines.is_empty();
+    let inset = if shown { RIBBON_HEIGHT_PX } else { 0.0 };
+    if timeline.header_inset != inset {
+        timeline.header_inset = inset;
+    }
+    if let Ok(mut node) = node_query.get_mut(ribbon) {
+        let top = Val::Px(timeline.navigator_height());
+        if node.top != top {
+            node.top = top;
+        }
+    }
+    if let Ok(mut visibility) = visibility_query.get_mut(ribbon) {
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    if !shown {
+        return;
+    }
+
+    let block_duration = clamp_block_duration(timeline.block_duration);
+    let block_index = (feed.current_time / block_duration).floor().max(0.0) as i32;
+    let block_start = block_index as f32 * block_duration;
+    let block_progress = ((feed.current_time - block_start) / block_duration).clamp(0.0, 1.0);
+
+    if let Some(mut node) = view
+        .ribbon_playhead
+        .and_then(|entity| node_query.get_mut(entity).ok())
+    {
+        let left = Val::Percent(block_progress * 100.0);
+        if node.left != left {
+            node.left = left;
+        }
+    }
+
+    if view.ribbon_block == Some((feed.generation, block_index)) {
+        return;
+    }
+    view.ribbon_block = Some((feed.generation, block_index));
+    commands.entity(track).despawn_related::<Children>();
+
+    let block_end = block_start + block_duration;
+    let words: Vec<&[LyricSyllable]> = feed
+        .lines
+        .iter()
+        .filter(|line| line.end_time >= block_start && line.start_time < block_end)
+        .flat_map(|line| line.words())
+        .filter(|word| word[0].time >= block_start && word[0].time < block_end)
+        .collect();
+    let Some(first) = words.first() else {
+        return;
+    };
+
+    let to_percent = |seconds: f32| (seconds / block_duration * 100.0).max(0.0);
+    let spacer = commands
+        .spawn(Node {
+            width: Val::Percent(to_percent(first[0].time - block_start)),
+            flex_shrink: 0.0,
+            ..default()
+        })
+        .id();
+    commands.entity(track).add_child(spacer);
+
+    for (index, word) in words.iter().enumerate() {
+        let next_start = words
+            .get(index + 1)
+            .map(|next| next[0].time)
+            .unwrap_or(block_end);
+        // Words get the width of their time slot, but never less than their text so
+        // quick passages push later words along instead of overlapping them.
+        let word_entity = commands
+            .spawn(Node {
+                width: Val::Percent(to_percent(next_start - word[0].time)),
+                flex_shrink: 0.0,
+                flex_direction: FlexDirection::Row,
+                padding: UiRect::right(Val::Px(RIBBON_WORD_GAP_PX)),
+                ..default()
+            })
+            .id();
+        for syllable in word.iter() {
+            let syllable_entity = spawn_syllable(
+                &mut commands,
+                syllable,
+                RIBBON_FONT_SIZE,
+                view.palette.current,
+                view.palette.sung,
+            );
+            commands.entity(word_entity).add_child(syllable_entity);
+        }
+        commands.entity(track).add_child(word_entity);
+    }
+}
+
+fn update_syllable_fills(
+    feed: Res<VocalTimelineFeed>,
+    mut fill_query: Query<(&SyllableFill, &mut Node)>,
+) {
+    for (fill, mut node) in &mut fill_query {
+        let progress = fill_progress(fill.time, fill.length, feed.current_time);
+        let width = Val::Percent(progress * 100.0);
+        if node.width != width {
+            node.width = width;
+        }
+    }
+}
+
+fn spawn_lyric_line(
+    commands: &mut Commands,
+    line: &LyricLine,
+    is_current: bool,
+    palette: VocalPalette,
+) -> Entity {
+    let font_size = if is_current {
+        CURRENT_LINE_FONT_SIZE
+    } else {
+        UPCOMING_LINE_FONT_SIZE
+    };
+    let base_color = if is_current {
+        palette.current
+    } else {
+        palette.upcoming
+    };
+
+    let line_entity = commands
+        .spawn((
+            Node {
+                max_width: Val::Percent(90.0),
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(WORD_GAP_PX),
+                padding: UiRect::axes(Val::Px(20.0), Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(if is_current {
+                palette.panel
+            } else {
+                Color::NONE
+            }),
+            BorderRadius::all(Val::Px(10.0)),
+        ))
+        .id();
+
+    for word in line.words() {
+        let word_entity = commands
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                ..default()
+            })
+            .id();
+        for syllable in word {
+            let syllable_entity =
+                spawn_syllable(commands, syllable, font_size, base_color, palette.sung);
+            commands.entity(word_entity).add_child(syllable_entity);
+        }
+        commands.entity(line_entity).add_child(word_entity);
+    }
+
+    line_entity
+}
+
+// The sung colour sits in a clipping node on top of the base text and widens as the
+// syllable is sung, so long notes fill gradually instead of flipping colour at once.
+fn spawn_syllable(
+    commands: &mut Commands,
+    syllable: &LyricSyllable,
+    font_size: f32,
+    base_color: Color,
+    sung_color: Color,
+) -> Entity {
+    let font = TextFont {
+        font_size,
+        ..default()
+    };
+    commands
+        .spawn(Node {
+            position_type: PositionType::Relative,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(syllable.text.clone()),
+                font.clone(),
+                TextColor(base_color),
+                TextLayout::new_with_no_wrap(),
+            ));
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    SyllableFill {
+                        time: syllable.time,
+                        length: syllable.length,
+                    },
+                ))
+                .with_children(|fill| {
+                    fill.spawn((
+                        Node {
+                            flex_shrink: 0.0,
+                            ..default()
+                        },
+                        Text::new(syllable.text.clone()),
+                        font,
+                        TextColor(sung_color),
+                        TextLayout::new_with_no_wrap(),
+                    ));
+                });
+        })
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrase(time: f32, length: f32, lyric: &str) -> VocalPhrase {
+        VocalPhrase {
+            time,
+            length,
+            lyric: lyric.to_string(),
+        }
+    }
+
+    fn line_texts(line: &LyricLine) -> Vec<String> {
+        line.words()
+            .iter()
+            .map(|word| word.iter().map(|s| s.text.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn an_empty_track_has_no_lines() {
+        assert!(lyric_lines(&[]).is_empty());
+        assert_eq!(current_line_index(&[], 1.0), None);
+    }
+
+    #[test]
+    fn a_single_word_is_one_line() {
+        let lines = lyric_lines(&[phrase(1.0, 0.5, "Hey+")]);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(line_texts(&lines[0]), ["Hey"]);
+        assert_eq!((lines[0].start_time, lines[0].end_time), (1.0, 1.5));
+        assert!(!lines[0].syllables[0].joins_next);
+    }
+
+    #[test]
+    fn markers_join_syllables_and_end_lines() {
+        let lines = lyric_lines(&[
+            phrase(1.4, 0.2, "song+"),
+            phrase(0.0, 0.2, "Sing-"),
+            phrase(0.5, 0.2, "ing"),
+            phrase(1.0, 0.2, "a"),
+            phrase(2.0, 0.3, "Next"),
+            phrase(2.5, 0.3, "line-"),
+        ]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(line_texts(&lines[0]), ["Singing", "a", "song"]);
+        // The last syllable of a line never joins onto the next line.
+        assert_eq!(line_texts(&lines[1]), ["Next", "line"]);
+        assert!(!lines[1].syllables[1].joins_next);
+    }
+
+    #[test]
+    fn long_pauses_start_a_new_line() {
+        let lines = lyric_lines(&[phrase(0.0, 0.5, "One"), phrase(5.0, 0.5, "Two")]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].start_time, 5.0);
+    }
+
+    #[test]
+    fn the_current_line_holds_until_the_next_one_is_due() {
+        let lines = lyric_lines(&[phrase(2.0, 1.0, "One+"), phrase(6.0, 1.0, "Two+")]);
+        // Before the first line it is the one coming up.
+        assert_eq!(current_line_index(&lines, 0.0), Some(0));
+        assert_eq!(current_line_index(&lines, 2.5), Some(0));
+        assert_eq!(current_line_index(&lines, 3.0 + LINE_HOLD_SECONDS), Some(0));
+        assert_eq!(current_line_index(&lines, 4.0), Some(1));
+        // Past the last line nothing is sung any more.
+        assert_eq!(
+            current_line_index(&lines, 7.0 + LINE_HOLD_SECONDS + 0.1),
+            None
+        );
+    }
+}
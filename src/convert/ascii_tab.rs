@@ -0,0 +1,852 @@
+use std::fs;
+use std::path::Path;
+
+use crate::convert::{
+    plain_note, sections_from_markers, string_arrangement_metadata, used_techniques,
+    ChordTemplates, ConvertError, ImportedSong, DEFAULT_TEMPO, UNPITCHED_SLIDE_FRETS,
+};
+use crate::file::song::{
+    SongArrangementMetadata, StringTab, Tab, TabNote, TabNoteChart, TabsInstrument, Techniques,
+};
+use crate::input::{standard_open_strings, InstrumentTuning};
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+// A bend written without a target fret is a full bend.
+const FULL_BEND: f32 = 2.0;
+const PALM_MUTE_MARKERS: [&str; 2] = ["P.M.", "PM"];
+// Characters that may appear on a staff line besides frets.
+const STAFF_CHARS: &str = "-|hpbr/\\~xs:*()<>.^= ";
+
+// Exports use the same fixed tempo as the MusicXML writer. Each measure takes the coarsest
+// subdivision of the beat that keeps its notes within a couple of milliseconds.
+const EXPORT_TEMPO: f64 = 120.0;
+const EXPORT_QUARTERS_PER_MEASURE: i64 = 4;
+const EXPORT_SUBDIVISIONS: [i64; 6] = [1, 2, 4, 3, 6, 8];
+// Divisible by every subdivision, used to decide which measure a note lands in.
+const EXPORT_TICKS_PER_QUARTER: i64 = 24;
+const EXPORT_GRID_TOLERANCE: f64 = 0.002;
+const EXPORT_LINE_WIDTH: usize = 80;
+
+// Values given here win over `BPM:` and `Time:` lines in the file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiTabOptions {
+    pub bpm: Option<f64>,
+    pub time_signature: Option<(u32, u32)>,
+}
+
+pub fn import_ascii_tab_file(
+    path: &Path,
+    options: &AsciiTabOptions,
+) -> Result<ImportedSong, ConvertError> {
+    let text = fs::read_to_string(path)?;
+    import_ascii_tab(&text, options)
+}
+
+// Plain-text tabs have no durations, so every bar is one measure with its notes spread
+// evenly across its width. Repeats are not unrolled.
+pub fn import_ascii_tab(
+    text: &str,
+    options: &AsciiTabOptions,
+) -> Result<ImportedSong, ConvertError> {
+    let lines: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+    let mut imported = ImportedSong::new("", "", "", 0.0);
+    let mut timing = Timing {
+        bpm: options.bpm.unwrap_or(DEFAULT_TEMPO),
+        quarters_per_measure: options
+            .time_signature
+            .map_or(4.0, |(beats, unit)| quarters_per_measure(beats, unit)),
+    };
+    let mut arrangements: Vec<AsciiArrangement> = Vec::new();
+    let mut pending_section: Option<String> = None;
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        if staff_start(line).is_some() {
+            let end = (index..lines.len())
+                .find(|&next| staff_start(&lines[next]).is_none())
+                .unwrap_or(lines.len());
+            let palm_mute_row = index
+                .checked_sub(1)
+                .map(|above| &lines[above][..])
+                .filter(|above| palm_mute_start(above, 0).is_some());
+            if arrangements.is_empty() {
+                arrangements.push(AsciiArrangement::new(None));
+            }
+            let arrangement = arrangements.last_mut().expect("an arrangement exists");
+            if let Some(name) = pending_section.take() {
+                arrangement.markers.push((name, arrangement.time));
+            }
+            arrangement.read_staff(&lines[index..end], palm_mute_row, &timing)?;
+            index = end;
+            continue;
+        }
+
+        let text: String = line.iter().collect();
+        let trimmed = text.trim();
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            pending_section = Some(name.trim().to_string());
+        } else if let Some((key, value)) = trimmed.split_once(':') {
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "title" | "song" => imported.metadata.title = value.to_string(),
+                "artist" | "band" => imported.metadata.artist = value.to_string(),
+                "album" => imported.metadata.album = value.to_string(),
+                "bpm" | "tempo" if options.bpm.is_none() => {
+                    if let Some(bpm) = first_number(value).filter(|bpm| *bpm > 0.0) {
+                        timing.bpm = bpm;
+                    }
+                }
+                "time" | "time signature" if options.time_signature.is_none() => {
+                    if let Some((beats, unit)) = value.split_once('/') {
+                        if let (Ok(beats), Ok(unit)) =
+                            (beats.trim().parse(), unit.trim().parse::<u32>())
+                        {
+                            if beats > 0 && unit > 0 {
+                                timing.quarters_per_measure = quarters_per_measure(beats, unit);
+                            }
+                        }
+                    }
+                }
+                "capo" => {
+                    let capo = first_number(value).unwrap_or(0.0) as i32;
+                    match arrangements.last_mut() {
+                        Some(arrangement) if arrangement.notes.is_empty() => {
+                            arrangement.capo = capo
+                        }
+                        _ => {
+                            let mut arrangement = AsciiArrangement::new(None);
+                            arrangement.capo = capo;
+                            arrangements.push(arrangement);
+                        }
+                    }
+                }
+                "arrangement" | "part" | "track" => {
+                    match arrangements.last_mut() {
+                        Some(arrangement)
+                            if arrangement.notes.is_empty() && arrangement.name.is_none() =>
+                        {
+                            arrangement.name = Some(value.to_string())
+                        }
+                        _ => arrangements.push(AsciiArrangement::new(Some(value.to_string()))),
+                    }
+                    pending_section = None;
+                }
+                _ => {}
+            }
+        } else if trimmed.to_lowercase().contains("bpm") && options.bpm.is_none() {
+            if let Some(bpm) = first_number(trimmed).filter(|bpm| *bpm > 0.0) {
+                timing.bpm = bpm;
+            }
+        }
+        index += 1;
+    }
+
+    for arrangement in arrangements {
+        if arrangement.notes.is_empty() {
+            continue;
+        }
+        imported.metadata.length = imported.metadata.length.max(arrangement.time);
+        let (name, metadata, tab) = arrangement.finish();
+        imported.add_arrangement(&name, metadata, Tab::Strings(tab));
+    }
+    if imported.arrangements.is_empty() {
+        return Err(ConvertError::NoStringTracks);
+    }
+    Ok(imported)
+}
+
+struct Timing {
+    bpm: f64,
+    quarters_per_measure: f64,
+}
+
+impl Timing {
+    fn measure_seconds(&self) -> f32 {
+        (self.quarters_per_measure * 60.0 / self.bpm) as f32
+    }
+}
+
+fn quarters_per_measure(beats: u32, unit: u32) -> f64 {
+    beats as f64 * 4.0 / unit as f64
+}
+
+struct AsciiArrangement {
+    name: Option<String>,
+    capo: i32,
+    // Open-string pitch classes from the staff labels, lowest string first.
+    labels: Option<Vec<Option<i32>>>,
+    notes: Vec<TabNote>,
+    markers: Vec<(String, f32)>,
+    // Where the next staff starts.
+    time: f32,
+    // The note on each string that slides into the next note on that string.
+    pending_slides: Vec<Option<(usize, bool)>>,
+}
+
+// A note as written on one staff line, before it is placed in time.
+struct WrittenNote {
+    column: usize,
+    fret: i32,
+    legato: Option<Techniques>,
+    bend: Option<f32>,
+    vibrato: bool,
+    // Slides up when true.
+    slide: Option<bool>,
+}
+
+impl AsciiArrangement {
+    fn new(name: Option<String>) -> Self {
+        AsciiArrangement {
+            name,
+            capo: 0,
+            labels: None,
+            notes: Vec::new(),
+            markers: Vec::new(),
+            time: 0.0,
+            pending_slides: Vec::new(),
+        }
+    }
+
+    fn read_staff(
+        &mut self,
+        staff: &[Vec<char>],
+        palm_mute_row: Option<&[char]>,
+        timing: &Timing,
+    ) -> Result<(), ConvertError> {
+        let string_count = staff.len();
+        let labels: Vec<Option<i32>> = staff
+            .iter()
+            .rev()
+            .map(|line| label_pitch_class(line))
+            .collect();
+        match &self.labels {
+            Some(existing) if existing.len() != string_count => {
+                return Err(ConvertError::InvalidData(format!(
+                    "staff with {string_count} strings after staves with {}",
+                    existing.len()
+                )));
+            }
+            Some(_) => {}
+            None => {
+                self.labels = Some(labels);
+                self.pending_slides = vec![None; string_count];
+            }
+        }
+
+        let measures = staff_measures(&staff[0]);
+        let measure_seconds = timing.measure_seconds();
+        let start_time = self.time;
+        let time_at = |column: usize| {
+            measures
+                .iter()
+                .enumerate()
+                .find(|(_, (start, end))| column > *start && column < *end)
+                .map(|(index, (start, end))| {
+                    let offset = (column - start - 1) as f32 / (end - start - 1) as f32;
+                    start_time + (index as f32 + offset) * measure_seconds
+                })
+        };
+
+        let mut written: Vec<(f32, i32, WrittenNote)> = Vec::new();
+        for (line_index, line) in staff.iter().enumerate() {
+            let string = (string_count - 1 - line_index) as i32;
+            let start = staff_start(line).unwrap_or(0);
+            for note in read_staff_line(line, start) {
+                if let Some(time) = time_at(note.column) {
+                    written.push((time, string, note));
+                }
+            }
+        }
+        written.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        for (time, string, note) in written {
+            let fret = if note.fret > 0 {
+                note.fret + self.capo
+            } else {
+                note.fret
+            };
+            let mut tab_note = plain_note(time, string, fret);
+            if let Some(legato) = note.legato {
+                tab_note.techniques.push(legato);
+            }
+            if let Some(bend) = note.bend {
+                tab_note.max_bend = bend;
+                tab_note.techniques.push(Techniques::Bend);
+            }
+            if note.vibrato {
+                tab_note.vibrato = 80;
+                tab_note.techniques.push(Techniques::Vibrato);
+            }
+            if palm_mute_row.is_some_and(|row| is_palm_muted(row, note.column)) {
+                tab_note.techniques.push(Techniques::PalmMute);
+            }
+
+            let slot = &mut self.pending_slides[string as usize];
+            if let Some((source, _)) = slot.take() {
+                let source = &mut self.notes[source];
+                source.slide_to = fret;
+                source.sustain = time - source.time;
+                source.techniques.push(Techniques::Slide);
+            }
+            *slot = note.slide.map(|up| (self.notes.len(), up));
+            self.notes.push(tab_note);
+        }
+
+        self.time += measures.len() as f32 * measure_seconds;
+        Ok(())
+    }
+
+    fn finish(mut self) -> (String, SongArrangementMetadata, StringTab) {
+        // A slide with nothing after it on its string has no target pitch.
+        for (source, up) in self.pending_slides.iter().flatten() {
+            let note = &mut self.notes[*source];
+            note.slide_unpitch_to = if *up {
+                note.fret + UNPITCHED_SLIDE_FRETS
+            } else {
+                (note.fret - UNPITCHED_SLIDE_FRETS).max(0)
+            };
+            note.techniques.push(Techniques::Slide);
+        }
+
+        let labels = self.labels.unwrap_or_default();
+        let instrument = if labels.len() <= 5 {
+            TabsInstrument::Bass
+        } else {
+            TabsInstrument::Guitar
+        };
+        let tuning: Vec<i32> = standard_open_strings(instrument, labels.len())
+            .into_iter()
+            .zip(&labels)
+            .map(|(standard, label)| match label {
+                Some(pitch_class) => standard + (pitch_class - standard + 6).rem_euclid(12) - 6,
+                None => standard,
+            })
+            .collect();
+
+        let mut notes = self.notes;
+        notes.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.string.cmp(&b.string)));
+        let mut chords = ChordTemplates::default();
+        let mut start = 0;
+        while start < notes.len() {
+            let end = start
+                + notes[start..]
+                    .iter()
+                    .take_while(|note| note.time == notes[start].time)
+                    .count();
+            if end - start > 1 {
+                let mut frets = vec![-1; tuning.len()];
+                for note in &notes[start..end] {
+                    frets[note.string as usize] = note.fret;
+                }
+                let chord_index = chords.index_for("", frets, vec![-1; tuning.len()]);
+                for note in &mut notes[start..end] {
+                    note.chord_index = chord_index;
+                    note.techniques.insert(0, Techniques::Chord);
+                }
+            }
+            start = end;
+        }
+
+        let name = self.name.unwrap_or_else(|| match instrument {
+            TabsInstrument::Bass => "Bass".to_string(),
+            _ => "Lead".to_string(),
+        });
+        let metadata = string_arrangement_metadata(
+            &name,
+            instrument,
+            &tuning,
+            self.capo,
+            used_techniques(&notes),
+        );
+        let tab = StringTab {
+            sections: sections_from_markers(&self.markers, self.time),
+            chords: chords.into_chords(),
+            note_charts: vec![TabNoteChart {
+                difficulty: 0,
+                notes,
+            }],
+        };
+        (name, metadata, tab)
+    }
+}
+
+// Index of the first `|` of a staff line, when the line is one.
+fn staff_start(line: &[char]) -> Option<usize> {
+    let bar = line.iter().position(|c| *c == '|')?;
+    let label: String = line[..bar].iter().collect();
+    let label = label.trim();
+    if label.chars().count() > 3 || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '#') {
+        return None;
+    }
+    let rest = &line[bar..];
+    let dashes = rest.iter().filter(|c| **c == '-').count();
+    let is_staff = rest
+        .iter()
+        .all(|c| c.is_ascii_digit() || STAFF_CHARS.contains(*c));
+    (is_staff && dashes >= 4).then_some(bar)
+}
+
+fn label_pitch_class(line: &[char]) -> Option<i32> {
+    let bar = line.iter().position(|c| *c == '|')?;
+    let mut label = line[..bar].iter().filter(|c| !c.is_whitespace());
+    let letter = label.next()?.to_ascii_uppercase();
+    let natural = NOTE_NAMES
+        .iter()
+        .position(|name| name.starts_with(letter) && name.len() == 1)?;
+    let accidental = match label.next() {
+        Some('#') => 1,
+        Some('b') => -1,
+        _ => 0,
+    };
+    Some((natural as i32 + accidental).rem_euclid(12))
+}
+
+// Column ranges between barlines, exclusive at both ends, that hold notes or dashes.
+fn staff_measures(line: &[char]) -> Vec<(usize, usize)> {
+    let mut bars: Vec<usize> = line
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c == '|')
+        .map(|(index, _)| index)
+        .collect();
+    let last = line.iter().rposition(|c| !c.is_whitespace()).unwrap_or(0);
+    if bars.last().is_some_and(|bar| *bar < last) {
+        bars.push(last + 1);
+    }
+    bars.windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .filter(|(start, end)| {
+            line[start + 1..*end]
+                .iter()
+                .any(|c| *c == '-' || c.is_ascii_digit())
+        })
+        .collect()
+}
+
+fn read_staff_line(line: &[char], start: usize) -> Vec<WrittenNote> {
+    let mut notes = Vec::new();
+    let mut legato: Option<(usize, Techniques)> = None;
+    let mut index = start;
+    while index < line.len() {
+        let c = line[index];
+        if let Some((fret, next)) = read_fret(line, index) {
+            let (column, legato) = match legato.take() {
+                Some((column, technique)) => (column, Some(technique)),
+                None => (index, None),
+            };
+            let mut note = WrittenNote {
+                column,
+                fret,
+                legato,
+                bend: None,
+                vibrato: false,
+                slide: None,
+            };
+            index = next;
+            while let Some(&suffix) = line.get(index) {
+                match suffix {
+                    'b' => {
+                        let (target, next) = read_bracketed_fret(line, index + 1);
+                        note.bend = Some(
+                            target
+                                .map(|target| (target - fret) as f32)
+                                .filter(|bend| *bend > 0.0)
+                                .unwrap_or(FULL_BEND),
+                        );
+                        index = next;
+                    }
+                    'r' => index = read_bracketed_fret(line, index + 1).1,
+                    '~' => {
+                        note.vibrato = true;
+                        index += 1;
+                    }
+                    '/' | '\\' | 's' => {
+                        note.slide = Some(suffix != '\\');
+                        index += 1;
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+            notes.push(note);
+            continue;
+        }
+        if matches!(c, 'h' | 'H' | 'p' | 'P') && read_fret(line, index + 1).is_some() {
+            let technique = if c.eq_ignore_ascii_case(&'h') {
+                Techniques::HammerOn
+            } else {
+                Techniques::PullOff
+            };
+            legato = Some((index, technique));
+        }
+        index += 1;
+    }
+    notes
+}
+
+// A fret of one or two digits at `index`, and the index after it.
+fn read_fret(line: &[char], index: usize) -> Option<(i32, usize)> {
+    let digits: String = line
+        .get(index..)?
+        .iter()
+        .take(2)
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    let fret = digits.parse().ok()?;
+    Some((fret, index + digits.len()))
+}
+
+// A fret after a bend or release marker, optionally in parentheses.
+fn read_bracketed_fret(line: &[char], index: usize) -> (Option<i32>, usize) {
+    let open = line.get(index) == Some(&'(');
+    let start = index + usize::from(open);
+    let Some((fret, mut next)) = read_fret(line, start) else {
+        return (None, start);
+    };
+    if open && line.get(next) == Some(&')') {
+        next += 1;
+    }
+    (Some(fret), next)
+}
+
+fn palm_mute_start(row: &[char], from: usize) -> Option<(usize, usize)> {
+    (from..row.len()).find_map(|index| {
+        PALM_MUTE_MARKERS.iter().find_map(|marker| {
+            let matches = marker
+                .chars()
+                .enumerate()
+                .all(|(offset, c)| row.get(index + offset) == Some(&c));
+            matches.then_some((index, index + marker.len()))
+        })
+    })
+}
+
+// Whether `column` falls under a `PM` marker or the dashes that extend it.
+fn is_palm_muted(row: &[char], column: usize) -> bool {
+    let mut from = 0;
+    while let Some((start, marker_end)) = palm_mute_start(row, from) {
+        let end = marker_end
+            + row[marker_end..]
+                .iter()
+                .take_while(|c| matches!(c, '-' | '|' | '.'))
+                .count();
+        if column >= start && column < end {
+            return true;
+        }
+        from = end.max(start + 1);
+    }
+    false
+}
+
+fn first_number(text: &str) -> Option<f64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let number: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.parse().ok()
+}
+
+pub fn export_ascii_tab(song: &ImportedSong) -> Result<String, ConvertError> {
+    let metadata = &song.metadata;
+    let mut text = String::new();
+    for (key, value) in [
+        ("Title", &metadata.title),
+        ("Artist", &metadata.artist),
+        ("Album", &metadata.album),
+    ] {
+        if !value.is_empty() {
+            text.push_str(&format!("{key}: {value}\n"));
+        }
+    }
+    text.push_str(&format!(
+        "BPM: {EXPORT_TEMPO}\nTime: {EXPORT_QUARTERS_PER_MEASURE}/4\n"
+    ));
+
+    let mut written = false;
+    for arrangement in &song.arrangements {
+        let Tab::Strings(tab) = &arrangement.tab else {
+            continue;
+        };
+        let Some(arrangement_metadata) = metadata.arrangements.get(&arrangement.key) else {
+            continue;
+        };
+        let Some(chart) = tab.note_charts.iter().max_by_key(|chart| chart.difficulty) else {
+            continue;
+        };
+        if InstrumentTuning::from_arrangement(arrangement_metadata)
+            .open_strings
+            .is_empty()
+        {
+            continue;
+        }
+        text.push_str(&format!("\nArrangement: {}\n", arrangement_metadata.name));
+        if let Some(capo) = arrangement_metadata.capo_fret.filter(|capo| *capo > 0) {
+            text.push_str(&format!("Capo: {capo}\n"));
+        }
+        text.push('\n');
+        text.push_str(&render_ascii_tab(tab, chart, arrangement_metadata));
+        written = true;
+    }
+    if !written {
+        return Err(ConvertError::NoStringTracks);
+    }
+    Ok(text)
+}
+
+pub fn export_ascii_tab_file(song: &ImportedSong, path: &Path) -> Result<(), ConvertError> {
+    fs::write(path, export_ascii_tab(song)?)?;
+    Ok(())
+}
+
+// Frets are written relative to the capo. Sustains and techniques without an ASCII mark
+// are dropped.
+pub fn render_ascii_tab(
+    tab: &StringTab,
+    chart: &TabNoteChart,
+    arrangement: &SongArrangementMetadata,
+) -> String {
+    let tuning = InstrumentTuning::from_arrangement(arrangement);
+    let string_count = tuning.open_strings.len();
+    let mut labels: Vec<String> = tuning
+        .open_strings
+        .iter()
+        .map(|open| NOTE_NAMES[open.rem_euclid(12) as usize].to_string())
+        .collect();
+    if string_count >= 6 {
+        if let Some(top) = labels.last_mut() {
+            *top = top.to_lowercase();
+        }
+    }
+    let label_width = labels.iter().map(String::len).max().unwrap_or(1);
+
+    let seconds_per_quarter = 60.0 / EXPORT_TEMPO;
+    let measure_seconds = seconds_per_quarter * EXPORT_QUARTERS_PER_MEASURE as f64;
+    let tick_seconds = seconds_per_quarter / EXPORT_TICKS_PER_QUARTER as f64;
+    let ticks_per_measure = EXPORT_TICKS_PER_QUARTER * EXPORT_QUARTERS_PER_MEASURE;
+
+    let mut by_measure: Vec<Vec<&TabNote>> = vec![Vec::new()];
+    for note in chart
+        .notes
+        .iter()
+        .filter(|note| note.string >= 0 && (note.string as usize) < string_count)
+    {
+        let tick = (note.time as f64 / tick_seconds).round().max(0.0) as i64;
+        let measure = (tick / ticks_per_measure) as usize;
+        if by_measure.len() <= measure {
+            by_measure.resize_with(measure + 1, Vec::new);
+        }
+        by_measure[measure].push(note);
+    }
+    let measures: Vec<ExportMeasure> = by_measure
+        .iter()
+        .enumerate()
+        .map(|(index, notes)| {
+            ExportMeasure::new(index as f64 * measure_seconds, notes, tuning.capo_fret)
+        })
+        .collect();
+
+    // Sections are rounded down to the measure they start in.
+    let mut groups: Vec<(Option<&str>, usize)> = Vec::new();
+    for section in &tab.sections {
+        let measure = ((section.start_time as f64 / measure_seconds)
+            .floor()
+            .max(0.0) as usize)
+            .min(measures.len());
+        if groups.last().is_some_and(|(_, start)| *start == measure) {
+            groups.pop();
+        }
+        groups.push((Some(section.name.as_str()), measure));
+    }
+    if groups.first().is_none_or(|(_, start)| *start > 0) {
+        groups.insert(0, (None, 0));
+    }
+
+    let mut text = String::new();
+    for (index, (name, first)) in groups.iter().enumerate() {
+        let end = groups
+            .get(index + 1)
+            .map_or(measures.len(), |(_, next)| *next);
+        if let Some(name) = name {
+            text.push_str(&format!("[{name}]\n"));
+        }
+        let mut line_start = *first;
+        while line_start < end {
+            let mut width = label_width + 1 + measures[line_start].width() + 1;
+            let mut line_end = line_start + 1;
+            while line_end < end && width + measures[line_end].width() < EXPORT_LINE_WIDTH {
+                width += measures[line_end].width() + 1;
+                line_end += 1;
+            }
+            write_staff(
+                &mut text,
+                &labels,
+                label_width,
+                &measures[line_start..line_end],
+            );
+            line_start = line_end;
+        }
+    }
+    text
+}
+
+// One bar of an export. Each bar gets its own grid and slot width, so a busy bar does not
+// widen the rest; the importer spreads every bar evenly whatever its width.
+struct ExportMeasure<'a> {
+    slots: usize,
+    slot_width: usize,
+    notes: Vec<(usize, &'a TabNote, String)>,
+}
+
+impl<'a> ExportMeasure<'a> {
+    fn new(start: f64, notes: &[&'a TabNote], capo: i32) -> Self {
+        let seconds_per_quarter = 60.0 / EXPORT_TEMPO;
+        let subdivision = EXPORT_SUBDIVISIONS
+            .iter()
+            .copied()
+            .find(|&subdivision| {
+                let slot = seconds_per_quarter / subdivision as f64;
+                notes.iter().all(|note| {
+                    let slots = (note.time as f64 - start) / slot;
+                    (slots - slots.round()).abs() * slot < EXPORT_GRID_TOLERANCE
+                })
+            })
+            .unwrap_or(EXPORT_SUBDIVISIONS[EXPORT_SUBDIVISIONS.len() - 1]);
+        let slots = (EXPORT_QUARTERS_PER_MEASURE * subdivision) as usize;
+        let slot_seconds = seconds_per_quarter / subdivision as f64;
+
+        let notes: Vec<(usize, &TabNote, String)> = notes
+            .iter()
+            .map(|note| {
+                let slot = ((note.time as f64 - start) / slot_seconds).round().max(0.0) as usize;
+                (slot.min(slots - 1), *note, note_token(note, capo))
+            })
+            .collect();
+        let slot_width = notes
+            .iter()
+            .map(|(_, _, token)| token.len() + 1)
+            .max()
+            .unwrap_or(2)
+            .max(2);
+        ExportMeasure {
+            slots,
+            slot_width,
+            notes,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.slots * self.slot_width
+    }
+}
+
+// Writes one line of staves, highest string on top, with a `PM` line above when needed.
+fn write_staff(
+    text: &mut String,
+    labels: &[String],
+    label_width: usize,
+    measures: &[ExportMeasure],
+) {
+    let mut rows: Vec<Vec<char>> = labels
+        .iter()
+        .map(|label| format!("{label:<label_width$}|").chars().collect())
+        .collect();
+    // (column, palm muted) of every written note.
+    let mut columns: Vec<(usize, bool)> = Vec::new();
+    for measure in measures {
+        let start = rows[0].len();
+        for row in &mut rows {
+            row.extend(std::iter::repeat_n('-', measure.width()));
+            row.push('|');
+        }
+        for (slot, note, token) in &measure.notes {
+            let row = &mut rows[note.string as usize];
+            let column = start + slot * measure.slot_width;
+            if row[column] != '-' {
+                continue;
+            }
+            for (offset, c) in token.chars().enumerate() {
+                row[column + offset] = c;
+            }
+            columns.push((column, note.techniques.contains(&Techniques::PalmMute)));
+        }
+    }
+
+    columns.sort();
+    columns.dedup_by(|later, earlier| {
+        let same = later.0 == earlier.0;
+        if same {
+            earlier.1 |= later.1;
+        }
+        same
+    });
+    let mut palm_mute_row = vec![' '; rows[0].len()];
+    let mut run_start: Option<usize> = None;
+    for (position, (column, muted)) in columns.iter().enumerate() {
+        if !muted {
+            continue;
+        }
+        let start = *run_start.get_or_insert(*column);
+        if start == *column {
+            palm_mute_row[start] = 'P';
+            palm_mute_row[start + 1] = 'M';
+        } else {
+            for cell in &mut palm_mute_row[start + 2..=*column] {
+                *cell = '-';
+            }
+        }
+        if !columns.get(position + 1).is_some_and(|(_, next)| *next) {
+            run_start = None;
+        }
+    }
+    if palm_mute_row.contains(&'P') {
+        let row: String = palm_mute_row.iter().collect();
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    for row in rows.iter().rev() {
+        text.extend(row.iter());
+        text.push('\n');
+    }
+    text.push('\n');
+}
+
+// A note as written on its staff line: legato prefix, fret, then bend, vibrato and slide.
+fn note_token(note: &TabNote, capo: i32) -> String {
+    let written = |fret: i32| {
+        if fret > 0 {
+            (fret - capo).max(0)
+        } else {
+            fret
+        }
+    };
+    let fret = written(note.fret);
+    let mut token = String::new();
+    if note.techniques.contains(&Techniques::HammerOn) {
+        token.push('h');
+    } else if note.techniques.contains(&Techniques::PullOff) {
+        token.push('p');
+    }
+    token.push_str(&fret.to_string());
+    if note.max_bend > 0.0 {
+        token.push_str(&format!("b{}", fret + note.max_bend.round() as i32));
+    }
+    if note.vibrato > 0 || note.techniques.contains(&Techniques::Vibrato) {
+        token.push('~');
+    }
+    let slide_target = if note.slide_to >= 0 {
+        note.slide_to
+    } else {
+        note.slide_unpitch_to
+    };
+    if slide_target >= 0 {
+        token.push(if slide_target >= note.fret { '/' } else { '\\' });
+    }
+    token
+}
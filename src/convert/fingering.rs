@@ -0,0 +1,345 @@
+use crate::input::InstrumentTuning;
+
+pub const MAX_FRET: i32 = 24;
+// Frets the hand reaches from its anchor without shifting, counting the anchor fret.
+const HAND_SPAN: i32 = 4;
+// Only the cheapest shapes of each chord are carried into the search.
+const MAX_SHAPES: usize = 32;
+
+const SHIFT_COST: f32 = 1.0;
+const SHIFT_DISTANCE_COST: f32 = 0.3;
+const STRETCH_COST: f32 = 3.0;
+const HIGH_POSITION_COST: f32 = 0.05;
+const DROPPED_NOTE_COST: f32 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FretPosition {
+    pub string: i32,
+    pub fret: i32,
+}
+
+/// Where each pitch of a group is played, and where the hand sits while playing it.
+#[derive(Debug, Clone)]
+pub struct Fingering {
+    /// One entry per input pitch; `None` when no free string can reach it.
+    pub positions: Vec<Option<FretPosition>>,
+    /// Lowest fret under the index finger, or -1 before the first fretted note.
+    pub anchor_fret: i32,
+}
+
+struct Shape {
+    positions: Vec<Option<FretPosition>>,
+    // Lowest and highest fretted note; `None` when everything is open or dropped.
+    window: Option<(i32, i32)>,
+    cost: f32,
+}
+
+struct Step {
+    cost: f32,
+    shape: usize,
+    anchor: Option<i32>,
+    previous: usize,
+}
+
+/// Picks a string and fret for every pitch in `groups`, where each group holds the MIDI
+/// pitches struck together. The whole passage is solved at once so the hand shifts as
+/// little as possible, preferring low positions and shapes that fit under four fingers.
+pub fn solve_fingering(groups: &[Vec<i32>], tuning: &InstrumentTuning) -> Vec<Fingering> {
+    let shapes: Vec<Vec<Shape>> = groups
+        .iter()
+        .map(|pitches| chord_shapes(pitches, tuning))
+        .collect();
+
+    let start = [Step {
+        cost: 0.0,
+        shape: 0,
+        anchor: None,
+        previous: 0,
+    }];
+    let mut steps: Vec<Vec<Step>> = Vec::with_capacity(shapes.len());
+    for (index, group_shapes) in shapes.iter().enumerate() {
+        let previous_steps = match index {
+            0 => &start[..],
+            _ => &steps[index - 1][..],
+        };
+        let mut group_steps: Vec<Step> = Vec::new();
+        for (shape_index, shape) in group_shapes.iter().enumerate() {
+            for (previous, step) in previous_steps.iter().enumerate() {
+                for anchor in hand_anchors(shape.window, step.anchor) {
+                    let cost = step.cost + shape.cost + shift_cost(step.anchor, anchor);
+                    match group_steps
+                        .iter_mut()
+                        .find(|other| other.shape == shape_index && other.anchor == anchor)
+                    {
+                        Some(other) if other.cost <= cost => {}
+                        Some(other) => {
+                            other.cost = cost;
+                            other.previous = previous;
+                        }
+                        None => group_steps.push(Step {
+                            cost,
+                            shape: shape_index,
+                            anchor,
+                            previous,
+                        }),
+                    }
+                }
+            }
+        }
+        steps.push(group_steps);
+    }
+
+    let mut chosen = vec![0; steps.len()];
+    if let Some(last) = steps.last() {
+        let mut index = last
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.cost.total_cmp(&b.1.cost))
+            .map_or(0, |(index, _)| index);
+        for group in (0..steps.len()).rev() {
+            chosen[group] = index;
+            index = steps[group][index].previous;
+        }
+    }
+
+    let first_anchor = chosen
+        .iter()
+        .enumerate()
+        .find_map(|(group, &index)| steps[group][index].anchor)
+        .unwrap_or(-1);
+    shapes
+        .into_iter()
+        .zip(chosen)
+        .enumerate()
+        .map(|(group, (mut group_shapes, index))| {
+            let step = &steps[group][index];
+            Fingering {
+                anchor_fret: step.anchor.unwrap_or(first_anchor),
+                positions: group_shapes.swap_remove(step.shape).positions,
+            }
+        })
+        .collect()
+}
+
+/// Frets where `pitch` can be played on each string, respecting the capo.
+pub fn fret_positions(pitch: i32, tuning: &InstrumentTuning) -> Vec<FretPosition> {
+    let capo = tuning.capo_fret;
+    tuning
+        .open_strings
+        .iter()
+        .enumerate()
+        .filter_map(|(string, open)| {
+            let fret = pitch - open;
+            let fret = if fret == capo {
+                0
+            } else if fret > capo && fret <= MAX_FRET {
+                fret
+            } else {
+                return None;
+            };
+            Some(FretPosition {
+                string: string as i32,
+                fret,
+            })
+        })
+        .collect()
+}
+
+// Where the index finger can sit to play `window`; open shapes leave the hand in place.
+fn hand_anchors(window: Option<(i32, i32)>, current: Option<i32>) -> Vec<Option<i32>> {
+    match window {
+        None => vec![current],
+        Some((low, high)) if high - low < HAND_SPAN => {
+            ((high - HAND_SPAN + 1).max(1)..=low).map(Some).collect()
+        }
+        Some((low, _)) => vec![Some(low)],
+    }
+}
+
+fn shift_cost(from: Option<i32>, to: Option<i32>) -> f32 {
+    match (from, to) {
+        (Some(from), Some(to)) if from != to => {
+            SHIFT_COST + SHIFT_DISTANCE_COST * (to - from).abs() as f32
+        }
+        _ => 0.0,
+    }
+}
+
+// Every way to put the pitches on distinct strings, cheapest first.
+fn chord_shapes(pitches: &[i32], tuning: &InstrumentTuning) -> Vec<Shape> {
+    let options: Vec<Vec<FretPosition>> = pitches
+        .iter()
+        .map(|&pitch| fret_positions(pitch, tuning))
+        .collect();
+    let mut shapes = Vec::new();
+    let mut current = Vec::with_capacity(pitches.len());
+    collect_shapes(&options, &mut current, &mut shapes);
+    shapes.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+    shapes.truncate(MAX_SHAPES);
+    shapes
+}
+
+fn collect_shapes(
+    options: &[Vec<FretPosition>],
+    current: &mut Vec<Option<FretPosition>>,
+    shapes: &mut Vec<Shape>,
+) {
+    let Some(choices) = options.get(current.len()) else {
+        shapes.push(score_shape(current.clone()));
+        return;
+    };
+    let mut placed = false;
+    for &position in choices {
+        let taken = current
+            .iter()
+            .flatten()
+            .any(|other| other.string == position.string);
+        if !taken {
+            current.push(Some(position));
+            collect_shapes(options, current, shapes);
+            current.pop();
+            placed = true;
+        }
+    }
+    // Only drop a note when it has nowhere to go.
+    if !placed {
+        current.push(None);
+        collect_shapes(options, current, shapes);
+        current.pop();
+    }
+}
+
+fn score_shape(positions: Vec<Option<FretPosition>>) -> Shape {
+    let window = positions
+        .iter()
+        .flatten()
+        .map(|position| position.fret)
+        .filter(|&fret| fret > 0)
+        .fold(None, |window: Option<(i32, i32)>, fret| match window {
+            Some((low, high)) => Some((low.min(fret), high.max(fret))),
+            None => Some((fret, fret)),
+        });
+    let dropped = positions
+        .iter()
+        .filter(|position| position.is_none())
+        .count();
+
+    let mut cost = DROPPED_NOTE_COST * dropped as f32;
+    if let Some((low, high)) = window {
+        cost += STRETCH_COST * (high - low + 1 - HAND_SPAN).max(0) as f32;
+        cost += HIGH_POSITION_COST * low as f32;
+    }
+    Shape {
+        positions,
+        window,
+        cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::song::TabsInstrument;
+    use crate::input::standard_open_strings;
+
+    fn guitar(offsets: [i32; 6], capo_fret: i32) -> InstrumentTuning {
+        let open_strings = standard_open_strings(TabsInstrument::Guitar, 6)
+            .into_iter()
+            .zip(offsets)
+            .map(|(open, offset)| open + offset)
+            .collect();
+        InstrumentTuning {
+            open_strings,
+            capo_fret,
+        }
+    }
+
+    fn solve(groups: &[&[i32]], tuning: &InstrumentTuning) -> Vec<Vec<Option<(i32, i32)>>> {
+        let groups: Vec<Vec<i32>> = groups.iter().map(|group| group.to_vec()).collect();
+        solve_fingering(&groups, tuning)
+            .into_iter()
+            .map(|fingering| {
+                fingering
+                    .positions
+                    .into_iter()
+                    .map(|position| position.map(|p| (p.string, p.fret)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scale_stays_in_open_position() {
+        let standard = guitar([0; 6], 0);
+        let scale: [&[i32]; 8] = [&[48], &[50], &[52], &[53], &[55], &[57], &[59], &[60]];
+        assert_eq!(
+            solve(&scale, &standard),
+            [
+                [Some((1, 3))],
+                [Some((2, 0))],
+                [Some((2, 2))],
+                [Some((2, 3))],
+                [Some((3, 0))],
+                [Some((3, 2))],
+                [Some((4, 0))],
+                [Some((4, 1))],
+            ]
+        );
+        let groups: Vec<Vec<i32>> = scale.iter().map(|group| group.to_vec()).collect();
+        assert!(solve_fingering(&groups, &standard)
+            .iter()
+            .all(|fingering| fingering.anchor_fret == 1));
+    }
+
+    #[test]
+    fn drop_d_reaches_the_low_d() {
+        assert_eq!(solve(&[&[38]], &guitar([0; 6], 0)), [[None]]);
+        assert_eq!(
+            solve(&[&[38], &[41], &[45]], &guitar([-2, 0, 0, 0, 0, 0], 0)),
+            [[Some((0, 0))], [Some((0, 3))], [Some((1, 0))]]
+        );
+    }
+
+    #[test]
+    fn capo_fret_plays_as_open() {
+        let capo = guitar([0; 6], 2);
+        assert!(fret_positions(41, &capo).is_empty());
+        assert_eq!(
+            solve(&[&[42], &[47], &[49]], &capo),
+            [[Some((0, 0))], [Some((1, 0))], [Some((1, 4))]]
+        );
+    }
+
+    #[test]
+    fn out_of_range_pitches_are_dropped() {
+        assert_eq!(
+            solve(&[&[30], &[89], &[88]], &guitar([0; 6], 0)),
+            [[None], [None], [Some((5, 24))]]
+        );
+    }
+
+    #[test]
+    fn chords_spread_across_strings() {
+        let standard = guitar([0; 6], 0);
+        assert_eq!(
+            solve(&[&[40, 47, 52, 56, 59, 64]], &standard),
+            [[
+                Some((0, 0)),
+                Some((1, 2)),
+                Some((2, 2)),
+                Some((3, 1)),
+                Some((4, 0)),
+                Some((5, 0)),
+            ]]
+        );
+        assert_eq!(
+            solve(&[&[43, 50, 55]], &standard),
+            [[Some((0, 3)), Some((1, 5)), Some((2, 5))]]
+        );
+        // Both pitches only fit on the low string, so one has to go.
+        assert_eq!(
+            solve(&[&[38, 40]], &guitar([-2, 0, 0, 0, 0, 0], 0)),
+            [[Some((0, 0)), None]]
+        );
+    }
+}
@@ -0,0 +1,980 @@
+use std::fs;
+use std::path::Path;
+
+use crate::convert::{
+    guess_instrument, plain_note, playback_order, sections_from_markers, single_chart_tab,
+    string_arrangement_metadata, used_techniques, ChordTemplates, ConvertError, ImportedSong,
+    MeasureRepeat, TempoMap, DEFAULT_TEMPO, MIN_SUSTAIN_QUARTERS, UNPITCHED_SLIDE_FRETS,
+};
+use crate::file::song::{Tab, TabChord, TabNote, Techniques};
+
+const CHANNEL_COUNT: usize = 64;
+const MAX_STRINGS: usize = 7;
+// Bend points are stored in 1/50 of a semitone.
+const BEND_UNITS_PER_SEMITONE: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum GpVersion {
+    V3,
+    V4,
+    V5_00,
+    V5_10,
+}
+
+impl GpVersion {
+    fn parse(version: &str) -> Option<Self> {
+        let number = version.rsplit(' ').next()?.get(1..)?;
+        match number {
+            "3.00" => Some(GpVersion::V3),
+            "4.00" | "4.06" => Some(GpVersion::V4),
+            "5.00" => Some(GpVersion::V5_00),
+            "5.10" => Some(GpVersion::V5_10),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a Guitar Pro 3, 4 or 5 file and converts every string track into an arrangement.
+pub fn import_guitar_pro_file(path: &Path) -> Result<ImportedSong, ConvertError> {
+    let bytes = fs::read(path)?;
+    import_guitar_pro(&bytes)
+}
+
+/// Converts Guitar Pro 3/4/5 data. Repeats are unrolled so note times follow the
+/// recording; percussion tracks are skipped.
+pub fn import_guitar_pro(bytes: &[u8]) -> Result<ImportedSong, ConvertError> {
+    let song = GpReader::new(bytes).read_song()?;
+    let timeline = SongTimeline::new(&song);
+
+    let markers: Vec<(String, f32)> = timeline
+        .order
+        .iter()
+        .enumerate()
+        .filter_map(|(position, &measure)| {
+            let name = song.headers[measure].marker.clone()?;
+            Some((
+                name,
+                timeline.seconds_at(timeline.measure_starts[position]) as f32,
+            ))
+        })
+        .collect();
+    let song_end = timeline.seconds_at(timeline.end) as f32;
+
+    let mut imported = ImportedSong::new(&song.title, &song.artist, &song.album, song_end);
+    for track in song.tracks.iter().filter(|track| !track.percussion) {
+        let (notes, chords) = convert_track(track, &timeline);
+        if notes.is_empty() {
+            continue;
+        }
+        let tuning: Vec<i32> = track.tuning.iter().rev().copied().collect();
+        let metadata = string_arrangement_metadata(
+            &track.name,
+            guess_instrument(&tuning),
+            &tuning,
+            track.capo,
+            used_techniques(&notes),
+        );
+        let sections = sections_from_markers(&markers, song_end);
+        imported.add_arrangement(
+            &track.name,
+            metadata,
+            Tab::Strings(single_chart_tab(sections, chords, notes)),
+        );
+    }
+
+    if imported.arrangements.is_empty() {
+        return Err(ConvertError::NoStringTracks);
+    }
+    Ok(imported)
+}
+
+struct GpSong {
+    title: String,
+    artist: String,
+    album: String,
+    tempo: i32,
+    headers: Vec<MeasureHeader>,
+    tracks: Vec<GpTrack>,
+}
+
+#[derive(Clone, Default)]
+struct MeasureHeader {
+    numerator: u8,
+    denominator: u8,
+    repeat: MeasureRepeat,
+    marker: Option<String>,
+}
+
+impl MeasureHeader {
+    fn quarters(&self) -> f64 {
+        self.numerator.max(1) as f64 * 4.0 / self.denominator.max(1) as f64
+    }
+}
+
+struct GpTrack {
+    name: String,
+    percussion: bool,
+    // Open-string pitches, highest string first as Guitar Pro stores them.
+    tuning: Vec<i32>,
+    capo: i32,
+    measures: Vec<Vec<Vec<GpBeat>>>,
+}
+
+#[derive(Default)]
+struct GpBeat {
+    quarters: f64,
+    notes: Vec<GpNote>,
+    chord: Option<GpChord>,
+    tempo: Option<i32>,
+    vibrato: bool,
+    stroke: Option<Techniques>,
+}
+
+#[derive(Default)]
+struct GpNote {
+    // 0 is the highest string.
+    string: usize,
+    fret: i32,
+    tied: bool,
+    dead: bool,
+    hammer: bool,
+    vibrato: bool,
+    palm_mute: bool,
+    tremolo: bool,
+    bend: f32,
+    slide: Option<SlideKind>,
+    harmonic: Option<Techniques>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SlideKind {
+    ToNext,
+    OutDown,
+    OutUp,
+}
+
+struct GpChord {
+    name: String,
+    // Per string, highest first; -1 is no finger.
+    fingers: Vec<i32>,
+}
+
+struct GpReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    version: GpVersion,
+}
+
+impl<'a> GpReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        GpReader {
+            data,
+            position: 0,
+            version: GpVersion::V3,
+        }
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ConvertError> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(ConvertError::UnexpectedEof(self.position))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), ConvertError> {
+        self.take(count).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, ConvertError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i8(&mut self) -> Result<i8, ConvertError> {
+        Ok(self.u8()? as i8)
+    }
+
+    fn bool(&mut self) -> Result<bool, ConvertError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn i32(&mut self) -> Result<i32, ConvertError> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // Guitar Pro strings are Latin-1; every byte maps straight to a code point.
+    fn latin1(bytes: &[u8]) -> String {
+        bytes.iter().map(|&byte| byte as char).collect()
+    }
+
+    // A length byte followed by a fixed `size` byte field, or by `length` bytes if `size` is 0.
+    fn byte_size_string(&mut self, size: usize) -> Result<String, ConvertError> {
+        let length = self.u8()? as usize;
+        let field = self.take(if size > 0 { size } else { length })?;
+        Ok(Self::latin1(&field[..length.min(field.len())]))
+    }
+
+    fn int_byte_size_string(&mut self) -> Result<String, ConvertError> {
+        let size = self.i32()?;
+        self.byte_size_string((size - 1).max(0) as usize)
+    }
+
+    fn int_size_string(&mut self) -> Result<String, ConvertError> {
+        let length = self.i32()?.max(0) as usize;
+        Ok(Self::latin1(self.take(length)?))
+    }
+
+    fn read_song(mut self) -> Result<GpSong, ConvertError> {
+        let version = self.byte_size_string(30)?;
+        self.version = GpVersion::parse(&version).ok_or(ConvertError::UnsupportedVersion {
+            format: "Guitar Pro",
+            version: version.clone(),
+        })?;
+
+        let title = self.int_byte_size_string()?;
+        self.int_byte_size_string()?; // subtitle
+        let artist = self.int_byte_size_string()?;
+        let album = self.int_byte_size_string()?;
+        self.int_byte_size_string()?; // words
+        if self.version >= GpVersion::V5_00 {
+            self.int_byte_size_string()?; // music
+        }
+        self.int_byte_size_string()?; // copyright
+        self.int_byte_size_string()?; // tab author
+        self.int_byte_size_string()?; // instructions
+        let notice_lines = self.i32()?;
+        for _ in 0..notice_lines.max(0) {
+            self.int_byte_size_string()?;
+        }
+
+        if self.version < GpVersion::V5_00 {
+            self.bool()?; // triplet feel
+        }
+        if self.version >= GpVersion::V4 {
+            self.i32()?; // lyrics track
+            for _ in 0..5 {
+                self.i32()?;
+                self.int_size_string()?;
+            }
+        }
+        if self.version >= GpVersion::V5_00 {
+            self.read_page_setup()?;
+        }
+
+        let tempo = self.i32()?;
+        if self.version == GpVersion::V5_10 {
+            self.skip(1)?; // hide tempo
+        }
+        self.i32()?; // key signature
+        if self.version >= GpVersion::V4 {
+            self.i8()?; // octave
+        }
+        self.skip(CHANNEL_COUNT * 12)?;
+        if self.version >= GpVersion::V5_00 {
+            self.skip(42)?; // musical directions and master reverb
+        }
+
+        let measure_count = self.i32()?.max(0) as usize;
+        let track_count = self.i32()?.max(0) as usize;
+        let headers = self.read_measure_headers(measure_count)?;
+        let mut tracks = (1..=track_count)
+            .map(|number| self.read_track(number))
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.version >= GpVersion::V5_00 {
+            self.skip(if self.version == GpVersion::V5_00 {
+                2
+            } else {
+                1
+            })?;
+        }
+
+        for _ in &headers {
+            for track in &mut tracks {
+                let measure = self.read_measure(track.tuning.len())?;
+                track.measures.push(measure);
+            }
+        }
+
+        Ok(GpSong {
+            title,
+            artist,
+            album,
+            tempo,
+            headers,
+            tracks,
+        })
+    }
+
+    fn read_page_setup(&mut self) -> Result<(), ConvertError> {
+        self.skip(if self.version == GpVersion::V5_10 {
+            49
+        } else {
+            30
+        })?;
+        for _ in 0..11 {
+            self.i32()?;
+            self.byte_size_string(0)?;
+        }
+        Ok(())
+    }
+
+    fn read_measure_headers(&mut self, count: usize) -> Result<Vec<MeasureHeader>, ConvertError> {
+        // The count comes from the file, so it is not trusted for pre-allocation.
+        let mut headers: Vec<MeasureHeader> = Vec::new();
+        for index in 0..count {
+            let is_v5 = self.version >= GpVersion::V5_00;
+            if is_v5 && index > 0 {
+                self.skip(1)?;
+            }
+            let mut header = headers.last().cloned().unwrap_or(MeasureHeader {
+                numerator: 4,
+                denominator: 4,
+                ..Default::default()
+            });
+            header.repeat = MeasureRepeat::default();
+            header.marker = None;
+
+            let flags = self.u8()?;
+            if flags & 0x01 != 0 {
+                header.numerator = self.u8()?;
+            }
+            if flags & 0x02 != 0 {
+                header.denominator = self.u8()?;
+            }
+            header.repeat.open = flags & 0x04 != 0;
+            if flags & 0x08 != 0 {
+                let count = self.u8()? as i32;
+                // Guitar Pro 5 stores the play count, earlier versions the repeat count.
+                header.repeat.close = if is_v5 { count - 1 } else { count };
+            }
+            if is_v5 {
+                if flags & 0x20 != 0 {
+                    header.marker = Some(self.read_marker()?);
+                }
+                if flags & 0x10 != 0 {
+                    header.repeat.alternatives = self.u8()?;
+                }
+            } else {
+                if flags & 0x10 != 0 {
+                    let ending = self.u8()?;
+                    header.repeat.alternatives = 1u8
+                        .checked_shl(ending.saturating_sub(1) as u32)
+                        .unwrap_or(0);
+                }
+                if flags & 0x20 != 0 {
+                    header.marker = Some(self.read_marker()?);
+                }
+            }
+            if flags & 0x40 != 0 {
+                self.skip(2)?; // key signature
+            }
+            if is_v5 {
+                if flags & 0x03 != 0 {
+                    self.skip(4)?; // beaming
+                }
+                if flags & 0x10 == 0 {
+                    self.skip(1)?;
+                }
+                self.skip(1)?; // triplet feel
+            }
+            headers.push(header);
+        }
+        Ok(headers)
+    }
+
+    fn read_marker(&mut self) -> Result<String, ConvertError> {
+        let name = self.int_byte_size_string()?;
+        self.skip(4)?; // colour
+        Ok(name)
+    }
+
+    fn read_track(&mut self, number: usize) -> Result<GpTrack, ConvertError> {
+        let flags = self.u8()?;
+        if self.version == GpVersion::V5_00 || (self.version == GpVersion::V5_10 && number == 1) {
+            self.skip(1)?;
+        }
+        let name = self.byte_size_string(40)?;
+        let string_count = self.i32()?.clamp(0, MAX_STRINGS as i32) as usize;
+        let mut tuning = Vec::with_capacity(string_count);
+        for string in 0..MAX_STRINGS {
+            let pitch = self.i32()?;
+            if string < string_count {
+                tuning.push(pitch);
+            }
+        }
+        self.i32()?; // port
+        self.i32()?; // channel
+        self.i32()?; // effects channel
+        self.i32()?; // fret count
+        let capo = self.i32()?;
+        self.skip(4)?; // colour
+        if self.version >= GpVersion::V5_00 {
+            self.skip(if self.version == GpVersion::V5_10 {
+                49
+            } else {
+                44
+            })?;
+            if self.version == GpVersion::V5_10 {
+                self.int_byte_size_string()?;
+                self.int_byte_size_string()?;
+            }
+        }
+
+        Ok(GpTrack {
+            name,
+            percussion: flags & 0x01 != 0,
+            tuning,
+            capo: capo.max(0),
+            measures: Vec::new(),
+        })
+    }
+
+    fn read_measure(&mut self, string_count: usize) -> Result<Vec<Vec<GpBeat>>, ConvertError> {
+        let voice_count = if self.version >= GpVersion::V5_00 {
+            2
+        } else {
+            1
+        };
+        let mut voices = Vec::with_capacity(voice_count);
+        for _ in 0..voice_count {
+            let beat_count = self.i32()?.max(0);
+            let beats = (0..beat_count)
+                .map(|_| self.read_beat(string_count))
+                .collect::<Result<Vec<_>, _>>()?;
+            voices.push(beats);
+        }
+        if self.version >= GpVersion::V5_00 {
+            self.skip(1)?; // line break
+        }
+        Ok(voices)
+    }
+
+    fn read_beat(&mut self, string_count: usize) -> Result<GpBeat, ConvertError> {
+        let mut beat = GpBeat::default();
+        let flags = self.u8()?;
+        if flags & 0x40 != 0 {
+            self.u8()?; // empty or rest
+        }
+        beat.quarters = self.read_duration(flags)?;
+        if flags & 0x02 != 0 {
+            beat.chord = Some(self.read_chord(string_count)?);
+        }
+        if flags & 0x04 != 0 {
+            self.int_byte_size_string()?; // text
+        }
+        if flags & 0x08 != 0 {
+            self.read_beat_effects(&mut beat)?;
+        }
+        if flags & 0x10 != 0 {
+            beat.tempo = self.read_mix_table_change()?;
+        }
+
+        let string_flags = self.u8()?;
+        for string in 0..MAX_STRINGS {
+            if string_flags & (1 << (6 - string)) == 0 {
+                continue;
+            }
+            let note = self.read_note(string)?;
+            if string < string_count {
+                beat.notes.push(note);
+            }
+        }
+
+        if self.version >= GpVersion::V5_00 {
+            self.skip(1)?;
+            let flags2 = self.u8()?;
+            if flags2 & 0x08 != 0 {
+                self.skip(1)?;
+            }
+        }
+        Ok(beat)
+    }
+
+    // Length in quarter notes.
+    fn read_duration(&mut self, flags: u8) -> Result<f64, ConvertError> {
+        let exponent = self.i8()?;
+        let mut quarters = 4.0 / 2f64.powi(exponent as i32 + 2);
+        if flags & 0x01 != 0 {
+            quarters *= 1.5;
+        }
+        if flags & 0x20 != 0 {
+            let (enters, times) = match self.i32()? {
+                3 => (3.0, 2.0),
+                5 => (5.0, 4.0),
+                6 => (6.0, 4.0),
+                7 => (7.0, 4.0),
+                9 => (9.0, 8.0),
+                10 => (10.0, 8.0),
+                11 => (11.0, 8.0),
+                12 => (12.0, 8.0),
+                13 => (13.0, 8.0),
+                _ => (1.0, 1.0),
+            };
+            quarters *= times / enters;
+        }
+        Ok(quarters)
+    }
+
+    // Only the name and fingering are kept; the shape itself comes from the notes.
+    fn read_chord(&mut self, string_count: usize) -> Result<GpChord, ConvertError> {
+        let new_format = self.version >= GpVersion::V5_00 || self.u8()? & 0x01 != 0;
+        let (name, mut fingers) = if !new_format {
+            let name = self.int_byte_size_string()?;
+            if self.i32()? != 0 {
+                self.skip(6 * 4)?; // frets
+            }
+            (name, Vec::new())
+        } else if self.version == GpVersion::V3 {
+            self.skip(25)?;
+            let name = self.byte_size_string(34)?;
+            self.skip(4 + 6 * 4 + 36)?; // first fret, frets, barres and omissions
+            (name, Vec::new())
+        } else {
+            self.skip(if self.version >= GpVersion::V5_00 {
+                17
+            } else {
+                16
+            })?;
+            let name = self.byte_size_string(21)?;
+            self.skip(4 + 4 + 7 * 4 + 24)?; // alterations, first fret, frets, barres, omissions
+            let fingers: Vec<i32> = self
+                .take(7)?
+                .iter()
+                .map(|&finger| (finger as i8).max(-1) as i32)
+                .collect();
+            self.skip(1)?;
+            (name, fingers)
+        };
+
+        fingers.resize(string_count, -1);
+        Ok(GpChord {
+            name: name.trim().to_string(),
+            fingers,
+        })
+    }
+
+    fn read_beat_effects(&mut self, beat: &mut GpBeat) -> Result<(), ConvertError> {
+        let flags1 = self.u8()?;
+        let flags2 = if self.version >= GpVersion::V4 {
+            self.u8()?
+        } else {
+            0
+        };
+        beat.vibrato = flags1 & 0x03 != 0;
+
+        if flags1 & 0x20 != 0 {
+            let effect = self.u8()?;
+            beat.stroke = match effect {
+                1 => Some(Techniques::Tap),
+                2 => Some(Techniques::Slap),
+                3 => Some(Techniques::Pop),
+                _ => None,
+            };
+            if self.version == GpVersion::V3 {
+                self.i32()?; // tremolo bar depth or unused
+            }
+        }
+        if flags2 & 0x04 != 0 {
+            self.read_bend()?; // tremolo bar
+        }
+        if flags1 & 0x40 != 0 {
+            self.skip(2)?; // strum up and down speeds
+        }
+        if flags2 & 0x02 != 0 {
+            self.skip(1)?; // pick stroke
+        }
+        Ok(())
+    }
+
+    // Returns the new tempo, if the change sets one.
+    fn read_mix_table_change(&mut self) -> Result<Option<i32>, ConvertError> {
+        self.i8()?; // instrument
+        if self.version >= GpVersion::V5_00 {
+            self.skip(16)?;
+        }
+        let mut values = [0i8; 6];
+        for value in &mut values {
+            *value = self.i8()?;
+        }
+        if self.version >= GpVersion::V5_00 {
+            self.int_byte_size_string()?; // tempo name
+        }
+        let tempo = self.i32()?;
+
+        for value in values {
+            if value >= 0 {
+                self.skip(1)?; // transition length
+            }
+        }
+        if tempo >= 0 {
+            self.skip(1)?;
+            if self.version == GpVersion::V5_10 {
+                self.skip(1)?; // hide tempo
+            }
+        }
+        if self.version >= GpVersion::V4 {
+            self.skip(1)?; // apply to all tracks
+        }
+        if self.version >= GpVersion::V5_00 {
+            self.skip(1)?; // wah
+            if self.version == GpVersion::V5_10 {
+                self.int_byte_size_string()?;
+                self.int_byte_size_string()?;
+            }
+        }
+        Ok((tempo > 0).then_some(tempo))
+    }
+
+    fn read_note(&mut self, string: usize) -> Result<GpNote, ConvertError> {
+        let mut note = GpNote {
+            string,
+            ..Default::default()
+        };
+        let flags = self.u8()?;
+        if flags & 0x20 != 0 {
+            match self.u8()? {
+                2 => note.tied = true,
+                3 => note.dead = true,
+                _ => {}
+            }
+        }
+        if flags & 0x01 != 0 && self.version < GpVersion::V5_00 {
+            self.skip(2)?; // independent duration
+        }
+        if flags & 0x10 != 0 {
+            self.i8()?; // dynamic
+        }
+        if flags & 0x20 != 0 {
+            note.fret = self.i8()? as i32;
+        }
+        if flags & 0x80 != 0 {
+            self.skip(2)?; // fingering
+        }
+        if self.version >= GpVersion::V5_00 {
+            if flags & 0x01 != 0 {
+                self.skip(8)?; // duration percent
+            }
+            self.skip(1)?;
+        }
+        if flags & 0x08 != 0 {
+            self.read_note_effects(&mut note)?;
+        }
+        Ok(note)
+    }
+
+    fn read_note_effects(&mut self, note: &mut GpNote) -> Result<(), ConvertError> {
+        let flags1 = self.u8()?;
+        let flags2 = if self.version >= GpVersion::V4 {
+            self.u8()?
+        } else {
+            0
+        };
+        note.hammer = flags1 & 0x02 != 0;
+        if self.version == GpVersion::V3 && flags1 & 0x04 != 0 {
+            note.slide = Some(SlideKind::ToNext);
+        }
+        if flags1 & 0x01 != 0 {
+            note.bend = self.read_bend()?;
+        }
+        if flags1 & 0x10 != 0 {
+            self.skip(if self.version >= GpVersion::V5_00 {
+                5
+            } else {
+                4
+            })?; // grace note
+        }
+        if flags2 & 0x04 != 0 {
+            self.skip(1)?;
+            note.tremolo = true;
+        }
+        if flags2 & 0x08 != 0 {
+            let slide = self.i8()?;
+            note.slide = if self.version >= GpVersion::V5_00 {
+                match slide {
+                    s if s & 0x03 != 0 => Some(SlideKind::ToNext),
+                    s if s & 0x04 != 0 => Some(SlideKind::OutDown),
+                    s if s & 0x08 != 0 => Some(SlideKind::OutUp),
+                    _ => None,
+                }
+            } else {
+                match slide {
+                    1 | 2 => Some(SlideKind::ToNext),
+                    3 => Some(SlideKind::OutDown),
+                    4 => Some(SlideKind::OutUp),
+                    _ => None,
+                }
+            };
+        }
+        if flags2 & 0x10 != 0 {
+            let kind = self.u8()?;
+            if self.version >= GpVersion::V5_00 {
+                match kind {
+                    2 => self.skip(3)?,
+                    3 => self.skip(1)?,
+                    _ => {}
+                }
+            }
+            note.harmonic = Some(if kind == 4 {
+                Techniques::PinchHarmonic
+            } else {
+                Techniques::Harmonic
+            });
+        }
+        if flags2 & 0x20 != 0 {
+            self.skip(2)?; // trill
+        }
+        note.palm_mute = flags2 & 0x02 != 0;
+        note.vibrato = flags2 & 0x40 != 0;
+        Ok(())
+    }
+
+    // Returns the highest bend in semitones.
+    fn read_bend(&mut self) -> Result<f32, ConvertError> {
+        self.skip(5)?; // type and overall value
+        let points = self.i32()?.max(0);
+        let mut highest = 0;
+        for _ in 0..points {
+            self.i32()?; // position
+            highest = highest.max(self.i32()?);
+            self.skip(1)?; // vibrato
+        }
+        Ok(highest as f32 / BEND_UNITS_PER_SEMITONE)
+    }
+}
+
+// Playback order of the measures with repeats unrolled, and a tempo map to turn quarter
+// note positions into seconds.
+struct SongTimeline {
+    order: Vec<usize>,
+    measure_starts: Vec<f64>,
+    end: f64,
+    tempo_map: TempoMap,
+}
+
+impl SongTimeline {
+    fn new(song: &GpSong) -> Self {
+        let repeats: Vec<MeasureRepeat> = song.headers.iter().map(|header| header.repeat).collect();
+        let order = playback_order(&repeats);
+        let mut measure_starts = Vec::with_capacity(order.len());
+        let mut position = 0.0;
+        for &measure in &order {
+            measure_starts.push(position);
+            position += song.headers[measure].quarters();
+        }
+
+        let mut changes: Vec<(f64, f64)> = Vec::new();
+        for (&measure, &start) in order.iter().zip(&measure_starts) {
+            for track in &song.tracks {
+                let Some(voices) = track.measures.get(measure) else {
+                    continue;
+                };
+                for voice in voices {
+                    let mut offset = 0.0;
+                    for beat in voice {
+                        if let Some(tempo) = beat.tempo {
+                            changes.push((start + offset, tempo as f64));
+                        }
+                        offset += beat.quarters;
+                    }
+                }
+            }
+        }
+
+        let initial = if song.tempo > 0 {
+            song.tempo as f64
+        } else {
+            DEFAULT_TEMPO
+        };
+        SongTimeline {
+            order,
+            measure_starts,
+            end: position,
+            tempo_map: TempoMap::new(initial, changes),
+        }
+    }
+
+    fn seconds_at(&self, quarters: f64) -> f64 {
+        self.tempo_map.seconds_at(quarters)
+    }
+}
+
+struct PendingNote {
+    note: TabNote,
+    gp_fret: i32,
+    end_quarters: f64,
+    start_quarters: f64,
+    hammer: bool,
+    slide: Option<SlideKind>,
+}
+
+fn convert_track(track: &GpTrack, timeline: &SongTimeline) -> (Vec<TabNote>, Vec<TabChord>) {
+    let string_count = track.tuning.len();
+    let mut pending: Vec<PendingNote> = Vec::new();
+    let mut last_on_string: Vec<Option<usize>> = vec![None; string_count];
+    let mut chords = ChordTemplates::default();
+    let absolute = |fret: i32| if fret > 0 { fret + track.capo } else { fret };
+
+    for (&measure, &measure_start) in timeline.order.iter().zip(&timeline.measure_starts) {
+        let Some(voices) = track.measures.get(measure) else {
+            continue;
+        };
+        for voice in voices {
+            let mut offset = 0.0;
+            for beat in voice {
+                let start = measure_start + offset;
+                offset += beat.quarters;
+                let end = start + beat.quarters;
+                let time = timeline.seconds_at(start) as f32;
+
+                let struck: Vec<&GpNote> = beat
+                    .notes
+                    .iter()
+                    .filter(|note| !note.tied && !note.dead)
+                    .collect();
+                let chord_index = if struck.len() > 1 {
+                    let mut frets = vec![-1; string_count];
+                    for note in &struck {
+                        frets[string_count - 1 - note.string] = absolute(note.fret);
+                    }
+                    let (name, fingers) = match &beat.chord {
+                        Some(chord) => (
+                            chord.name.as_str(),
+                            chord.fingers.iter().rev().copied().collect(),
+                        ),
+                        None => ("", vec![-1; string_count]),
+                    };
+                    chords.index_for(name, frets, fingers)
+                } else {
+                    -1
+                };
+
+                for note in &beat.notes {
+                    if note.dead {
+                        continue;
+                    }
+                    let string = string_count - 1 - note.string;
+                    if note.tied {
+                        if let Some(previous) = last_on_string[string] {
+                            pending[previous].end_quarters = end;
+                        }
+                        continue;
+                    }
+
+                    let mut tab_note = plain_note(time, string as i32, absolute(note.fret));
+                    tab_note.chord_index = chord_index;
+                    let techniques = &mut tab_note.techniques;
+                    if chord_index >= 0 {
+                        techniques.push(Techniques::Chord);
+                    }
+                    if note.bend > 0.0 {
+                        techniques.push(Techniques::Bend);
+                        tab_note.max_bend = note.bend;
+                    }
+                    if note.vibrato || beat.vibrato {
+                        techniques.push(Techniques::Vibrato);
+                        tab_note.vibrato = 80;
+                    }
+                    if note.palm_mute {
+                        techniques.push(Techniques::PalmMute);
+                    }
+                    if note.tremolo {
+                        techniques.push(Techniques::Tremolo);
+                    }
+                    if let Some(harmonic) = note.harmonic {
+                        techniques.push(harmonic);
+                    }
+                    match beat.stroke {
+                        Some(Techniques::Tap) => {
+                            techniques.push(Techniques::Tap);
+                            tab_note.tap = 1;
+                        }
+                        Some(Techniques::Slap) => {
+                            techniques.push(Techniques::Slap);
+                            tab_note.slap = 1;
+                        }
+                        Some(Techniques::Pop) => {
+                            techniques.push(Techniques::Pop);
+                            tab_note.pluck = 1;
+                        }
+                        _ => {}
+                    }
+
+                    last_on_string[string] = Some(pending.len());
+                    pending.push(PendingNote {
+                        note: tab_note,
+                        gp_fret: note.fret,
+                        start_quarters: start,
+                        end_quarters: end,
+                        hammer: note.hammer,
+                        slide: note.slide,
+                    });
+                }
+            }
+        }
+    }
+
+    link_legato(&mut pending, string_count);
+
+    let notes = pending
+        .into_iter()
+        .map(|pending| {
+            let mut note = pending.note;
+            if pending.end_quarters - pending.start_quarters >= MIN_SUSTAIN_QUARTERS {
+                note.sustain = (timeline.seconds_at(pending.end_quarters)
+                    - timeline.seconds_at(pending.start_quarters))
+                    as f32;
+            }
+            note
+        })
+        .collect();
+    (notes, chords.into_chords())
+}
+
+// Hammer-ons, pull-offs and slides are marked on the note they start from in Guitar Pro;
+// charts put the technique on the note they lead to and the target fret on the slide.
+fn link_legato(pending: &mut [PendingNote], string_count: usize) {
+    for string in 0..string_count {
+        let mut indices: Vec<usize> = (0..pending.len())
+            .filter(|&index| pending[index].note.string == string as i32)
+            .collect();
+        indices.sort_by(|&a, &b| {
+            pending[a]
+                .start_quarters
+                .total_cmp(&pending[b].start_quarters)
+        });
+
+        for pair in indices.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let target_fret = pending[to].note.fret;
+            if pending[from].hammer {
+                let technique = if pending[to].gp_fret >= pending[from].gp_fret {
+                    Techniques::HammerOn
+                } else {
+                    Techniques::PullOff
+                };
+                pending[to].note.techniques.push(technique);
+            }
+            if pending[from].slide == Some(SlideKind::ToNext)
+                && target_fret != pending[from].note.fret
+            {
+                pending[from].note.slide_to = target_fret;
+                pending[from].note.techniques.push(Techniques::Slide);
+            }
+        }
+
+        for &index in &indices {
+            let note = &mut pending[index].note;
+            let target = match pending[index].slide {
+                Some(SlideKind::OutDown) => (note.fret - UNPITCHED_SLIDE_FRETS).max(1),
+                Some(SlideKind::OutUp) => note.fret + UNPITCHED_SLIDE_FRETS,
+                _ => continue,
+            };
+            note.slide_unpitch_to = target;
+            note.techniques.push(Techniques::Slide);
+        }
+    }
+}
@@ -0,0 +1,602 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use midly::{MetaMessage, MidiMessage, Smf, Timing, TrackEventKind};
+
+use crate::convert::fingering::{solve_fingering, MAX_FRET};
+use crate::convert::{
+    plain_note, sections_from_markers, single_chart_tab, string_arrangement_metadata,
+    used_techniques, ChordTemplates, ConvertError, ImportedSong, TempoMap, DEFAULT_TEMPO,
+    MIN_SUSTAIN_QUARTERS,
+};
+use crate::file::song::{
+    SongArrangementMetadata, Tab, TabChord, TabNote, TabsInstrument, Techniques,
+};
+use crate::input::{standard_open_strings, InstrumentTuning};
+
+const PERCUSSION_CHANNEL: u8 = 9;
+const GUITAR_PROGRAMS: std::ops::RangeInclusive<u8> = 24..=31;
+const BASS_PROGRAMS: std::ops::RangeInclusive<u8> = 32..=39;
+// Notes starting this close together are played as one chord.
+const CHORD_WINDOW_QUARTERS: f64 = 1.0 / 32.0;
+const LOWEST_GUITAR_PITCH: i32 = 40;
+
+/// What to take from a MIDI file and which instrument to fit it to.
+#[derive(Debug, Clone, Default)]
+pub struct MidiImportOptions {
+    /// Track to import, counting from 0. By default every guitar and bass track is
+    /// imported, or every melodic track when the file names no such instruments.
+    pub track: Option<usize>,
+    /// Instrument, tuning and capo to finger the notes for. By default this is standard
+    /// tuning for the instrument the track's program points to.
+    pub arrangement: Option<SongArrangementMetadata>,
+}
+
+/// Reads a Standard MIDI File and converts the chosen tracks into arrangements.
+pub fn import_midi_file(
+    path: &Path,
+    options: &MidiImportOptions,
+) -> Result<ImportedSong, ConvertError> {
+    let bytes = fs::read(path)?;
+    import_midi(&bytes, options)
+}
+
+/// Converts Standard MIDI File data. MIDI only carries pitches, so string, fret and hand
+/// position come from the fingering solver. Sections follow the file's markers, or its
+/// tempo changes when there are none.
+pub fn import_midi(
+    bytes: &[u8],
+    options: &MidiImportOptions,
+) -> Result<ImportedSong, ConvertError> {
+    let smf = Smf::parse(bytes).map_err(|err| ConvertError::InvalidData(err.to_string()))?;
+    let tracks: Vec<MidiTrack> = smf.tracks.iter().map(|events| read_track(events)).collect();
+    let clock = MidiClock::new(smf.header.timing, &tracks);
+
+    let selected: Vec<usize> = match options.track {
+        Some(index) if index < tracks.len() => vec![index],
+        Some(index) => return Err(ConvertError::TrackNotFound(index)),
+        None => {
+            let melodic: Vec<usize> = (0..tracks.len())
+                .filter(|&index| !tracks[index].notes.is_empty())
+                .collect();
+            let fretted: Vec<usize> = melodic
+                .iter()
+                .copied()
+                .filter(|&index| tracks[index].instrument().is_some())
+                .collect();
+            if fretted.is_empty() {
+                melodic
+            } else {
+                fretted
+            }
+        }
+    };
+
+    let song_end = tracks
+        .iter()
+        .flat_map(|track| &track.notes)
+        .map(|note| note.end)
+        .max()
+        .map_or(0.0, |tick| clock.seconds_at(tick) as f32);
+    let sections = sections_from_markers(&section_markers(&tracks, &clock), song_end);
+    // In multi-track files the first track usually only holds the song name and tempo.
+    let title = tracks
+        .first()
+        .filter(|track| track.notes.is_empty() && tracks.len() > 1)
+        .map(|track| track.name.clone())
+        .unwrap_or_default();
+
+    let mut imported = ImportedSong::new(&title, "", "", song_end);
+    for index in selected {
+        let track = &tracks[index];
+        if track.notes.is_empty() {
+            continue;
+        }
+        let arrangement = options
+            .arrangement
+            .clone()
+            .unwrap_or_else(|| default_arrangement(track));
+        let (notes, chords) = convert_track(track, &arrangement, &clock);
+        if notes.is_empty() {
+            continue;
+        }
+
+        let name = if !track.name.is_empty() {
+            track.name.clone()
+        } else if !arrangement.name.is_empty() {
+            arrangement.name.clone()
+        } else {
+            format!("Track {}", index + 1)
+        };
+        let metadata = SongArrangementMetadata {
+            name: name.clone(),
+            techniques: used_techniques(&notes),
+            ..arrangement
+        };
+        imported.add_arrangement(
+            &name,
+            metadata,
+            Tab::Strings(single_chart_tab(sections.clone(), chords, notes)),
+        );
+    }
+
+    if imported.arrangements.is_empty() {
+        return Err(ConvertError::NoStringTracks);
+    }
+    Ok(imported)
+}
+
+struct MidiTrack {
+    name: String,
+    program: Option<u8>,
+    notes: Vec<MidiNote>,
+    // (tick, microseconds per quarter note)
+    tempos: Vec<(u64, u32)>,
+    markers: Vec<(u64, String)>,
+}
+
+impl MidiTrack {
+    fn instrument(&self) -> Option<TabsInstrument> {
+        let program = self.program?;
+        if GUITAR_PROGRAMS.contains(&program) {
+            Some(TabsInstrument::Guitar)
+        } else if BASS_PROGRAMS.contains(&program) {
+            Some(TabsInstrument::Bass)
+        } else {
+            None
+        }
+    }
+}
+
+struct MidiNote {
+    start: u64,
+    end: u64,
+    pitch: i32,
+}
+
+fn read_track(events: &[midly::TrackEvent]) -> MidiTrack {
+    let mut track = MidiTrack {
+        name: String::new(),
+        program: None,
+        notes: Vec::new(),
+        tempos: Vec::new(),
+        markers: Vec::new(),
+    };
+    let mut held: HashMap<(u8, u8), VecDeque<u64>> = HashMap::new();
+    let mut tick: u64 = 0;
+
+    for event in events {
+        tick += event.delta.as_int() as u64;
+        match event.kind {
+            TrackEventKind::Midi { channel, message } => {
+                let channel = channel.as_int();
+                if channel == PERCUSSION_CHANNEL {
+                    continue;
+                }
+                match message {
+                    MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => {
+                        held.entry((channel, key.as_int()))
+                            .or_default()
+                            .push_back(tick);
+                    }
+                    MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                        if let Some(start) = held
+                            .get_mut(&(channel, key.as_int()))
+                            .and_then(|starts| starts.pop_front())
+                        {
+                            track.notes.push(MidiNote {
+                                start,
+                                end: tick,
+                                pitch: key.as_int() as i32,
+                            });
+                        }
+                    }
+                    MidiMessage::ProgramChange { program } => {
+                        track.program.get_or_insert(program.as_int());
+                    }
+                    _ => {}
+                }
+            }
+            TrackEventKind::Meta(MetaMessage::TrackName(name)) if track.name.is_empty() => {
+                track.name = String::from_utf8_lossy(name).trim().to_string();
+            }
+            TrackEventKind::Meta(MetaMessage::Tempo(tempo)) => {
+                track.tempos.push((tick, tempo.as_int()));
+            }
+            TrackEventKind::Meta(MetaMessage::Marker(text)) => {
+                let text = String::from_utf8_lossy(text).trim().to_string();
+                if !text.is_empty() {
+                    track.markers.push((tick, text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Notes still held at the end of the track stop there.
+    for ((_, key), starts) in held {
+        track.notes.extend(starts.into_iter().map(|start| MidiNote {
+            start,
+            end: tick,
+            pitch: key as i32,
+        }));
+    }
+    track
+        .notes
+        .sort_by(|a, b| a.start.cmp(&b.start).then(a.pitch.cmp(&b.pitch)));
+    track
+}
+
+// Turns ticks into seconds, either through the tempo map or a fixed SMPTE frame rate.
+enum MidiClock {
+    Metrical {
+        ticks_per_quarter: f64,
+        tempo_map: TempoMap,
+    },
+    Timecode {
+        ticks_per_second: f64,
+    },
+}
+
+impl MidiClock {
+    fn new(timing: Timing, tracks: &[MidiTrack]) -> Self {
+        match timing {
+            Timing::Metrical(ticks) => {
+                let ticks_per_quarter = ticks.as_int().max(1) as f64;
+                let changes = tracks
+                    .iter()
+                    .flat_map(|track| &track.tempos)
+                    .map(|&(tick, micros)| {
+                        (
+                            tick as f64 / ticks_per_quarter,
+                            60_000_000.0 / micros.max(1) as f64,
+                        )
+                    })
+                    .collect();
+                MidiClock::Metrical {
+                    ticks_per_quarter,
+                    tempo_map: TempoMap::new(DEFAULT_TEMPO, changes),
+                }
+            }
+            Timing::Timecode(fps, subframes) => MidiClock::Timecode {
+                ticks_per_second: (fps.as_f32() * subframes.max(1) as f32) as f64,
+            },
+        }
+    }
+
+    fn quarters_at(&self, tick: u64) -> f64 {
+        match self {
+            MidiClock::Metrical {
+                ticks_per_quarter, ..
+            } => tick as f64 / ticks_per_quarter,
+            MidiClock::Timecode { .. } => self.seconds_at(tick) * DEFAULT_TEMPO / 60.0,
+        }
+    }
+
+    fn seconds_at(&self, tick: u64) -> f64 {
+        match self {
+            MidiClock::Metrical {
+                ticks_per_quarter,
+                tempo_map,
+            } => tempo_map.seconds_at(tick as f64 / ticks_per_quarter),
+            MidiClock::Timecode { ticks_per_second } => tick as f64 / ticks_per_second,
+        }
+    }
+}
+
+// Markers when the file has them, otherwise one section per tempo.
+fn section_markers(tracks: &[MidiTrack], clock: &MidiClock) -> Vec<(String, f32)> {
+    let mut markers: Vec<(u64, String)> = tracks
+        .iter()
+        .flat_map(|track| track.markers.iter().cloned())
+        .collect();
+    if markers.is_empty() {
+        let mut tempos: Vec<(u64, u32)> = tracks
+            .iter()
+            .flat_map(|track| track.tempos.iter().copied())
+            .collect();
+        tempos.sort_by_key(|(tick, _)| *tick);
+        if tempos.first().is_none_or(|(tick, _)| *tick > 0) {
+            tempos.insert(0, (0, (60_000_000.0 / DEFAULT_TEMPO) as u32));
+        }
+        let mut last_bpm = None;
+        for (tick, micros) in tempos {
+            let bpm = (60_000_000.0 / micros.max(1) as f64).round() as i64;
+            if last_bpm == Some(bpm) {
+                continue;
+            }
+            // A tempo set again at the same tick replaces the earlier one.
+            if markers.last().is_some_and(|(last, _)| *last == tick) {
+                markers.pop();
+            }
+            markers.push((tick, format!("{bpm} BPM")));
+            last_bpm = Some(bpm);
+        }
+    }
+    markers.sort_by_key(|(tick, _)| *tick);
+    markers
+        .into_iter()
+        .map(|(tick, name)| (name, clock.seconds_at(tick) as f32))
+        .collect()
+}
+
+fn default_arrangement(track: &MidiTrack) -> SongArrangementMetadata {
+    let instrument = track.instrument().unwrap_or_else(|| {
+        let lowest = track.notes.iter().map(|note| note.pitch).min();
+        if lowest.is_some_and(|pitch| pitch < LOWEST_GUITAR_PITCH) {
+            TabsInstrument::Bass
+        } else {
+            TabsInstrument::Guitar
+        }
+    });
+    let string_count = match instrument {
+        TabsInstrument::Bass => 4,
+        _ => 6,
+    };
+    let tuning = standard_open_strings(instrument, string_count);
+    string_arrangement_metadata(&track.name, instrument, &tuning, 0, Vec::new())
+}
+
+fn convert_track(
+    track: &MidiTrack,
+    arrangement: &SongArrangementMetadata,
+    clock: &MidiClock,
+) -> (Vec<TabNote>, Vec<TabChord>) {
+    let tuning = InstrumentTuning::from_arrangement(arrangement);
+    let Some(lowest) = tuning
+        .open_strings
+        .iter()
+        .min()
+        .map(|open| open + tuning.capo_fret)
+    else {
+        return (Vec::new(), Vec::new());
+    };
+    let highest = tuning.open_strings.iter().max().copied().unwrap_or(lowest) + MAX_FRET;
+    let string_count = tuning.open_strings.len();
+
+    // Pitches outside the instrument's range are moved by octaves until they fit.
+    let fit = |pitch: i32| {
+        let mut pitch = pitch;
+        while pitch < lowest {
+            pitch += 12;
+        }
+        while pitch > highest && pitch - 12 >= lowest {
+            pitch -= 12;
+        }
+        pitch
+    };
+
+    let mut groups: Vec<Vec<&MidiNote>> = Vec::new();
+    for note in &track.notes {
+        match groups.last_mut() {
+            Some(group)
+                if clock.quarters_at(note.start) - clock.quarters_at(group[0].start)
+                    <= CHORD_WINDOW_QUARTERS =>
+            {
+                group.push(note)
+            }
+            _ => groups.push(vec![note]),
+        }
+    }
+    // More notes than strings can't be fretted; keep the bass note and the top voices.
+    for group in &mut groups {
+        group.sort_by_key(|note| fit(note.pitch));
+        group.dedup_by_key(|note| fit(note.pitch));
+        if group.len() > string_count {
+            let excess = group.len() - string_count;
+            group.drain(1..1 + excess);
+        }
+    }
+
+    let pitches: Vec<Vec<i32>> = groups
+        .iter()
+        .map(|group| group.iter().map(|note| fit(note.pitch)).collect())
+        .collect();
+    let fingerings = solve_fingering(&pitches, &tuning);
+
+    let mut notes = Vec::new();
+    let mut chords = ChordTemplates::default();
+    for (group, fingering) in groups.iter().zip(&fingerings) {
+        let placed: Vec<_> = group
+            .iter()
+            .zip(&fingering.positions)
+            .filter_map(|(note, position)| Some((*note, (*position)?)))
+            .collect();
+        let chord_index = if placed.len() > 1 {
+            let mut frets = vec![-1; string_count];
+            for (_, position) in &placed {
+                frets[position.string as usize] = position.fret;
+            }
+            chords.index_for("", frets, vec![-1; string_count])
+        } else {
+            -1
+        };
+
+        for (note, position) in placed {
+            let time = clock.seconds_at(note.start) as f32;
+            let mut tab_note = plain_note(time, position.string, position.fret);
+            tab_note.anchor_fret = fingering.anchor_fret;
+            tab_note.chord_index = chord_index;
+            if chord_index >= 0 {
+                tab_note.techniques.push(Techniques::Chord);
+            }
+            if clock.quarters_at(note.end) - clock.quarters_at(note.start) >= MIN_SUSTAIN_QUARTERS {
+                tab_note.sustain =
+                    (clock.seconds_at(note.end) - clock.seconds_at(note.start)) as f32;
+            }
+            notes.push(tab_note);
+        }
+    }
+    (notes, chords.into_chords())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use midly::num::{u15, u28, u4, u7};
+    use midly::{Format, Header, TrackEvent};
+
+    const TICKS_PER_QUARTER: u64 = 480;
+
+    // A single guitar track holding `(start, length, pitch)` notes, timed in quarters.
+    fn midi(notes: &[(f64, f64, u8)]) -> Vec<u8> {
+        let tick = |quarters: f64| (quarters * TICKS_PER_QUARTER as f64) as u64;
+        let mut events: Vec<(u64, MidiMessage)> = vec![(
+            0,
+            MidiMessage::ProgramChange {
+                program: u7::new(25),
+            },
+        )];
+        for &(start, length, pitch) in notes {
+            let key = u7::new(pitch);
+            events.push((
+                tick(start),
+                MidiMessage::NoteOn {
+                    key,
+                    vel: u7::new(100),
+                },
+            ));
+            events.push((
+                tick(start + length),
+                MidiMessage::NoteOff {
+                    key,
+                    vel: u7::new(0),
+                },
+            ));
+        }
+        events
+            .sort_by_key(|(tick, message)| (*tick, matches!(message, MidiMessage::NoteOn { .. })));
+
+        let mut last = 0;
+        let mut track: Vec<TrackEvent> = events
+            .into_iter()
+            .map(|(tick, message)| {
+                let delta = u28::new((tick - last) as u32);
+                last = tick;
+                TrackEvent {
+                    delta,
+                    kind: TrackEventKind::Midi {
+                        channel: u4::new(0),
+                        message,
+                    },
+                }
+            })
+            .collect();
+        track.push(TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
+
+        let mut smf = Smf::new(Header::new(
+            Format::SingleTrack,
+            Timing::Metrical(u15::new(TICKS_PER_QUARTER as u16)),
+        ));
+        smf.tracks.push(track);
+        let mut bytes = Vec::new();
+        smf.write_std(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn import(
+        notes: &[(f64, f64, u8)],
+        arrangement: Option<SongArrangementMetadata>,
+    ) -> ImportedSong {
+        let options = MidiImportOptions {
+            track: None,
+            arrangement,
+        };
+        import_midi(&midi(notes), &options).unwrap()
+    }
+
+    fn positions(song: &ImportedSong) -> Vec<(f32, i32, i32)> {
+        let Tab::Strings(tab) = &song.arrangements[0].tab else {
+            panic!("expected a string arrangement");
+        };
+        tab.note_charts[0]
+            .notes
+            .iter()
+            .map(|note| (note.time, note.string, note.fret))
+            .collect()
+    }
+
+    fn guitar(tuning: &[i32], capo_fret: i32) -> Option<SongArrangementMetadata> {
+        Some(string_arrangement_metadata(
+            "Lead",
+            TabsInstrument::Guitar,
+            tuning,
+            capo_fret,
+            Vec::new(),
+        ))
+    }
+
+    #[test]
+    fn scale_is_fingered_in_standard_tuning() {
+        let scale: Vec<(f64, f64, u8)> = [48, 50, 52, 53, 55, 57, 59, 60]
+            .into_iter()
+            .enumerate()
+            .map(|(beat, pitch)| (beat as f64, 1.0, pitch))
+            .collect();
+        let song = import(&scale, None);
+        assert_eq!(
+            song.metadata
+                .arrangements
+                .values()
+                .next()
+                .unwrap()
+                .instrument,
+            TabsInstrument::Guitar
+        );
+        assert_eq!(
+            positions(&song),
+            [
+                (0.0, 1, 3),
+                (0.5, 2, 0),
+                (1.0, 2, 2),
+                (1.5, 2, 3),
+                (2.0, 3, 0),
+                (2.5, 3, 2),
+                (3.0, 4, 0),
+                (3.5, 4, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_d_and_capo_change_the_frets() {
+        let notes = [(0.0, 1.0, 38), (1.0, 1.0, 42)];
+        // Standard tuning has no low D, so it moves up an octave.
+        assert_eq!(positions(&import(&notes, None)), [(0.0, 2, 0), (0.5, 0, 2)]);
+        assert_eq!(
+            positions(&import(&notes, guitar(&[38, 45, 50, 55, 59, 64], 0))),
+            [(0.0, 0, 0), (0.5, 0, 4)]
+        );
+        assert_eq!(
+            positions(&import(&notes, guitar(&[40, 45, 50, 55, 59, 64], 2))),
+            [(0.0, 1, 5), (0.5, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn out_of_range_pitches_move_by_octaves() {
+        let song = import(&[(0.0, 1.0, 28), (1.0, 1.0, 100)], None);
+        assert_eq!(positions(&song), [(0.0, 0, 0), (0.5, 5, 24)]);
+    }
+
+    #[test]
+    fn chords_spread_across_strings() {
+        let song = import(&[(0.0, 2.0, 40), (0.0, 2.0, 47), (0.0, 2.0, 52)], None);
+        assert_eq!(positions(&song), [(0.0, 0, 0), (0.0, 1, 2), (0.0, 2, 2)]);
+
+        let Tab::Strings(tab) = &song.arrangements[0].tab else {
+            panic!("expected a string arrangement");
+        };
+        let note = &tab.note_charts[0].notes[0];
+        assert!(note.techniques.contains(&Techniques::Chord));
+        assert_eq!(
+            tab.chords[note.chord_index as usize].frets,
+            [0, 2, 2, -1, -1, -1]
+        );
+        assert!((note.sustain - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::file::schema::SONG_METADATA_VERSION;
+use crate::file::song::{
+    arrangement_file_stem, SongArrangementMetadata, SongLoaderError, SongMetadata, StringTab, Tab,
+    TabChord, TabLoaderError, TabNote, TabNoteChart, TabSection, TabsInstrument, Techniques,
+};
+use crate::input::standard_open_strings;
+
+pub mod ascii_tab;
+pub mod fingering;
+pub mod guitar_pro;
+pub mod midi;
+pub mod music_xml;
+pub mod rocksmith;
+
+pub use ascii_tab::{
+    export_ascii_tab, export_ascii_tab_file, import_ascii_tab, import_ascii_tab_file,
+    render_ascii_tab, AsciiTabOptions,
+};
+pub use fingering::{solve_fingering, Fingering, FretPosition};
+pub use guitar_pro::{import_guitar_pro, import_guitar_pro_file};
+pub use midi::{import_midi, import_midi_file, MidiImportOptions};
+pub use music_xml::{
+    export_music_xml, export_music_xml_file, import_music_xml, import_music_xml_file,
+};
+pub use rocksmith::{
+    import_rocksmith_file, import_rocksmith_folder, import_rocksmith_xml, is_rocksmith_xml,
+};
+
+pub const DEFAULT_TEMPO: f64 = 120.0;
+/// Shorter notes get no sustain line.
+pub const MIN_SUSTAIN_QUARTERS: f64 = 1.0;
+pub const UNPITCHED_SLIDE_FRETS: i32 = 5;
+
+/// Picks the importer from the extension; a folder is read as Rocksmith XML.
+pub fn import_file(path: &Path) -> Result<ImportedSong, ConvertError> {
+    if path.is_dir() {
+        return import_rocksmith_folder(path);
+    }
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "gp3" | "gp4" | "gp5" => import_guitar_pro_file(path),
+        "musicxml" => import_music_xml_file(path),
+        "xml" => {
+            let text = fs::read_to_string(path)?;
+            if is_rocksmith_xml(&text) {
+                import_rocksmith_xml(&[&text])
+            } else {
+                import_music_xml(&text)
+            }
+        }
+        "mid" | "midi" => import_midi_file(path, &MidiImportOptions::default()),
+        "txt" => import_ascii_tab_file(path, &AsciiTabOptions::default()),
+        _ => Err(ConvertError::UnsupportedFormat(path.display().to_string())),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("Invalid song.metadata: {0}")]
+    SongMetadata(#[from] SongLoaderError),
+
+    #[error("Invalid tab file: {0}")]
+    Tab(#[from] TabLoaderError),
+
+    #[error("Unsupported file format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Unsupported {format} version '{version}'")]
+    UnsupportedVersion {
+        format: &'static str,
+        version: String,
+    },
+
+    #[error("Unexpected end of file at byte {0}")]
+    UnexpectedEof(usize),
+
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+
+    #[error("No playable string tracks found")]
+    NoStringTracks,
+
+    #[error("Track {0} does not exist")]
+    TrackNotFound(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedSong {
+    pub metadata: SongMetadata,
+    pub arrangements: Vec<ImportedArrangement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedArrangement {
+    pub key: String,
+    pub tab: Tab,
+}
+
+impl ImportedSong {
+    pub fn new(title: &str, artist: &str, album: &str, length: f32) -> Self {
+        ImportedSong {
+            metadata: SongMetadata {
+                version: SONG_METADATA_VERSION,
+                title: title.to_string(),
+                artist: artist.to_string(),
+                album: album.to_string(),
+                year: 0,
+                length,
+                audio: "song.wav".to_string(),
+                preview: "preview.wav".to_string(),
+                arrangements: HashMap::new(),
+            },
+            arrangements: Vec::new(),
+        }
+    }
+
+    /// Arrangements whose tab file is missing are skipped.
+    pub fn read_from_folder(folder: &Path) -> Result<Self, ConvertError> {
+        let metadata = SongMetadata::from_yaml(&fs::read(folder.join("song.metadata"))?)?;
+        let mut keys: Vec<&String> = metadata.arrangements.keys().collect();
+        keys.sort();
+
+        let mut arrangements = Vec::new();
+        for key in keys {
+            let tab_path = folder.join(&metadata.arrangements[key].file);
+            if !tab_path.exists() {
+                continue;
+            }
+            let tab = Tab::from_yaml(&fs::read(&tab_path)?)?;
+            arrangements.push(ImportedArrangement {
+                key: key.clone(),
+                tab,
+            });
+        }
+        Ok(ImportedSong {
+            metadata,
+            arrangements,
+        })
+    }
+
+    /// Returns the paths that were written.
+    pub fn write_to_folder(&self, folder: &Path) -> Result<Vec<PathBuf>, ConvertError> {
+        fs::create_dir_all(folder)?;
+        let mut written = Vec::new();
+
+        let metadata_path = folder.join("song.metadata");
+        fs::write(&metadata_path, serde_yaml::to_string(&self.metadata)?)?;
+        written.push(metadata_path);
+
+        for arrangement in &self.arrangements {
+            let file = match self.metadata.arrangements.get(&arrangement.key) {
+                Some(metadata) => metadata.file.clone(),
+                None => format!("{}.tab", arrangement_file_stem(&arrangement.key)),
+            };
+            let tab_path = folder.join(file);
+            fs::write(&tab_path, arrangement.tab.to_yaml()?)?;
+            written.push(tab_path);
+        }
+        Ok(written)
+    }
+
+    pub fn add_arrangement(&mut self, name: &str, mut metadata: SongArrangementMetadata, tab: Tab) {
+        let key = unique_arrangement_key(&self.metadata.arrangements, name);
+        metadata.file = format!("{}.tab", arrangement_file_stem(&key));
+        self.metadata.arrangements.insert(key.clone(), metadata);
+        self.arrangements.push(ImportedArrangement { key, tab });
+    }
+}
+
+// Arrangement keys double as file names, so keep them to letters, digits and spaces and
+// make sure two tracks never map to the same file.
+fn unique_arrangement_key(
+    existing: &HashMap<String, SongArrangementMetadata>,
+    name: &str,
+) -> String {
+    let cleaned: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ')
+        .collect();
+    let base = match cleaned.trim() {
+        "" => "Arrangement".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let taken = |key: &str| {
+        existing
+            .keys()
+            .any(|other| arrangement_file_stem(other) == arrangement_file_stem(key))
+    };
+    if !taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{base} {n}"))
+        .find(|key| !taken(key))
+        .unwrap_or(base)
+}
+
+/// `tuning` is lowest string first.
+pub fn guess_instrument(tuning: &[i32]) -> TabsInstrument {
+    let lowest = tuning.iter().copied().min().unwrap_or(40);
+    if tuning.len() <= 4 || lowest < 35 {
+        TabsInstrument::Bass
+    } else {
+        TabsInstrument::Guitar
+    }
+}
+
+/// `tuning` holds open-string MIDI pitches, lowest string first.
+pub fn string_arrangement_metadata(
+    name: &str,
+    instrument: TabsInstrument,
+    tuning: &[i32],
+    capo_fret: i32,
+    techniques: Vec<Techniques>,
+) -> SongArrangementMetadata {
+    let standard = standard_open_strings(instrument, tuning.len());
+    let offsets = tuning
+        .iter()
+        .zip(&standard)
+        .map(|(open, standard)| open - standard)
+        .collect();
+    SongArrangementMetadata {
+        name: name.to_string(),
+        file: String::new(),
+        capo_fret: (capo_fret > 0).then_some(capo_fret),
+        instrument,
+        string_count: Some(tuning.len() as i32),
+        string_semitone_offset: Some(offsets),
+        techniques,
+    }
+}
+
+pub fn used_techniques(notes: &[TabNote]) -> Vec<Techniques> {
+    let mut techniques = Vec::new();
+    for technique in notes.iter().flat_map(|note| &note.techniques) {
+        if !techniques.contains(technique) {
+            techniques.push(*technique);
+        }
+    }
+    techniques
+}
+
+pub fn sections_from_markers(markers: &[(String, f32)], song_end: f32) -> Vec<TabSection> {
+    markers
+        .iter()
+        .enumerate()
+        .map(|(index, (name, start_time))| TabSection {
+            name: name.clone(),
+            start_time: *start_time,
+            end_time: markers
+                .get(index + 1)
+                .map(|(_, next)| *next)
+                .unwrap_or(song_end)
+                .max(*start_time),
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct ChordTemplates {
+    chords: Vec<TabChord>,
+}
+
+impl ChordTemplates {
+    pub fn index_for(&mut self, name: &str, frets: Vec<i32>, fingers: Vec<i32>) -> i32 {
+        if let Some(index) = self
+            .chords
+            .iter()
+            .position(|chord| chord.name == name && chord.frets == frets)
+        {
+            return index as i32;
+        }
+        self.chords.push(TabChord {
+            name: name.to_string(),
+            fingers,
+            frets,
+        });
+        (self.chords.len() - 1) as i32
+    }
+
+    pub fn into_chords(self) -> Vec<TabChord> {
+        self.chords
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeasureRepeat {
+    pub open: bool,
+    /// Jumps back to the last open repeat.
+    pub close: i32,
+    /// Bit `n` set means the measure is played on pass `n + 1` of its repeat.
+    pub alternatives: u8,
+}
+
+pub fn playback_order(measures: &[MeasureRepeat]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(measures.len());
+    let mut repeats_done: HashMap<usize, i32> = HashMap::new();
+    let mut repeat_start = 0;
+    let mut pass: u32 = 1;
+    let mut index = 0;
+    let mut jumped_back = false;
+
+    while index < measures.len() {
+        let measure = &measures[index];
+        if measure.open && !jumped_back {
+            repeat_start = index;
+            pass = 1;
+        }
+        jumped_back = false;
+
+        let skip_ending = measure.alternatives != 0
+            && measure.alternatives & 1u8.checked_shl(pass - 1).unwrap_or(0) == 0;
+        if !skip_ending {
+            order.push(index);
+        }
+
+        if measure.close > 0 && !skip_ending {
+            let done = repeats_done.entry(index).or_insert(0);
+            if *done < measure.close {
+                *done += 1;
+                pass += 1;
+                index = repeat_start;
+                jumped_back = true;
+                continue;
+            }
+            repeats_done.remove(&index);
+            pass = 1;
+        }
+        index += 1;
+    }
+    order
+}
+
+#[derive(Debug, Clone)]
+pub struct TempoMap {
+    // (position in quarters, seconds at that position, quarters per second from there on)
+    points: Vec<(f64, f64, f64)>,
+}
+
+impl TempoMap {
+    /// `changes` holds `(quarters, bpm)` pairs in any order.
+    pub fn new(initial_bpm: f64, mut changes: Vec<(f64, f64)>) -> Self {
+        changes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut points = vec![(0.0, 0.0, initial_bpm / 60.0)];
+        for (at, bpm) in changes {
+            if bpm <= 0.0 {
+                continue;
+            }
+            let (last_at, last_seconds, last_rate) = points[points.len() - 1];
+            let seconds = last_seconds + (at - last_at) / last_rate;
+            if at <= last_at {
+                points.pop();
+            }
+            points.push((at, seconds, bpm / 60.0));
+        }
+        TempoMap { points }
+    }
+
+    pub fn seconds_at(&self, quarters: f64) -> f64 {
+        let index = self
+            .points
+            .partition_point(|(at, _, _)| *at <= quarters)
+            .saturating_sub(1);
+        let (at, seconds, rate) = self.points[index];
+        seconds + (quarters - at) / rate
+    }
+}
+
+pub fn plain_note(time: f32, string: i32, fret: i32) -> TabNote {
+    TabNote {
+        time,
+        techniques: Vec::new(),
+        chord_index: -1,
+        string,
+        fret,
+        anchor_fret: -1,
+        sustain: 0.0,
+        slide_to: -1,
+        slide_unpitch_to: -1,
+        vibrato: 0,
+        max_bend: 0.0,
+        slap: -1,
+        pluck: -1,
+        tap: 0,
+    }
+}
+
+pub fn single_chart_tab(
+    sections: Vec<TabSection>,
+    chords: Vec<TabChord>,
+    mut notes: Vec<TabNote>,
+) -> StringTab {
+    notes.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.string.cmp(&b.string)));
+    StringTab {
+        sections,
+        chords,
+        note_charts: vec![TabNoteChart {
+            difficulty: 0,
+            notes,
+        }],
+    }
+}
@@ -0,0 +1,1180 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::convert::{
+    guess_instrument, plain_note, playback_order, sections_from_markers, single_chart_tab,
+    string_arrangement_metadata, used_techniques, ChordTemplates, ConvertError, ImportedSong,
+    MeasureRepeat, TempoMap, DEFAULT_TEMPO, MIN_SUSTAIN_QUARTERS, UNPITCHED_SLIDE_FRETS,
+};
+use crate::file::song::{StringTab, Tab, TabChord, TabNote, TabsInstrument, Techniques};
+use crate::input::{standard_open_strings, InstrumentTuning};
+
+const PALM_MUTE_TEXT: &str = "P.M.";
+const SLAP_TEXT: &str = "slap";
+const POP_TEXT: &str = "pop";
+
+// Charts carry no beat grid, so exports use a fixed tempo in 4/4 with divisions fine
+// enough to keep note times within a millisecond.
+const EXPORT_TEMPO: f64 = 120.0;
+const EXPORT_DIVISIONS: i64 = 960;
+const EXPORT_MEASURE_TICKS: i64 = 4 * EXPORT_DIVISIONS;
+// Unsustained notes are written no longer than an eighth so they read back unsustained.
+const EXPORT_SHORT_NOTE_TICKS: i64 = EXPORT_DIVISIONS / 2;
+
+const STEP_NAMES: [&str; 12] = ["C", "C", "D", "D", "E", "F", "F", "G", "G", "A", "A", "B"];
+
+pub fn import_music_xml_file(path: &Path) -> Result<ImportedSong, ConvertError> {
+    let text = fs::read_to_string(path)?;
+    import_music_xml(&text)
+}
+
+// Parts without string and fret technical elements are skipped; repeats are unrolled.
+pub fn import_music_xml(text: &str) -> Result<ImportedSong, ConvertError> {
+    // Scores almost always declare the MusicXML DTD, which roxmltree refuses by default.
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(text, options)
+        .map_err(|err| ConvertError::InvalidData(err.to_string()))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "score-partwise" {
+        return Err(ConvertError::UnsupportedFormat(format!(
+            "MusicXML <{}>",
+            root.tag_name().name()
+        )));
+    }
+
+    let title = descendant_text(root, &["work", "work-title"])
+        .or_else(|| descendant_text(root, &["movement-title"]))
+        .unwrap_or_default();
+    let artist = child(root, "identification")
+        .into_iter()
+        .flat_map(|identification| identification.children())
+        .find(|node| {
+            node.has_tag_name("creator")
+                && matches!(node.attribute("type"), Some("composer" | "artist"))
+        })
+        .and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .unwrap_or_default();
+    let part_names: HashMap<&str, String> = child(root, "part-list")
+        .into_iter()
+        .flat_map(|list| list.children())
+        .filter(|node| node.has_tag_name("score-part"))
+        .filter_map(|part| {
+            let name = descendant_text(part, &["part-name"]).unwrap_or_default();
+            Some((part.attribute("id")?, name))
+        })
+        .collect();
+
+    let parts: Vec<XmlPart> = root
+        .children()
+        .filter(|node| node.has_tag_name("part"))
+        .map(|part| {
+            let name = part
+                .attribute("id")
+                .and_then(|id| part_names.get(id))
+                .cloned()
+                .unwrap_or_default();
+            read_part(part, name)
+        })
+        .collect();
+    let Some(first) = parts.first() else {
+        return Err(ConvertError::NoStringTracks);
+    };
+
+    let timeline = ScoreTimeline::new(&parts);
+    let tempo_map = &timeline.tempo_map;
+    let markers: Vec<(String, f32)> = timeline
+        .order
+        .iter()
+        .zip(&timeline.measure_starts)
+        .flat_map(|(&measure, &start)| {
+            first.measures[measure]
+                .rehearsals
+                .iter()
+                .map(move |(offset, name)| {
+                    (name.clone(), tempo_map.seconds_at(start + offset) as f32)
+                })
+        })
+        .collect();
+    let song_end = timeline.tempo_map.seconds_at(timeline.end) as f32;
+
+    let mut imported = ImportedSong::new(&title, &artist, "", song_end);
+    for part in &parts {
+        let Some(tuning) = part.tuning() else {
+            continue;
+        };
+        let (notes, chords) = convert_part(part, &tuning, &timeline);
+        if notes.is_empty() {
+            continue;
+        }
+        let metadata = string_arrangement_metadata(
+            &part.name,
+            guess_instrument(&tuning),
+            &tuning,
+            part.capo,
+            used_techniques(&notes),
+        );
+        let sections = sections_from_markers(&markers, song_end);
+        imported.add_arrangement(
+            &part.name,
+            metadata,
+            Tab::Strings(single_chart_tab(sections, chords, notes)),
+        );
+    }
+
+    if imported.arrangements.is_empty() {
+        return Err(ConvertError::NoStringTracks);
+    }
+    Ok(imported)
+}
+
+struct XmlPart {
+    name: String,
+    // Open-string pitches from the staff details, lowest string first.
+    tuning: Vec<i32>,
+    capo: i32,
+    measures: Vec<XmlMeasure>,
+}
+
+impl XmlPart {
+    fn tuning(&self) -> Option<Vec<i32>> {
+        if !self.tuning.is_empty() {
+            return Some(self.tuning.clone());
+        }
+        let highest_string = self
+            .measures
+            .iter()
+            .flat_map(|measure| &measure.notes)
+            .map(|note| note.string)
+            .max()?;
+        Some(standard_open_strings(
+            TabsInstrument::Guitar,
+            highest_string.max(6) as usize,
+        ))
+    }
+}
+
+#[derive(Default)]
+struct XmlMeasure {
+    // Length in quarters, from the time signature or the content when that runs longer.
+    quarters: f64,
+    repeat: MeasureRepeat,
+    // (offset in quarters, mark)
+    rehearsals: Vec<(f64, String)>,
+    // (offset in quarters, beats per minute)
+    tempos: Vec<(f64, f64)>,
+    // (offset in quarters, chord name)
+    harmonies: Vec<(f64, String)>,
+    notes: Vec<XmlNote>,
+}
+
+#[derive(Default)]
+struct XmlNote {
+    offset: f64,
+    quarters: f64,
+    // Notes written with `<chord/>` share the group of the note before them.
+    group: usize,
+    // MusicXML counts strings from the highest, starting at 1.
+    string: i32,
+    fret: i32,
+    finger: Option<i32>,
+    tied: bool,
+    hammer_on: bool,
+    pull_off: bool,
+    slide_start: bool,
+    unpitched_slide: Option<i32>,
+    bend: f32,
+    vibrato: bool,
+    palm_mute: bool,
+    tremolo: bool,
+    harmonic: Option<Techniques>,
+    tap: bool,
+    slap: bool,
+    pop: bool,
+}
+
+fn read_part(part: Node, name: String) -> XmlPart {
+    let mut result = XmlPart {
+        name,
+        tuning: Vec::new(),
+        capo: 0,
+        measures: Vec::new(),
+    };
+    let mut divisions = 1.0;
+    let mut signature_quarters = 4.0;
+    let mut ending = 0u8;
+    let mut group = 0;
+
+    for measure_node in part.children().filter(|node| node.has_tag_name("measure")) {
+        let mut measure = XmlMeasure::default();
+        let mut cursor: f64 = 0.0;
+        let mut longest: f64 = 0.0;
+        let mut last_start = 0.0;
+        let mut ending_closes = false;
+
+        for element in measure_node.children().filter(Node::is_element) {
+            match element.tag_name().name() {
+                "attributes" => {
+                    if let Some(value) = child_number(element, "divisions") {
+                        divisions = value.max(1.0);
+                    }
+                    if let Some(time) = child(element, "time") {
+                        let beats = child_number(time, "beats").unwrap_or(4.0);
+                        let beat_type = child_number(time, "beat-type").unwrap_or(4.0);
+                        signature_quarters = beats * 4.0 / beat_type.max(1.0);
+                    }
+                    for details in element
+                        .children()
+                        .filter(|node| node.has_tag_name("staff-details"))
+                    {
+                        read_staff_details(details, &mut result);
+                    }
+                }
+                "direction" => {
+                    let offset = child_number(element, "offset").unwrap_or(0.0) / divisions;
+                    if let Some(text) = element
+                        .descendants()
+                        .find(|node| node.has_tag_name("rehearsal"))
+                        .and_then(|node| node.text())
+                    {
+                        measure
+                            .rehearsals
+                            .push((cursor + offset, text.trim().to_string()));
+                    }
+                    if let Some(tempo) = child(element, "sound").and_then(sound_tempo) {
+                        measure.tempos.push((cursor + offset, tempo));
+                    }
+                }
+                "sound" => {
+                    if let Some(tempo) = sound_tempo(element) {
+                        measure.tempos.push((cursor, tempo));
+                    }
+                }
+                "harmony" => {
+                    if let Some(name) = harmony_name(element) {
+                        measure.harmonies.push((cursor, name));
+                    }
+                }
+                "backup" => {
+                    cursor -= child_number(element, "duration").unwrap_or(0.0) / divisions;
+                    cursor = cursor.max(0.0);
+                }
+                "forward" => {
+                    cursor += child_number(element, "duration").unwrap_or(0.0) / divisions;
+                    longest = longest.max(cursor);
+                }
+                "barline" => {
+                    if let Some(repeat) = child(element, "repeat") {
+                        match repeat.attribute("direction") {
+                            Some("forward") => measure.repeat.open = true,
+                            Some("backward") => {
+                                let times = repeat
+                                    .attribute("times")
+                                    .and_then(|times| times.parse::<i32>().ok())
+                                    .unwrap_or(2);
+                                measure.repeat.close = (times - 1).max(1);
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let Some(node) = child(element, "ending") {
+                        match node.attribute("type") {
+                            Some("start") => ending = ending_bits(node.attribute("number")),
+                            Some("stop" | "discontinue") => ending_closes = true,
+                            _ => {}
+                        }
+                    }
+                }
+                "note" => {
+                    if child(element, "grace").is_some() || child(element, "cue").is_some() {
+                        continue;
+                    }
+                    let quarters = child_number(element, "duration").unwrap_or(0.0) / divisions;
+                    let start = if child(element, "chord").is_some() {
+                        last_start
+                    } else {
+                        group += 1;
+                        last_start = cursor;
+                        cursor += quarters;
+                        longest = longest.max(cursor);
+                        last_start
+                    };
+                    if child(element, "rest").is_some() {
+                        continue;
+                    }
+                    if let Some(mut note) = read_note(element) {
+                        note.offset = start;
+                        note.quarters = quarters;
+                        note.group = group;
+                        measure.notes.push(note);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        measure.repeat.alternatives = ending;
+        if ending_closes {
+            ending = 0;
+        }
+        measure.quarters = if measure_node.attribute("implicit") == Some("yes") && longest > 0.0 {
+            longest
+        } else {
+            signature_quarters.max(longest)
+        };
+        result.measures.push(measure);
+    }
+    result
+}
+
+fn read_staff_details(details: Node, part: &mut XmlPart) {
+    let mut tuning: Vec<(i32, i32)> = details
+        .children()
+        .filter(|node| node.has_tag_name("staff-tuning"))
+        .filter_map(|node| {
+            let line = node.attribute("line")?.parse().ok()?;
+            let step = descendant_text(node, &["tuning-step"])?;
+            let alter = child_number(node, "tuning-alter").unwrap_or(0.0) as i32;
+            let octave = child_number(node, "tuning-octave")? as i32;
+            Some((line, midi_from_pitch(&step, alter, octave)?))
+        })
+        .collect();
+    if !tuning.is_empty() {
+        tuning.sort_by_key(|(line, _)| *line);
+        part.tuning = tuning.into_iter().map(|(_, midi)| midi).collect();
+    }
+    if let Some(capo) = child_number(details, "capo") {
+        part.capo = capo as i32;
+    }
+}
+
+fn read_note(note: Node) -> Option<XmlNote> {
+    let notations = child(note, "notations");
+    let technical = notations.and_then(|notations| child(notations, "technical"))?;
+    let mut result = XmlNote {
+        string: child_number(technical, "string")? as i32,
+        fret: child_number(technical, "fret")? as i32,
+        finger: child_number(technical, "fingering").map(|finger| finger as i32),
+        ..Default::default()
+    };
+
+    let is_stop = |node: Node| node.attribute("type") == Some("stop");
+    result.tied = note
+        .children()
+        .any(|node| node.has_tag_name("tie") && is_stop(node));
+    for element in notations
+        .into_iter()
+        .flat_map(|notations| notations.children())
+    {
+        match element.tag_name().name() {
+            "tied" if is_stop(element) => result.tied = true,
+            "slide" | "glissando" if element.attribute("type") == Some("start") => {
+                result.slide_start = true
+            }
+            "ornaments" => {
+                for ornament in element.children().filter(Node::is_element) {
+                    match ornament.tag_name().name() {
+                        "wavy-line" | "vibrato" => result.vibrato = true,
+                        "tremolo" => result.tremolo = true,
+                        _ => {}
+                    }
+                }
+            }
+            "articulations" => {
+                for articulation in element.children().filter(Node::is_element) {
+                    match articulation.tag_name().name() {
+                        "falloff" => result.unpitched_slide = Some(-UNPITCHED_SLIDE_FRETS),
+                        "doit" => result.unpitched_slide = Some(UNPITCHED_SLIDE_FRETS),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for element in technical.children().filter(Node::is_element) {
+        match element.tag_name().name() {
+            "hammer-on" if is_stop(element) => result.hammer_on = true,
+            "pull-off" if is_stop(element) => result.pull_off = true,
+            "bend" => {
+                let alter = child_number(element, "bend-alter").unwrap_or(0.0) as f32;
+                result.bend = result.bend.max(alter);
+            }
+            "harmonic" => {
+                result.harmonic = Some(if child(element, "artificial").is_some() {
+                    Techniques::PinchHarmonic
+                } else {
+                    Techniques::Harmonic
+                })
+            }
+            "tap" => result.tap = true,
+            "other-technical" => match element.text().map(str::trim) {
+                Some(PALM_MUTE_TEXT) => result.palm_mute = true,
+                Some(SLAP_TEXT) => result.slap = true,
+                Some(POP_TEXT) => result.pop = true,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Some(result)
+}
+
+// Playback order of the measures with repeats unrolled, taken from the first part, and
+// a tempo map built from every part.
+struct ScoreTimeline {
+    order: Vec<usize>,
+    measure_starts: Vec<f64>,
+    end: f64,
+    tempo_map: TempoMap,
+}
+
+impl ScoreTimeline {
+    fn new(parts: &[XmlPart]) -> Self {
+        let measures = &parts[0].measures;
+        let repeats: Vec<MeasureRepeat> = measures.iter().map(|measure| measure.repeat).collect();
+        let order = playback_order(&repeats);
+
+        let mut measure_starts = Vec::with_capacity(order.len());
+        let mut changes = Vec::new();
+        let mut position = 0.0;
+        for &index in &order {
+            measure_starts.push(position);
+            for part in parts {
+                if let Some(measure) = part.measures.get(index) {
+                    changes.extend(
+                        measure
+                            .tempos
+                            .iter()
+                            .map(|(offset, tempo)| (position + offset, *tempo)),
+                    );
+                }
+            }
+            position += measures[index].quarters;
+        }
+
+        ScoreTimeline {
+            order,
+            measure_starts,
+            end: position,
+            tempo_map: TempoMap::new(DEFAULT_TEMPO, changes),
+        }
+    }
+}
+
+struct PendingNote {
+    note: TabNote,
+    start_quarters: f64,
+    end_quarters: f64,
+    slide_start: bool,
+}
+
+fn convert_part(
+    part: &XmlPart,
+    tuning: &[i32],
+    timeline: &ScoreTimeline,
+) -> (Vec<TabNote>, Vec<TabChord>) {
+    let string_count = tuning.len();
+    let mut pending: Vec<PendingNote> = Vec::new();
+    let mut last_on_string: Vec<Option<usize>> = vec![None; string_count];
+    let mut chords = ChordTemplates::default();
+    let absolute = |fret: i32| if fret > 0 { fret + part.capo } else { fret };
+    let chart_string = |note: &XmlNote| {
+        let string = string_count as i32 - note.string;
+        (0..string_count as i32)
+            .contains(&string)
+            .then_some(string as usize)
+    };
+
+    for (&index, &measure_start) in timeline.order.iter().zip(&timeline.measure_starts) {
+        let Some(measure) = part.measures.get(index) else {
+            continue;
+        };
+        let mut groups: Vec<&[XmlNote]> =
+            measure.notes.chunk_by(|a, b| a.group == b.group).collect();
+        groups.sort_by(|a, b| a[0].offset.total_cmp(&b[0].offset));
+
+        for group in groups {
+            let start = measure_start + group[0].offset;
+            let time = timeline.tempo_map.seconds_at(start) as f32;
+            let struck: Vec<&XmlNote> = group.iter().filter(|note| !note.tied).collect();
+            let harmony = measure
+                .harmonies
+                .iter()
+                .find(|(offset, _)| (offset - group[0].offset).abs() < 1e-6)
+                .map(|(_, name)| name.as_str());
+
+            let chord_index = if struck.len() > 1 {
+                let mut frets = vec![-1; string_count];
+                let mut fingers = vec![-1; string_count];
+                for note in &struck {
+                    if let Some(string) = chart_string(note) {
+                        frets[string] = absolute(note.fret);
+                        fingers[string] = note.finger.unwrap_or(-1);
+                    }
+                }
+                chords.index_for(harmony.unwrap_or(""), frets, fingers)
+            } else {
+                -1
+            };
+
+            for note in group {
+                let Some(string) = chart_string(note) else {
+                    continue;
+                };
+                let end = start + note.quarters;
+                if note.tied {
+                    if let Some(previous) = last_on_string[string] {
+                        pending[previous].end_quarters = end;
+                    }
+                    continue;
+                }
+
+                let fret = absolute(note.fret);
+                let mut tab_note = plain_note(time, string as i32, fret);
+                tab_note.chord_index = chord_index;
+                let techniques = &mut tab_note.techniques;
+                if chord_index >= 0 {
+                    techniques.push(Techniques::Chord);
+                }
+                if note.hammer_on {
+                    techniques.push(Techniques::HammerOn);
+                }
+                if note.pull_off {
+                    techniques.push(Techniques::PullOff);
+                }
+                if note.bend > 0.0 {
+                    techniques.push(Techniques::Bend);
+                    tab_note.max_bend = note.bend;
+                }
+                if note.vibrato {
+                    techniques.push(Techniques::Vibrato);
+                    tab_note.vibrato = 80;
+                }
+                if note.palm_mute {
+                    techniques.push(Techniques::PalmMute);
+                }
+                if note.tremolo {
+                    techniques.push(Techniques::Tremolo);
+                }
+                if let Some(harmonic) = note.harmonic {
+                    techniques.push(harmonic);
+                }
+                if note.tap {
+                    techniques.push(Techniques::Tap);
+                    tab_note.tap = 1;
+                }
+                if note.slap {
+                    techniques.push(Techniques::Slap);
+                    tab_note.slap = 1;
+                }
+                if note.pop {
+                    techniques.push(Techniques::Pop);
+                    tab_note.pluck = 1;
+                }
+                if let Some(direction) = note.unpitched_slide {
+                    techniques.push(Techniques::Slide);
+                    tab_note.slide_unpitch_to = (fret + direction).max(1);
+                }
+
+                last_on_string[string] = Some(pending.len());
+                pending.push(PendingNote {
+                    note: tab_note,
+                    start_quarters: start,
+                    end_quarters: end,
+                    slide_start: note.slide_start,
+                });
+            }
+        }
+    }
+
+    link_slides(&mut pending, string_count);
+
+    let notes = pending
+        .into_iter()
+        .map(|pending| {
+            let mut note = pending.note;
+            if pending.end_quarters - pending.start_quarters >= MIN_SUSTAIN_QUARTERS {
+                note.sustain = (timeline.tempo_map.seconds_at(pending.end_quarters)
+                    - timeline.tempo_map.seconds_at(pending.start_quarters))
+                    as f32;
+            }
+            note
+        })
+        .collect();
+    (notes, chords.into_chords())
+}
+
+// A slide starts on one written note and lands on the next one on the same string.
+fn link_slides(pending: &mut [PendingNote], string_count: usize) {
+    for string in 0..string_count {
+        let mut indices: Vec<usize> = (0..pending.len())
+            .filter(|&index| pending[index].note.string == string as i32)
+            .collect();
+        indices.sort_by(|&a, &b| {
+            pending[a]
+                .start_quarters
+                .total_cmp(&pending[b].start_quarters)
+        });
+        for pair in indices.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let target_fret = pending[to].note.fret;
+            if pending[from].slide_start && target_fret != pending[from].note.fret {
+                let note = &mut pending[from].note;
+                note.slide_to = target_fret;
+                if !note.techniques.contains(&Techniques::Slide) {
+                    note.techniques.push(Techniques::Slide);
+                }
+            }
+        }
+    }
+}
+
+// A slide with no note at its target fret gets one written so notation editors can show it.
+pub fn export_music_xml(song: &ImportedSong) -> Result<String, ConvertError> {
+    let mut parts = Vec::new();
+    for arrangement in &song.arrangements {
+        let Tab::Strings(tab) = &arrangement.tab else {
+            continue;
+        };
+        let Some(metadata) = song.metadata.arrangements.get(&arrangement.key) else {
+            continue;
+        };
+        let tuning = InstrumentTuning::from_arrangement(metadata);
+        if tuning.open_strings.is_empty() {
+            continue;
+        }
+        parts.push((metadata.name.as_str(), tuning, tab));
+    }
+    if parts.is_empty() {
+        return Err(ConvertError::NoStringTracks);
+    }
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    xml.push_str("<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \"http://www.musicxml.org/dtds/partwise.dtd\">\n");
+    xml.push_str("<score-partwise version=\"4.0\">\n");
+    let _ = writeln!(
+        xml,
+        "  <work><work-title>{}</work-title></work>",
+        escape(&song.metadata.title)
+    );
+    let _ = writeln!(
+        xml,
+        "  <identification><creator type=\"composer\">{}</creator></identification>",
+        escape(&song.metadata.artist)
+    );
+    xml.push_str("  <part-list>\n");
+    for (index, (name, _, _)) in parts.iter().enumerate() {
+        let _ = writeln!(
+            xml,
+            "    <score-part id=\"P{}\"><part-name>{}</part-name></score-part>",
+            index + 1,
+            escape(name)
+        );
+    }
+    xml.push_str("  </part-list>\n");
+    for (index, (_, tuning, tab)) in parts.iter().enumerate() {
+        write_part(&mut xml, index + 1, tuning, tab);
+    }
+    xml.push_str("</score-partwise>\n");
+    Ok(xml)
+}
+
+pub fn export_music_xml_file(song: &ImportedSong, path: &Path) -> Result<(), ConvertError> {
+    fs::write(path, export_music_xml(song)?)?;
+    Ok(())
+}
+
+// A written note before it is cut at barlines and at the start of other notes.
+struct ExportNote<'a> {
+    start: i64,
+    end: i64,
+    string: usize,
+    fret: i32,
+    source: Option<&'a TabNote>,
+    hammer_start: bool,
+    pull_start: bool,
+    slide_start: bool,
+    slide_stop: bool,
+}
+
+fn to_ticks(seconds: f32) -> i64 {
+    (seconds as f64 * EXPORT_TEMPO / 60.0 * EXPORT_DIVISIONS as f64).round() as i64
+}
+
+fn write_part(xml: &mut String, number: usize, tuning: &InstrumentTuning, tab: &StringTab) {
+    let string_count = tuning.open_strings.len();
+    let chart = tab
+        .note_charts
+        .iter()
+        .max_by_key(|chart| chart.difficulty)
+        .map(|chart| chart.notes.as_slice())
+        .unwrap_or_default();
+    let mut sources: Vec<&TabNote> = chart
+        .iter()
+        .filter(|note| (0..string_count as i32).contains(&note.string))
+        .collect();
+    sources.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.string.cmp(&b.string)));
+
+    let mut notes: Vec<ExportNote> = sources
+        .iter()
+        .map(|note| ExportNote {
+            start: to_ticks(note.time),
+            end: 0,
+            string: note.string as usize,
+            fret: note.fret,
+            source: Some(note),
+            hammer_start: false,
+            pull_start: false,
+            slide_start: false,
+            slide_stop: false,
+        })
+        .collect();
+
+    let onsets: Vec<i64> = notes.iter().map(|note| note.start).collect();
+    let next_onset = |tick: i64| {
+        let index = onsets.partition_point(|&onset| onset <= tick);
+        onsets.get(index).copied()
+    };
+    for (note, source) in notes.iter_mut().zip(&sources) {
+        note.end = if source.sustain > 0.0 {
+            to_ticks(source.time + source.sustain)
+        } else {
+            let short = note.start + EXPORT_SHORT_NOTE_TICKS;
+            next_onset(note.start).map_or(short, |next| next.min(short))
+        }
+        .max(note.start + 1);
+    }
+
+    // Legato marks go on the note a hammer-on, pull-off or slide starts from.
+    let mut targets = Vec::new();
+    for string in 0..string_count {
+        let indices: Vec<usize> = (0..notes.len())
+            .filter(|&index| notes[index].string == string)
+            .collect();
+        for (position, &index) in indices.iter().enumerate() {
+            let source = sources[index];
+            if position > 0 {
+                let previous = indices[position - 1];
+                if source.techniques.contains(&Techniques::HammerOn) {
+                    notes[previous].hammer_start = true;
+                }
+                if source.techniques.contains(&Techniques::PullOff) {
+                    notes[previous].pull_start = true;
+                }
+            }
+            if source.slide_to < 0 || source.slide_to == source.fret {
+                continue;
+            }
+            notes[index].slide_start = true;
+            match indices.get(position + 1) {
+                Some(&next) if notes[next].fret == source.slide_to => notes[next].slide_stop = true,
+                _ => {
+                    let start = notes[index].end;
+                    targets.push(ExportNote {
+                        start,
+                        end: start + EXPORT_SHORT_NOTE_TICKS,
+                        string,
+                        fret: source.slide_to,
+                        source: None,
+                        hammer_start: false,
+                        pull_start: false,
+                        slide_start: false,
+                        slide_stop: true,
+                    });
+                }
+            }
+        }
+    }
+    notes.extend(targets);
+
+    let section_starts: Vec<(i64, &str)> = tab
+        .sections
+        .iter()
+        .map(|section| (to_ticks(section.start_time), section.name.as_str()))
+        .collect();
+    let last_tick = notes
+        .iter()
+        .map(|note| note.end)
+        .chain(
+            tab.sections
+                .iter()
+                .map(|section| to_ticks(section.end_time)),
+        )
+        .max()
+        .unwrap_or(0);
+    let measure_count = ((last_tick + EXPORT_MEASURE_TICKS - 1) / EXPORT_MEASURE_TICKS).max(1);
+    let song_end = measure_count * EXPORT_MEASURE_TICKS;
+
+    let mut grid: Vec<i64> = notes
+        .iter()
+        .flat_map(|note| [note.start, note.end])
+        .chain(section_starts.iter().map(|(tick, _)| *tick))
+        .chain((0..=measure_count).map(|measure| measure * EXPORT_MEASURE_TICKS))
+        .filter(|tick| (0..=song_end).contains(tick))
+        .collect();
+    grid.sort_unstable();
+    grid.dedup();
+
+    let _ = writeln!(xml, "  <part id=\"P{number}\">");
+    let mut segments = grid.windows(2).peekable();
+    for measure in 0..measure_count {
+        let measure_end = (measure + 1) * EXPORT_MEASURE_TICKS;
+        let _ = writeln!(xml, "    <measure number=\"{}\">", measure + 1);
+        if measure == 0 {
+            write_attributes(xml, tuning);
+        }
+
+        while let Some(segment) = segments.next_if(|segment| segment[1] <= measure_end) {
+            let (start, end) = (segment[0], segment[1]);
+            for (_, name) in section_starts.iter().filter(|(tick, _)| *tick == start) {
+                let _ = writeln!(
+                    xml,
+                    "      <direction placement=\"above\"><direction-type><rehearsal>{}</rehearsal></direction-type></direction>",
+                    escape(name)
+                );
+            }
+
+            let mut sounding: Vec<&ExportNote> = notes
+                .iter()
+                .filter(|note| note.start <= start && note.end > start)
+                .collect();
+            sounding.sort_by_key(|note| std::cmp::Reverse(note.string));
+            if sounding.is_empty() {
+                let _ = writeln!(
+                    xml,
+                    "      <note><rest/><duration>{}</duration><voice>1</voice></note>",
+                    end - start
+                );
+                continue;
+            }
+
+            if let Some(chord) = sounding
+                .iter()
+                .filter(|note| note.start == start)
+                .filter_map(|note| note.source)
+                .find(|note| note.chord_index >= 0)
+                .and_then(|note| tab.chords.get(note.chord_index as usize))
+            {
+                write_harmony(xml, &chord.name);
+            }
+
+            for (position, note) in sounding.iter().enumerate() {
+                write_note(
+                    xml,
+                    note,
+                    tuning,
+                    string_count,
+                    tab,
+                    NoteSlice {
+                        in_chord: position > 0,
+                        duration: end - start,
+                        struck: note.start == start,
+                        continues: note.end > end,
+                    },
+                );
+            }
+        }
+        xml.push_str("    </measure>\n");
+    }
+    xml.push_str("  </part>\n");
+}
+
+fn write_attributes(xml: &mut String, tuning: &InstrumentTuning) {
+    xml.push_str("      <attributes>\n");
+    let _ = writeln!(xml, "        <divisions>{EXPORT_DIVISIONS}</divisions>");
+    xml.push_str("        <key><fifths>0</fifths></key>\n");
+    xml.push_str("        <time><beats>4</beats><beat-type>4</beat-type></time>\n");
+    xml.push_str("        <clef><sign>TAB</sign><line>5</line></clef>\n");
+    xml.push_str("        <staff-details>\n");
+    let _ = writeln!(
+        xml,
+        "          <staff-lines>{}</staff-lines>",
+        tuning.open_strings.len()
+    );
+    for (line, &open) in tuning.open_strings.iter().enumerate() {
+        let (step, alter, octave) = pitch_from_midi(open);
+        let alter = if alter != 0 {
+            format!("<tuning-alter>{alter}</tuning-alter>")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            xml,
+            "          <staff-tuning line=\"{}\"><tuning-step>{step}</tuning-step>{alter}<tuning-octave>{octave}</tuning-octave></staff-tuning>",
+            line + 1
+        );
+    }
+    if tuning.capo_fret > 0 {
+        let _ = writeln!(xml, "          <capo>{}</capo>", tuning.capo_fret);
+    }
+    xml.push_str("        </staff-details>\n");
+    xml.push_str("      </attributes>\n");
+    let _ = writeln!(
+        xml,
+        "      <direction placement=\"above\"><direction-type><metronome><beat-unit>quarter</beat-unit><per-minute>{EXPORT_TEMPO}</per-minute></metronome></direction-type><sound tempo=\"{EXPORT_TEMPO}\"/></direction>"
+    );
+}
+
+fn write_harmony(xml: &mut String, name: &str) {
+    let mut chars = name.chars();
+    let Some(root @ 'A'..='G') = chars.next() else {
+        return;
+    };
+    let rest = chars.as_str();
+    let (alter, kind) = match rest.chars().next() {
+        Some('#') => (1, &rest[1..]),
+        Some('b') => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let alter = if alter != 0 {
+        format!("<root-alter>{alter}</root-alter>")
+    } else {
+        String::new()
+    };
+    let _ = writeln!(
+        xml,
+        "      <harmony><root><root-step>{root}</root-step>{alter}</root><kind text=\"{}\">none</kind></harmony>",
+        escape(kind)
+    );
+}
+
+// Where one piece of a written note sits after cutting at barlines and other onsets.
+struct NoteSlice {
+    in_chord: bool,
+    duration: i64,
+    struck: bool,
+    continues: bool,
+}
+
+fn write_note(
+    xml: &mut String,
+    note: &ExportNote,
+    tuning: &InstrumentTuning,
+    string_count: usize,
+    tab: &StringTab,
+    slice: NoteSlice,
+) {
+    let midi = tuning.note_midi(note.string, note.fret).unwrap_or(0);
+    let (step, alter, octave) = pitch_from_midi(midi);
+    // Tablature counts frets from the capo.
+    let written_fret = if note.fret > 0 {
+        (note.fret - tuning.capo_fret).max(0)
+    } else {
+        note.fret
+    };
+
+    xml.push_str("      <note>");
+    if slice.in_chord {
+        xml.push_str("<chord/>");
+    }
+    let _ = write!(xml, "<pitch><step>{step}</step>");
+    if alter != 0 {
+        let _ = write!(xml, "<alter>{alter}</alter>");
+    }
+    let _ = write!(
+        xml,
+        "<octave>{octave}</octave></pitch><duration>{}</duration>",
+        slice.duration
+    );
+    if !slice.struck {
+        xml.push_str("<tie type=\"stop\"/>");
+    }
+    if slice.continues {
+        xml.push_str("<tie type=\"start\"/>");
+    }
+    xml.push_str("<voice>1</voice>");
+    if let Some((kind, dots)) = note_type(slice.duration) {
+        let _ = write!(xml, "<type>{kind}</type>");
+        for _ in 0..dots {
+            xml.push_str("<dot/>");
+        }
+    }
+
+    xml.push_str("<notations>");
+    if !slice.struck {
+        xml.push_str("<tied type=\"stop\"/>");
+    }
+    if slice.continues {
+        xml.push_str("<tied type=\"start\"/>");
+    }
+
+    let source = note.source.filter(|_| slice.struck);
+    let has =
+        |technique: Techniques| source.is_some_and(|source| source.techniques.contains(&technique));
+    if slice.struck && note.slide_stop {
+        xml.push_str("<slide type=\"stop\" line-type=\"solid\"/>");
+    }
+    if slice.struck && note.slide_start {
+        xml.push_str("<slide type=\"start\" line-type=\"solid\"/>");
+    }
+    if has(Techniques::Vibrato) || has(Techniques::Tremolo) {
+        xml.push_str("<ornaments>");
+        if has(Techniques::Vibrato) {
+            xml.push_str("<wavy-line type=\"start\"/><wavy-line type=\"stop\"/>");
+        }
+        if has(Techniques::Tremolo) {
+            xml.push_str("<tremolo type=\"single\">3</tremolo>");
+        }
+        xml.push_str("</ornaments>");
+    }
+    if let Some(source) = source.filter(|source| source.slide_unpitch_to >= 0) {
+        let articulation = if source.slide_unpitch_to < source.fret {
+            "falloff"
+        } else {
+            "doit"
+        };
+        let _ = write!(xml, "<articulations><{articulation}/></articulations>");
+    }
+
+    let _ = write!(
+        xml,
+        "<technical><string>{}</string><fret>{written_fret}</fret>",
+        string_count - note.string
+    );
+    if let Some(finger) = source
+        .filter(|source| source.chord_index >= 0)
+        .and_then(|source| tab.chords.get(source.chord_index as usize))
+        .and_then(|chord| chord.fingers.get(note.string))
+        .filter(|finger| **finger > 0)
+    {
+        let _ = write!(xml, "<fingering>{finger}</fingering>");
+    }
+    if has(Techniques::HammerOn) {
+        xml.push_str("<hammer-on type=\"stop\"/>");
+    }
+    if has(Techniques::PullOff) {
+        xml.push_str("<pull-off type=\"stop\"/>");
+    }
+    if slice.struck && note.hammer_start {
+        xml.push_str("<hammer-on type=\"start\">H</hammer-on>");
+    }
+    if slice.struck && note.pull_start {
+        xml.push_str("<pull-off type=\"start\">P</pull-off>");
+    }
+    if let Some(source) = source.filter(|source| source.max_bend > 0.0) {
+        let _ = write!(
+            xml,
+            "<bend><bend-alter>{}</bend-alter></bend>",
+            source.max_bend
+        );
+    }
+    if has(Techniques::PinchHarmonic) {
+        xml.push_str("<harmonic><artificial/></harmonic>");
+    } else if has(Techniques::Harmonic) {
+        xml.push_str("<harmonic><natural/></harmonic>");
+    }
+    if has(Techniques::Tap) {
+        xml.push_str("<tap/>");
+    }
+    for (technique, text) in [
+        (Techniques::PalmMute, PALM_MUTE_TEXT),
+        (Techniques::Slap, SLAP_TEXT),
+        (Techniques::Pop, POP_TEXT),
+    ] {
+        if has(technique) {
+            let _ = write!(xml, "<other-technical>{text}</other-technical>");
+        }
+    }
+    xml.push_str("</technical></notations></note>\n");
+}
+
+fn note_type(duration: i64) -> Option<(&'static str, usize)> {
+    const TYPES: [(&str, i64); 7] = [
+        ("whole", 4 * EXPORT_DIVISIONS),
+        ("half", 2 * EXPORT_DIVISIONS),
+        ("quarter", EXPORT_DIVISIONS),
+        ("eighth", EXPORT_DIVISIONS / 2),
+        ("16th", EXPORT_DIVISIONS / 4),
+        ("32nd", EXPORT_DIVISIONS / 8),
+        ("64th", EXPORT_DIVISIONS / 16),
+    ];
+    TYPES.iter().find_map(|&(name, ticks)| {
+        if duration == ticks {
+            Some((name, 0))
+        } else if duration * 2 == ticks * 3 {
+            Some((name, 1))
+        } else {
+            None
+        }
+    })
+}
+
+fn pitch_from_midi(midi: i32) -> (&'static str, i32, i32) {
+    let class = midi.rem_euclid(12) as usize;
+    let alter = matches!(class, 1 | 3 | 6 | 8 | 10) as i32;
+    (STEP_NAMES[class], alter, midi.div_euclid(12) - 1)
+}
+
+fn midi_from_pitch(step: &str, alter: i32, octave: i32) -> Option<i32> {
+    let class = match step {
+        "C" => 0,
+        "D" => 2,
+        "E" => 4,
+        "F" => 5,
+        "G" => 7,
+        "A" => 9,
+        "B" => 11,
+        _ => return None,
+    };
+    Some((octave + 1) * 12 + class + alter)
+}
+
+fn ending_bits(numbers: Option<&str>) -> u8 {
+    numbers
+        .unwrap_or("1")
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|number| number.parse::<u32>().ok())
+        .filter(|number| (1..=8).contains(number))
+        .fold(0, |bits, number| bits | 1 << (number - 1))
+}
+
+fn sound_tempo(sound: Node) -> Option<f64> {
+    sound.attribute("tempo")?.parse().ok()
+}
+
+fn harmony_name(harmony: Node) -> Option<String> {
+    let root = child(harmony, "root")?;
+    let step = descendant_text(root, &["root-step"])?;
+    let alter = match child_number(root, "root-alter").unwrap_or(0.0) as i32 {
+        1 => "#",
+        -1 => "b",
+        _ => "",
+    };
+    let kind = child(harmony, "kind")
+        .and_then(|kind| kind.attribute("text"))
+        .unwrap_or("");
+    Some(format!("{step}{alter}{kind}"))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn child_number(node: Node, name: &str) -> Option<f64> {
+    child(node, name)?.text()?.trim().parse().ok()
+}
+
+fn descendant_text(node: Node, path: &[&str]) -> Option<String> {
+    let mut current = node;
+    for name in path {
+        current = child(current, name)?;
+    }
+    Some(current.text()?.trim().to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
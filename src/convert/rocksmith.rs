@@ -0,0 +1,383 @@
+use std::fs;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+use crate::convert::{
+    plain_note, sections_from_markers, used_techniques, ConvertError, ImportedSong,
+};
+use crate::file::song::{
+    SongArrangementMetadata, StringTab, Tab, TabChord, TabNote, TabNoteChart, TabsInstrument,
+    Techniques, VocalPhrase, VocalTab,
+};
+
+// Chord templates marked this way are played one string at a time.
+const ARPEGGIO_SUFFIX: &str = "-arp";
+
+/// Whether `text` is a Rocksmith arrangement or vocals XML rather than another XML format.
+pub fn is_rocksmith_xml(text: &str) -> bool {
+    Document::parse(text).is_ok_and(|document| {
+        matches!(document.root_element().tag_name().name(), "song" | "vocals")
+    })
+}
+
+/// Imports every Rocksmith arrangement and vocals XML in `folder` as one song. Other XML
+/// files, such as show lights, are skipped.
+pub fn import_rocksmith_folder(folder: &Path) -> Result<ImportedSong, ConvertError> {
+    let mut paths: Vec<_> = fs::read_dir(folder)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("xml"))
+        })
+        .collect();
+    paths.sort();
+
+    let mut texts = Vec::new();
+    for path in paths {
+        let text = fs::read_to_string(&path)?;
+        if is_rocksmith_xml(&text) {
+            texts.push(text);
+        }
+    }
+    import_rocksmith_xml(&texts.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+/// Reads a single Rocksmith arrangement or vocals XML file.
+pub fn import_rocksmith_file(path: &Path) -> Result<ImportedSong, ConvertError> {
+    let text = fs::read_to_string(path)?;
+    import_rocksmith_xml(&[&text])
+}
+
+/// Converts Rocksmith arrangement XML documents (`<song>`, one per arrangement) and
+/// vocals documents (`<vocals>`) into a single song. Every level becomes a note chart
+/// with the same difficulty.
+pub fn import_rocksmith_xml(documents: &[&str]) -> Result<ImportedSong, ConvertError> {
+    let mut imported = ImportedSong::new("", "", "", 0.0);
+    let mut vocals = Vec::new();
+
+    for text in documents {
+        let document =
+            Document::parse(text).map_err(|err| ConvertError::InvalidData(err.to_string()))?;
+        let root = document.root_element();
+        match root.tag_name().name() {
+            "song" => import_arrangement(root, &mut imported),
+            "vocals" => vocals.push(read_vocals(root)),
+            other => {
+                return Err(ConvertError::UnsupportedFormat(format!(
+                    "Rocksmith XML <{other}>"
+                )))
+            }
+        }
+    }
+
+    for vocal_tab in vocals {
+        let metadata = SongArrangementMetadata {
+            name: "Vocals".to_string(),
+            file: String::new(),
+            capo_fret: None,
+            instrument: TabsInstrument::Vocals,
+            string_count: None,
+            string_semitone_offset: None,
+            techniques: Vec::new(),
+        };
+        if let Some(end) = vocal_tab
+            .vocals
+            .iter()
+            .map(|vocal| vocal.time + vocal.length)
+            .reduce(f32::max)
+        {
+            imported.metadata.length = imported.metadata.length.max(end);
+        }
+        imported.add_arrangement("Vocals", metadata, Tab::Vocals(vocal_tab));
+    }
+
+    if imported.arrangements.is_empty() {
+        return Err(ConvertError::NoStringTracks);
+    }
+    Ok(imported)
+}
+
+fn import_arrangement(song: Node, imported: &mut ImportedSong) {
+    let metadata = &mut imported.metadata;
+    let fill = |field: &mut String, name: &str| {
+        if field.is_empty() {
+            *field = child_text(song, name).unwrap_or_default();
+        }
+    };
+    fill(&mut metadata.title, "title");
+    fill(&mut metadata.artist, "artistName");
+    fill(&mut metadata.album, "albumName");
+    if metadata.year == 0 {
+        metadata.year = child_number(song, "albumYear").unwrap_or(0.0) as i32;
+    }
+    let song_length = child_number(song, "songLength").unwrap_or(0.0) as f32;
+    metadata.length = metadata.length.max(song_length);
+
+    let name = child_text(song, "arrangement").unwrap_or_else(|| "Lead".to_string());
+    let is_bass = name.eq_ignore_ascii_case("bass")
+        || child(song, "arrangementProperties")
+            .is_some_and(|properties| properties.attribute("pathBass") == Some("1"));
+    let (instrument, string_count) = if is_bass {
+        (TabsInstrument::Bass, 4)
+    } else {
+        (TabsInstrument::Guitar, 6)
+    };
+    let offsets: Vec<i32> = (0..string_count)
+        .map(|string| {
+            child(song, "tuning")
+                .map(|tuning| attr_i32(tuning, &format!("string{string}"), 0))
+                .unwrap_or(0)
+        })
+        .collect();
+    let capo = child_number(song, "capo").unwrap_or(0.0) as i32;
+
+    let markers: Vec<(String, f32)> = children(song, "sections", "section")
+        .map(|section| {
+            (
+                section.attribute("name").unwrap_or_default().to_string(),
+                attr_f32(section, "startTime", 0.0),
+            )
+        })
+        .collect();
+
+    let chords: Vec<TabChord> = children(song, "chordTemplates", "chordTemplate")
+        .map(|template| TabChord {
+            name: template
+                .attribute("chordName")
+                .unwrap_or_default()
+                .to_string(),
+            fingers: (0..string_count)
+                .map(|string| attr_i32(template, &format!("finger{string}"), -1))
+                .collect(),
+            frets: (0..string_count)
+                .map(|string| attr_i32(template, &format!("fret{string}"), -1))
+                .collect(),
+        })
+        .collect();
+    let arpeggios: Vec<bool> = children(song, "chordTemplates", "chordTemplate")
+        .map(|template| {
+            template
+                .attribute("displayName")
+                .is_some_and(|name| name.ends_with(ARPEGGIO_SUFFIX))
+        })
+        .collect();
+
+    let note_charts: Vec<TabNoteChart> = children(song, "levels", "level")
+        .map(|level| read_level(level, &chords, &arpeggios))
+        .filter(|chart| !chart.notes.is_empty())
+        .collect();
+    if note_charts.is_empty() {
+        return;
+    }
+
+    let all_notes: Vec<TabNote> = note_charts
+        .iter()
+        .flat_map(|chart| chart.notes.iter().cloned())
+        .collect();
+    let arrangement = SongArrangementMetadata {
+        name: name.clone(),
+        file: String::new(),
+        capo_fret: (capo > 0).then_some(capo),
+        instrument,
+        string_count: Some(string_count),
+        string_semitone_offset: Some(offsets),
+        techniques: used_techniques(&all_notes),
+    };
+    let tab = StringTab {
+        sections: sections_from_markers(&markers, song_length),
+        chords,
+        note_charts,
+    };
+    imported.add_arrangement(&name, arrangement, Tab::Strings(tab));
+}
+
+fn read_level(level: Node, chords: &[TabChord], arpeggios: &[bool]) -> TabNoteChart {
+    let mut anchors: Vec<(f32, i32)> = children(level, "anchors", "anchor")
+        .map(|anchor| (attr_f32(anchor, "time", 0.0), attr_i32(anchor, "fret", -1)))
+        .collect();
+    anchors.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let anchor_at = |time: f32| {
+        let index = anchors.partition_point(|(start, _)| *start <= time);
+        index.checked_sub(1).map_or(-1, |index| anchors[index].1)
+    };
+
+    // Hand shapes over arpeggio templates mark the single notes inside them.
+    let arpeggio_shapes: Vec<(f32, f32, i32)> = children(level, "handShapes", "handShape")
+        .filter_map(|shape| {
+            let chord = attr_i32(shape, "chordId", -1);
+            let is_arpeggio = arpeggios.get(usize::try_from(chord).ok()?).copied()?;
+            is_arpeggio.then(|| {
+                (
+                    attr_f32(shape, "startTime", 0.0),
+                    attr_f32(shape, "endTime", 0.0),
+                    chord,
+                )
+            })
+        })
+        .collect();
+
+    let mut notes = Vec::new();
+    for node in children(level, "notes", "note") {
+        if attr_i32(node, "ignore", 0) != 0 {
+            continue;
+        }
+        let mut note = read_note(node, attr_f32(node, "time", 0.0));
+        note.anchor_fret = anchor_at(note.time);
+        if let Some((_, _, chord)) = arpeggio_shapes
+            .iter()
+            .find(|(start, end, _)| note.time >= *start && note.time < *end)
+        {
+            note.chord_index = *chord;
+            note.techniques.push(Techniques::Arpeggio);
+        }
+        notes.push(note);
+    }
+
+    for chord in children(level, "chords", "chord") {
+        if attr_i32(chord, "ignore", 0) != 0 {
+            continue;
+        }
+        let time = attr_f32(chord, "time", 0.0);
+        let chord_index = attr_i32(chord, "chordId", -1);
+        let mut chord_notes: Vec<TabNote> = chord
+            .children()
+            .filter(|node| node.has_tag_name("chordNote"))
+            .map(|node| read_note(node, time))
+            .collect();
+        // Chords without techniques only reference their template.
+        if chord_notes.is_empty() {
+            if let Some(template) = usize::try_from(chord_index)
+                .ok()
+                .and_then(|index| chords.get(index))
+            {
+                chord_notes = template
+                    .frets
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fret)| **fret >= 0)
+                    .map(|(string, fret)| plain_note(time, string as i32, *fret))
+                    .collect();
+            }
+        }
+        let palm_mute = attr_i32(chord, "palmMute", 0) != 0;
+        for mut note in chord_notes {
+            note.chord_index = chord_index;
+            note.anchor_fret = anchor_at(time);
+            note.techniques.insert(0, Techniques::Chord);
+            if palm_mute && !note.techniques.contains(&Techniques::PalmMute) {
+                note.techniques.push(Techniques::PalmMute);
+            }
+            notes.push(note);
+        }
+    }
+
+    notes.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.string.cmp(&b.string)));
+    TabNoteChart {
+        difficulty: attr_i32(level, "difficulty", 0),
+        notes,
+    }
+}
+
+fn read_note(node: Node, time: f32) -> TabNote {
+    let mut note = plain_note(time, attr_i32(node, "string", 0), attr_i32(node, "fret", 0));
+    note.sustain = attr_f32(node, "sustain", 0.0);
+    note.slide_to = attr_i32(node, "slideTo", -1);
+    note.slide_unpitch_to = attr_i32(node, "slideUnpitchTo", -1);
+    note.vibrato = attr_i32(node, "vibrato", 0);
+    note.max_bend = attr_f32(node, "bend", 0.0);
+    note.slap = attr_i32(node, "slap", -1);
+    note.pluck = attr_i32(node, "pluck", -1);
+    note.tap = attr_i32(node, "tap", 0);
+
+    let flag = |name: &str| attr_i32(node, name, 0) != 0;
+    let techniques = &mut note.techniques;
+    if note.slide_to >= 0 || note.slide_unpitch_to >= 0 {
+        techniques.push(Techniques::Slide);
+    }
+    if note.max_bend > 0.0 {
+        techniques.push(Techniques::Bend);
+    }
+    if flag("tremolo") {
+        techniques.push(Techniques::Tremolo);
+    }
+    if flag("harmonic") {
+        techniques.push(Techniques::Harmonic);
+    }
+    if flag("hammerOn") {
+        techniques.push(Techniques::HammerOn);
+    }
+    if flag("pullOff") {
+        techniques.push(Techniques::PullOff);
+    }
+    if flag("palmMute") {
+        techniques.push(Techniques::PalmMute);
+    }
+    if note.vibrato > 0 {
+        techniques.push(Techniques::Vibrato);
+    }
+    if note.tap > 0 {
+        techniques.push(Techniques::Tap);
+    }
+    if note.slap > 0 {
+        techniques.push(Techniques::Slap);
+    }
+    if note.pluck > 0 {
+        techniques.push(Techniques::Pop);
+    }
+    if flag("harmonicPinch") {
+        techniques.push(Techniques::PinchHarmonic);
+    }
+    note
+}
+
+fn read_vocals(root: Node) -> VocalTab {
+    let mut vocals: Vec<VocalPhrase> = root
+        .children()
+        .filter(|node| node.has_tag_name("vocal"))
+        .map(|vocal| VocalPhrase {
+            time: attr_f32(vocal, "time", 0.0),
+            length: attr_f32(vocal, "length", 0.0),
+            lyric: vocal.attribute("lyric").unwrap_or_default().to_string(),
+        })
+        .collect();
+    vocals.sort_by(|a, b| a.time.total_cmp(&b.time));
+    VocalTab { vocals }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+// Elements named `item` inside the `list` child of `node`.
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    list: &'a str,
+    item: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    child(node, list)
+        .into_iter()
+        .flat_map(|list| list.children())
+        .filter(move |child| child.has_tag_name(item))
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    let text = child(node, name)?.text()?.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn child_number(node: Node, name: &str) -> Option<f64> {
+    child_text(node, name)?.parse().ok()
+}
+
+fn attr_f32(node: Node, name: &str, default: f32) -> f32 {
+    node.attribute(name)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+fn attr_i32(node: Node, name: &str, default: i32) -> i32 {
+    node.attribute(name)
+        .and_then(|value| value.trim().parse::<f32>().ok())
+        .map_or(default, |value| value as i32)
+}
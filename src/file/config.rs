@@ -3,6 +3,8 @@ use serde::Deserialize;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::file::library::LibraryRoots;
+
 #[derive(Debug, Deserialize, Resource)]
 pub struct AppConfig {
     pub window: WindowConfig,
@@ -18,6 +20,11 @@ pub struct WindowConfig {
 #[derive(Debug, Deserialize, Resource)]
 pub struct PathConfig {
     pub song_directory: String,
+    /// More folders to find songs in; see [`LibraryRoot::from_setting`].
+    ///
+    /// [`LibraryRoot::from_setting`]: crate::file::library::LibraryRoot::from_setting
+    #[serde(default)]
+    pub library_roots: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Resource)]
@@ -37,6 +44,7 @@ impl Plugin for ConfigPlugin {
             fs::create_dir_all(&save_path).expect("Failed to create save directory");
         }
         config.saves.directory = save_path.into_os_string().into_string().unwrap();
+        app.insert_resource(LibraryRoots::from_config(&config));
         app.insert_resource(config);
     }
 }
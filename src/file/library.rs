@@ -0,0 +1,313 @@
+use bevy::asset::io::AssetSourceId;
+use bevy::asset::AssetPath;
+use bevy::prelude::*;
+use bevy::tasks::futures::check_ready;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::file::package::{read_song_file, split_package_path};
+use crate::file::song::{Song, SongMetadata};
+use crate::file::AppConfig;
+
+pub const ASSET_FOLDER: &str = "assets";
+pub const LIBRARY_INDEX_FILE: &str = "library.index";
+/// Bumped whenever the index layout changes, so old indexes are rebuilt.
+pub const LIBRARY_INDEX_VERSION: u32 = 2;
+/// Replaced by the user's data directory at the start of a library root setting.
+pub const DATA_DIR_PLACEHOLDER: &str = "{data}";
+// Saving a chart often touches several files; they are picked up together.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone)]
+pub struct LibraryRoot {
+    pub source: AssetSourceId<'static>,
+    pub directory: PathBuf,
+    /// Folder holding the songs, relative to `directory`.
+    pub songs: PathBuf,
+}
+
+impl LibraryRoot {
+    /// Relative settings stay in the asset folder; others get the asset source `source`.
+    pub fn from_setting(setting: &str, source: &str) -> Option<Self> {
+        let directory = match setting.strip_prefix(DATA_DIR_PLACEHOLDER) {
+            Some(rest) => dirs::data_dir()?.join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(setting),
+        };
+        if directory.is_relative() {
+            return Some(LibraryRoot {
+                source: AssetSourceId::Default,
+                directory: PathBuf::from(ASSET_FOLDER),
+                songs: directory,
+            });
+        }
+        Some(LibraryRoot {
+            source: AssetSourceId::from(source.to_string()),
+            directory,
+            songs: PathBuf::new(),
+        })
+    }
+
+    pub fn song_folder(&self) -> PathBuf {
+        self.directory.join(&self.songs)
+    }
+
+    /// `path` is relative to `directory`.
+    pub fn asset_path(&self, path: &Path) -> AssetPath<'static> {
+        AssetPath::from_path_buf(path.to_path_buf()).with_source(self.source.clone())
+    }
+}
+
+/// The first root is always the song directory inside the asset folder.
+#[derive(Resource, Debug, Clone)]
+pub struct LibraryRoots {
+    roots: Vec<LibraryRoot>,
+}
+
+impl LibraryRoots {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut roots = vec![LibraryRoot {
+            source: AssetSourceId::Default,
+            directory: PathBuf::from(ASSET_FOLDER),
+            songs: PathBuf::from(&config.paths.song_directory),
+        }];
+        for (index, setting) in config.paths.library_roots.iter().enumerate() {
+            match LibraryRoot::from_setting(setting, &format!("library-{}", index + 1)) {
+                Some(root) => roots.push(root),
+                None => warn!("Skipping library root '{setting}': no user data directory"),
+            }
+        }
+        LibraryRoots { roots }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LibraryRoot> {
+        self.roots.iter()
+    }
+
+    pub fn file_path(&self, path: &AssetPath) -> Option<PathBuf> {
+        self.roots
+            .iter()
+            .find(|root| &root.source == path.source())
+            .map(|root| root.directory.join(path.path()))
+    }
+}
+
+pub fn song_asset_path(metadata_path: &AssetPath, file: &str) -> AssetPath<'static> {
+    AssetPath::from_path_buf(metadata_path.path().with_file_name(file))
+        .with_source(metadata_path.source().clone_owned())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub metadata_path: AssetPath<'static>,
+    /// Nanoseconds since the Unix epoch.
+    pub modified: u64,
+    pub metadata: SongMetadata,
+}
+
+impl LibraryEntry {
+    pub fn asset_path(&self, file: &str) -> AssetPath<'static> {
+        song_asset_path(&self.metadata_path, file)
+    }
+}
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    version: u32,
+    /// Keyed by the asset path of the song folder.
+    songs: BTreeMap<String, LibraryEntry>,
+}
+
+impl Default for LibraryIndex {
+    fn default() -> Self {
+        LibraryIndex {
+            version: LIBRARY_INDEX_VERSION,
+            songs: BTreeMap::new(),
+        }
+    }
+}
+
+impl LibraryIndex {
+    /// Starts over when the index is missing, unreadable or from another version.
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = fs::read(path) else {
+            return LibraryIndex::default();
+        };
+        match serde_yaml::from_slice::<LibraryIndex>(&content) {
+            Ok(index) if index.version == LIBRARY_INDEX_VERSION => index,
+            Ok(_) => LibraryIndex::default(),
+            Err(err) => {
+                warn!("Rebuilding library index '{}': {err}", path.display());
+                LibraryIndex::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let yaml = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, yaml)
+    }
+
+    pub fn songs(&self) -> impl Iterator<Item = &LibraryEntry> {
+        self.songs.values()
+    }
+
+    /// Only re-reads songs that changed since they were indexed; returns whether any did.
+    pub fn refresh(&mut self, roots: &LibraryRoots) -> bool {
+        let mut changed = false;
+        let mut found = HashSet::new();
+
+        for (root, relative_path) in roots.iter().flat_map(|root| {
+            Song::get_all_songs(root)
+                .into_iter()
+                .map(move |path| (root, path))
+        }) {
+            let file_path = root.directory.join(&relative_path);
+            let Some(modified) = modified_time(&file_path) else {
+                continue;
+            };
+            let metadata_path = root.asset_path(&relative_path);
+            let Some(key) = metadata_path.parent().map(|folder| folder.to_string()) else {
+                continue;
+            };
+            found.insert(key.clone());
+
+            let unchanged = self.songs.get(&key).is_some_and(|entry| {
+                entry.modified == modified && entry.metadata_path == metadata_path
+            });
+            if unchanged {
+                continue;
+            }
+
+            changed = true;
+            match read_metadata(&file_path) {
+                Ok(metadata) => {
+                    self.songs.insert(
+                        key,
+                        LibraryEntry {
+                            metadata_path,
+                            modified,
+                            metadata,
+                        },
+                    );
+                }
+                Err(err) => {
+                    warn!("Skipping song '{key}': {err}");
+                    self.songs.remove(&key);
+                }
+            }
+        }
+
+        let indexed = self.songs.len();
+        self.songs.retain(|key, _| found.contains(key));
+        changed || self.songs.len() != indexed
+    }
+}
+
+fn library_index_path(config: &AppConfig) -> PathBuf {
+    Path::new(&config.saves.directory).join(LIBRARY_INDEX_FILE)
+}
+
+pub fn load_library_index(config: &AppConfig) -> LibraryIndex {
+    LibraryIndex::load(&library_index_path(config))
+}
+
+/// Returns whether the index changed, in which case it was written back to `path`.
+pub fn update_library_index(index: &mut LibraryIndex, path: &Path, roots: &LibraryRoots) -> bool {
+    if !index.refresh(roots) {
+        return false;
+    }
+    if let Err(err) = index.save(path) {
+        error!("Failed to write library index '{}': {err}", path.display());
+    }
+    true
+}
+
+// Walking the roots and re-reading metadata can take a while on large libraries, so it
+// runs off the main thread while the list shows the persisted index.
+#[derive(Resource)]
+pub struct LibraryRefresh(Task<Option<LibraryIndex>>);
+
+impl LibraryRefresh {
+    pub fn start(mut index: LibraryIndex, config: &AppConfig, roots: &LibraryRoots) -> Self {
+        let path = library_index_path(config);
+        let roots = roots.clone();
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { update_library_index(&mut index, &path, &roots).then_some(index) });
+        LibraryRefresh(task)
+    }
+
+    /// `Some` once the task is done, holding the new index if anything changed.
+    pub fn poll(&mut self) -> Option<Option<LibraryIndex>> {
+        check_ready(&mut self.0)
+    }
+}
+
+#[derive(Resource)]
+pub struct LibraryWatcher {
+    changed: Arc<AtomicBool>,
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl LibraryWatcher {
+    pub fn new(song_folders: &[PathBuf]) -> notify_debouncer_full::notify::Result<Self> {
+        let changed = Arc::new(AtomicBool::new(false));
+        let flag = changed.clone();
+        let mut debouncer = new_debouncer(
+            WATCH_DEBOUNCE,
+            None,
+            move |result: DebounceEventResult| match result {
+                Ok(events) if !events.is_empty() => flag.store(true, Ordering::Relaxed),
+                Ok(_) => {}
+                Err(errors) => {
+                    for err in errors {
+                        warn!("Song directory watcher: {err}");
+                    }
+                }
+            },
+        )?;
+        for folder in song_folders {
+            if let Err(err) = debouncer.watch(folder, RecursiveMode::Recursive) {
+                warn!("Not watching song folder '{}': {err}", folder.display());
+            }
+        }
+        Ok(LibraryWatcher {
+            changed,
+            _debouncer: debouncer,
+        })
+    }
+
+    /// Whether anything changed since the last call.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+pub fn watch_library_roots(mut commands: Commands, roots: Res<LibraryRoots>) {
+    let song_folders: Vec<PathBuf> = roots.iter().map(LibraryRoot::song_folder).collect();
+    match LibraryWatcher::new(&song_folders) {
+        Ok(watcher) => commands.insert_resource(watcher),
+        Err(err) => warn!("Not watching the song library: {err}"),
+    }
+}
+
+fn read_metadata(metadata_path: &Path) -> Result<SongMetadata, String> {
+    let bytes = read_song_file(metadata_path).map_err(|err| err.to_string())?;
+    SongMetadata::from_yaml(&bytes).map_err(|err| err.to_string())
+}
+
+// Packages are only ever rewritten whole, so their own time stands in for the metadata's.
+fn modified_time(metadata_path: &Path) -> Option<u64> {
+    let file = split_package_path(metadata_path)
+        .map_or_else(|| metadata_path.to_path_buf(), |(package, _)| package);
+    let modified = fs::metadata(file).ok()?.modified().ok()?;
+    u64::try_from(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos()).ok()
+}
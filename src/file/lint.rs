@@ -0,0 +1,301 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::audio::AUDIO_EXTENSIONS;
+use crate::file::package::{read_song_file, song_file_exists};
+use crate::file::song::{
+    SongArrangementMetadata, SongMetadata, StringTab, Tab, TabNoteChart, VocalTab,
+};
+use crate::input::InstrumentTuning;
+
+/// Files the game loads from every song folder, besides those named in `song.metadata`.
+pub const REQUIRED_SONG_FILES: [&str; 1] = ["album_art.png"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    /// The game fails to load or play the song.
+    Error,
+    /// The song loads, but the chart is probably not what the author meant.
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub severity: LintSeverity,
+    /// Key of the arrangement the issue is in, if it is about one.
+    pub arrangement: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SongLintReport {
+    pub folder: PathBuf,
+    pub issues: Vec<LintIssue>,
+}
+
+impl SongLintReport {
+    pub fn count(&self, severity: LintSeverity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    fn push(&mut self, severity: LintSeverity, arrangement: Option<&str>, message: String) {
+        self.issues.push(LintIssue {
+            severity,
+            arrangement: arrangement.map(str::to_string),
+            message,
+        });
+    }
+}
+
+/// Checks a song folder for problems that would stop it loading or make its charts
+/// misbehave in game.
+pub fn lint_song_folder(folder: &Path) -> SongLintReport {
+    let mut report = SongLintReport {
+        folder: folder.to_path_buf(),
+        issues: Vec::new(),
+    };
+
+    for file in REQUIRED_SONG_FILES {
+        if !song_file_exists(&folder.join(file)) {
+            report.push(LintSeverity::Error, None, format!("missing {file}"));
+        }
+    }
+
+    let metadata = match read_song_file(&folder.join("song.metadata")) {
+        Ok(bytes) => SongMetadata::from_yaml(&bytes).map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            report.push(
+                LintSeverity::Error,
+                None,
+                format!("failed to read song.metadata: {err}"),
+            );
+            return report;
+        }
+    };
+
+    for file in [&metadata.audio, &metadata.preview] {
+        lint_audio_file(&mut report, folder, file);
+    }
+
+    let mut keys: Vec<&String> = metadata.arrangements.keys().collect();
+    keys.sort();
+    for key in keys {
+        let arrangement = &metadata.arrangements[key];
+        let bytes = match read_song_file(&folder.join(&arrangement.file)) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                let message = if err.kind() == ErrorKind::NotFound {
+                    format!("missing tab file {}", arrangement.file)
+                } else {
+                    format!("failed to read {}: {err}", arrangement.file)
+                };
+                report.push(LintSeverity::Error, Some(key), message);
+                continue;
+            }
+        };
+        match Tab::from_yaml(&bytes) {
+            Ok(Tab::Strings(tab)) => {
+                lint_string_tab(&mut report, key, arrangement, &metadata, &tab)
+            }
+            Ok(Tab::Vocals(tab)) => lint_vocal_tab(&mut report, key, &metadata, &tab),
+            Err(err) => report.push(
+                LintSeverity::Error,
+                Some(key),
+                format!("failed to parse {}: {err}", arrangement.file),
+            ),
+        }
+    }
+    report
+}
+
+fn lint_audio_file(report: &mut SongLintReport, folder: &Path, file: &str) {
+    if !song_file_exists(&folder.join(file)) {
+        report.push(LintSeverity::Error, None, format!("missing {file}"));
+        return;
+    }
+    let supported = Path::new(file)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            AUDIO_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        });
+    if !supported {
+        report.push(
+            LintSeverity::Error,
+            None,
+            format!(
+                "{file} is not a supported audio format ({})",
+                AUDIO_EXTENSIONS.join(", ")
+            ),
+        );
+    }
+}
+
+fn lint_string_tab(
+    report: &mut SongLintReport,
+    key: &str,
+    arrangement: &SongArrangementMetadata,
+    metadata: &SongMetadata,
+    tab: &StringTab,
+) {
+    for pair in tab.sections.windows(2) {
+        let (previous, section) = (&pair[0], &pair[1]);
+        if section.start_time < previous.start_time {
+            report.push(
+                LintSeverity::Warning,
+                Some(key),
+                format!(
+                    "section '{}' at {:.2}s comes after '{}' at {:.2}s",
+                    section.name, section.start_time, previous.name, previous.start_time
+                ),
+            );
+        } else if section.start_time < previous.end_time {
+            report.push(
+                LintSeverity::Warning,
+                Some(key),
+                format!(
+                    "section '{}' overlaps '{}' ({:.2}s < {:.2}s)",
+                    section.name, previous.name, section.start_time, previous.end_time
+                ),
+            );
+        }
+    }
+    for section in &tab.sections {
+        if section.end_time < section.start_time {
+            report.push(
+                LintSeverity::Warning,
+                Some(key),
+                format!("section '{}' ends before it starts", section.name),
+            );
+        }
+    }
+
+    let mut undeclared = BTreeSet::new();
+    for chart in &tab.note_charts {
+        lint_note_chart(report, key, arrangement, metadata, tab, chart);
+        undeclared.extend(
+            chart
+                .notes
+                .iter()
+                .flat_map(|note| &note.techniques)
+                .filter(|technique| !arrangement.techniques.contains(technique))
+                .map(|technique| format!("{technique:?}")),
+        );
+    }
+    if !undeclared.is_empty() {
+        report.push(
+            LintSeverity::Warning,
+            Some(key),
+            format!(
+                "techniques used but not declared in song.metadata: {}",
+                undeclared.into_iter().collect::<Vec<_>>().join(", ")
+            ),
+        );
+    }
+}
+
+// Each check reports once per chart with a count, so a broken chart does not drown
+// the output in one line per note.
+fn lint_note_chart(
+    report: &mut SongLintReport,
+    key: &str,
+    arrangement: &SongArrangementMetadata,
+    metadata: &SongMetadata,
+    tab: &StringTab,
+    chart: &TabNoteChart,
+) {
+    let difficulty = chart.difficulty;
+    let mut report_notes = |severity, times: Vec<f32>, what: String| {
+        if let Some(first) = times.first() {
+            report.push(
+                severity,
+                Some(key),
+                format!(
+                    "difficulty {difficulty}: {} note(s) {what}, first at {first:.2}s",
+                    times.len()
+                ),
+            );
+        }
+    };
+
+    // Without a string count the game plays the instrument's standard strings.
+    let string_count = InstrumentTuning::from_arrangement(arrangement)
+        .open_strings
+        .len() as i32;
+    let times = chart
+        .notes
+        .iter()
+        .filter(|note| note.string < 0 || note.string >= string_count)
+        .map(|note| note.time)
+        .collect();
+    report_notes(
+        LintSeverity::Error,
+        times,
+        format!("on a string outside the {string_count} strings"),
+    );
+
+    let chord_count = tab.chords.len() as i32;
+    let times = chart
+        .notes
+        .iter()
+        .filter(|note| note.chord_index < -1 || note.chord_index >= chord_count)
+        .map(|note| note.time)
+        .collect();
+    report_notes(
+        LintSeverity::Error,
+        times,
+        format!("with a chord_index outside the {chord_count} chords"),
+    );
+
+    let times = chart
+        .notes
+        .iter()
+        .filter(|note| note.time > metadata.length)
+        .map(|note| note.time)
+        .collect();
+    report_notes(
+        LintSeverity::Warning,
+        times,
+        format!("after the song ends at {:.2}s", metadata.length),
+    );
+
+    if chart
+        .notes
+        .windows(2)
+        .any(|pair| pair[1].time < pair[0].time)
+    {
+        report.push(
+            LintSeverity::Warning,
+            Some(key),
+            format!("difficulty {difficulty}: notes are not sorted by time"),
+        );
+    }
+}
+
+fn lint_vocal_tab(report: &mut SongLintReport, key: &str, metadata: &SongMetadata, tab: &VocalTab) {
+    let late = tab
+        .vocals
+        .iter()
+        .filter(|phrase| phrase.time > metadata.length)
+        .count();
+    if late > 0 {
+        report.push(
+            LintSeverity::Warning,
+            Some(key),
+            format!(
+                "{late} phrase(s) after the song ends at {:.2}s",
+                metadata.length
+            ),
+        );
+    }
+}
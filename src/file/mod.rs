@@ -1,9 +1,15 @@
 pub mod config;
+pub mod library;
+pub mod lint;
+pub mod package;
+pub mod schema;
 pub mod settings;
 pub mod song;
+pub mod tab_cache;
 pub mod theme;
 
 pub use config::AppConfig;
 pub use settings::Settings;
 pub use song::{Song, SongLoader, StringTab, Tab, TabLoader, VocalTab};
+pub use tab_cache::TabCache;
 pub use theme::{Theme, Themes};
@@ -0,0 +1,446 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::io::{
+    AssetReader, AssetReaderError, AssetSourceBuilder, AssetSourceId, PathStream, Reader, VecReader,
+};
+use bevy::prelude::*;
+use bevy::tasks::futures_lite::stream;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::file::library::{LibraryRoots, ASSET_FOLDER};
+
+/// A zip of one song folder, with the files at its root.
+pub const PACKAGE_EXTENSION: &str = "tabspack";
+
+// Already compressed or streamed, so stored as-is and read in place.
+const STORED_EXTENSIONS: [&str; 5] = ["wav", "ogg", "mp3", "flac", "png"];
+
+/// The entry is empty when `path` is the package itself.
+pub fn split_package_path(path: &Path) -> Option<(PathBuf, String)> {
+    let components: Vec<Component> = path.components().collect();
+    let index = components.iter().position(|component| {
+        Path::new(component.as_os_str())
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(PACKAGE_EXTENSION))
+    })?;
+    let package: PathBuf = components[..=index].iter().collect();
+    let entry = components[index + 1..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some((package, entry))
+}
+
+fn open_archive(package: &Path) -> io::Result<ZipArchive<File>> {
+    Ok(ZipArchive::new(File::open(package)?)?)
+}
+
+pub fn package_contains(package: &Path, entry: &str) -> bool {
+    open_archive(package).is_ok_and(|archive| archive.index_for_name(entry).is_some())
+}
+
+pub fn song_file_exists(path: &Path) -> bool {
+    match split_package_path(path) {
+        Some((package, entry)) => package_contains(&package, &entry),
+        None => path.is_file(),
+    }
+}
+
+pub fn open_song_file(path: &Path) -> io::Result<SongFile> {
+    let Some((package, entry)) = split_package_path(path) else {
+        return File::open(path).map(SongFile::Plain);
+    };
+    let mut archive = open_archive(&package)?;
+    let mut file = archive.by_name(&entry)?;
+    if file.compression() == CompressionMethod::Stored {
+        let start = file.data_start();
+        let len = file.size();
+        let mut package_file = File::open(&package)?;
+        package_file.seek(SeekFrom::Start(start))?;
+        return Ok(SongFile::Stored(StoredEntry {
+            file: package_file,
+            start,
+            len,
+            position: 0,
+        }));
+    }
+    let mut bytes = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut bytes)?;
+    Ok(SongFile::Inflated(Cursor::new(bytes)))
+}
+
+pub fn read_song_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    open_song_file(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn pack_song_folder(folder: &Path, output: &Path) -> io::Result<()> {
+    let mut files: Vec<PathBuf> = fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    if !files.iter().any(|path| path.ends_with("song.metadata")) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} has no song.metadata", folder.display()),
+        ));
+    }
+
+    let mut writer = ZipWriter::new(File::create(output)?);
+    for path in files {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let stored = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                STORED_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            });
+        let method = if stored {
+            CompressionMethod::Stored
+        } else {
+            CompressionMethod::Deflated
+        };
+        writer.start_file(
+            name,
+            SimpleFileOptions::default()
+                .compression_method(method)
+                .large_file(fs::metadata(&path)?.len() >= u32::MAX as u64),
+        )?;
+        writer.write_all(&fs::read(&path)?)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+pub enum SongFile {
+    Plain(File),
+    /// Read in place, so large audio is not loaded up front.
+    Stored(StoredEntry),
+    Inflated(Cursor<Vec<u8>>),
+}
+
+impl SongFile {
+    pub fn byte_len(&self) -> Option<u64> {
+        match self {
+            SongFile::Plain(file) => file.metadata().ok().map(|metadata| metadata.len()),
+            SongFile::Stored(entry) => Some(entry.len),
+            SongFile::Inflated(cursor) => Some(cursor.get_ref().len() as u64),
+        }
+    }
+}
+
+impl Read for SongFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SongFile::Plain(file) => file.read(buf),
+            SongFile::Stored(entry) => entry.read(buf),
+            SongFile::Inflated(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for SongFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            SongFile::Plain(file) => file.seek(pos),
+            SongFile::Stored(entry) => entry.seek(pos),
+            SongFile::Inflated(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+// Reads and seeks stay within the entry's byte range of the package file.
+pub struct StoredEntry {
+    file: File,
+    start: u64,
+    len: u64,
+    position: u64,
+}
+
+impl Read for StoredEntry {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.position);
+        let limit = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let read = self.file.read(&mut buf[..limit])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for StoredEntry {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start of entry"))?;
+        self.file.seek(SeekFrom::Start(self.start + target))?;
+        self.position = target;
+        Ok(target)
+    }
+}
+
+fn reader_error(path: &Path, err: io::Error) -> AssetReaderError {
+    if err.kind() == io::ErrorKind::NotFound {
+        AssetReaderError::NotFound(path.to_path_buf())
+    } else {
+        AssetReaderError::Io(Arc::new(err))
+    }
+}
+
+pub struct PackageAssetReader {
+    files: FileAssetReader,
+}
+
+impl PackageAssetReader {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        PackageAssetReader {
+            files: FileAssetReader::new(path),
+        }
+    }
+
+    fn entry_names(&self, package: &Path) -> Result<Vec<String>, AssetReaderError> {
+        let archive = open_archive(&self.files.root_path().join(package))
+            .map_err(|err| reader_error(package, err))?;
+        Ok(archive.file_names().map(str::to_string).collect())
+    }
+}
+
+impl AssetReader for PackageAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<Box<dyn Reader + 'a>, AssetReaderError> {
+        if split_package_path(path).is_none() {
+            return Ok(Box::new(self.files.read(path).await?));
+        }
+        let bytes = read_song_file(&self.files.root_path().join(path))
+            .map_err(|err| reader_error(path, err))?;
+        Ok(Box::new(VecReader::new(bytes)))
+    }
+
+    async fn read_meta<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<dyn Reader + 'a>, AssetReaderError> {
+        // Packages carry no `.meta` files, so their assets use the default settings.
+        if split_package_path(path).is_some() {
+            return Err(AssetReaderError::NotFound(path.to_path_buf()));
+        }
+        Ok(Box::new(self.files.read_meta(path).await?))
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        let Some((package, entry)) = split_package_path(path) else {
+            return self.files.read_directory(path).await;
+        };
+        let prefix = if entry.is_empty() {
+            entry
+        } else {
+            format!("{entry}/")
+        };
+        let mut children: Vec<PathBuf> = self
+            .entry_names(&package)?
+            .into_iter()
+            .filter_map(|name| {
+                let child = name.strip_prefix(&prefix)?.split('/').next()?;
+                (!child.is_empty()).then(|| path.join(child))
+            })
+            .collect();
+        children.sort();
+        children.dedup();
+        Ok(Box::new(stream::iter(children)))
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        let Some((package, entry)) = split_package_path(path) else {
+            return self.files.is_directory(path).await;
+        };
+        if entry.is_empty() {
+            return Ok(true);
+        }
+        let prefix = format!("{entry}/");
+        Ok(self
+            .entry_names(&package)?
+            .iter()
+            .any(|name| name.starts_with(&prefix)))
+    }
+}
+
+/// Add after `ConfigPlugin` and before `DefaultPlugins`, which would register the plain
+/// file source.
+pub struct SongPackagePlugin;
+
+impl Plugin for SongPackagePlugin {
+    fn build(&self, app: &mut App) {
+        let mut sources = vec![(AssetSourceId::Default, ASSET_FOLDER.to_string())];
+        if let Some(roots) = app.world().get_resource::<LibraryRoots>() {
+            sources.extend(
+                roots
+                    .iter()
+                    .filter(|root| root.source != AssetSourceId::Default)
+                    .map(|root| {
+                        let directory = root.directory.to_string_lossy().into_owned();
+                        (root.source.clone(), directory)
+                    }),
+            );
+        }
+        for (source, directory) in sources {
+            let builder = AssetSourceBuilder::platform_default(&directory, None)
+                .with_reader(move || Box::new(PackageAssetReader::new(&directory)));
+            app.register_asset_source(source, builder);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUDIO: [u8; 256] = {
+        let mut bytes = [0; 256];
+        let mut index = 0;
+        while index < bytes.len() {
+            bytes[index] = index as u8;
+            index += 1;
+        }
+        bytes
+    };
+    const METADATA: &str = "title: Riff\nartist: Someone\n";
+
+    // A packed song folder, removed again when dropped.
+    struct Packed {
+        directory: PathBuf,
+        package: PathBuf,
+    }
+
+    impl Packed {
+        fn new(name: &str) -> Self {
+            let directory =
+                std::env::temp_dir().join(format!("tabs-package-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&directory);
+            let folder = directory.join("song");
+            fs::create_dir_all(&folder).unwrap();
+            fs::write(folder.join("song.metadata"), METADATA).unwrap();
+            fs::write(folder.join("audio.ogg"), AUDIO).unwrap();
+            // Sorts after the audio, so its header follows the stored bytes in the package.
+            fs::write(folder.join("lead.tab"), "kind: strings\n".repeat(20)).unwrap();
+            let package = directory.join("song.tabspack");
+            pack_song_folder(&folder, &package).unwrap();
+            Packed { directory, package }
+        }
+
+        fn open(&self, entry: &str) -> io::Result<SongFile> {
+            open_song_file(&self.package.join(entry))
+        }
+    }
+
+    impl Drop for Packed {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    fn split(path: &str) -> Option<(PathBuf, String)> {
+        split_package_path(Path::new(path))
+    }
+
+    #[test]
+    fn package_paths_split_at_the_package() {
+        assert_eq!(
+            split("songs/Artist/riff.tabspack/lead.tab"),
+            Some((
+                PathBuf::from("songs/Artist/riff.tabspack"),
+                "lead.tab".to_string()
+            ))
+        );
+        assert_eq!(
+            split("riff.TabsPack/art/cover.png"),
+            Some((PathBuf::from("riff.TabsPack"), "art/cover.png".to_string()))
+        );
+        assert_eq!(
+            split("songs/riff.tabspack"),
+            Some((PathBuf::from("songs/riff.tabspack"), String::new()))
+        );
+        assert_eq!(split("songs/riff/lead.tab"), None);
+        assert_eq!(split("songs/tabspack/lead.tab"), None);
+    }
+
+    #[test]
+    fn packed_files_read_back_unchanged() {
+        let packed = Packed::new("round-trip");
+
+        let audio = packed.open("audio.ogg").unwrap();
+        assert!(matches!(audio, SongFile::Stored(_)));
+        assert_eq!(audio.byte_len(), Some(AUDIO.len() as u64));
+        assert_eq!(
+            read_song_file(&packed.package.join("audio.ogg")).unwrap(),
+            AUDIO
+        );
+
+        let metadata = packed.open("song.metadata").unwrap();
+        assert!(matches!(metadata, SongFile::Inflated(_)));
+        assert_eq!(
+            read_song_file(&packed.package.join("song.metadata")).unwrap(),
+            METADATA.as_bytes()
+        );
+
+        assert!(song_file_exists(&packed.package.join("lead.tab")));
+        assert!(!song_file_exists(&packed.package.join("bass.tab")));
+        assert!(packed.open("bass.tab").is_err());
+    }
+
+    #[test]
+    fn folders_without_metadata_are_not_packed() {
+        let packed = Packed::new("no-metadata");
+        let folder = packed.directory.join("song");
+        fs::remove_file(folder.join("song.metadata")).unwrap();
+        let err = pack_song_folder(&folder, &packed.directory.join("other.tabspack")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn stored_entries_stay_within_their_bytes() {
+        let packed = Packed::new("bounds");
+        let mut audio = packed.open("audio.ogg").unwrap();
+        let mut buf = [0; 4];
+
+        assert_eq!(audio.seek(SeekFrom::Start(3)).unwrap(), 3);
+        audio.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [3, 4, 5, 6]);
+
+        assert_eq!(audio.seek(SeekFrom::Current(-2)).unwrap(), 5);
+        audio.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [5, 6, 7, 8]);
+
+        // Reads stop at the end of the entry rather than running into the next one.
+        assert_eq!(audio.seek(SeekFrom::End(-2)).unwrap(), 254);
+        let mut rest = Vec::new();
+        audio.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [254, 255]);
+
+        assert_eq!(audio.seek(SeekFrom::End(0)).unwrap(), 256);
+        assert_eq!(audio.read(&mut buf).unwrap(), 0);
+        assert_eq!(audio.seek(SeekFrom::Start(300)).unwrap(), 300);
+        assert_eq!(audio.read(&mut buf).unwrap(), 0);
+
+        assert!(audio.seek(SeekFrom::Current(-301)).is_err());
+        assert!(audio.seek(SeekFrom::End(-257)).is_err());
+        assert_eq!(audio.seek(SeekFrom::Start(0)).unwrap(), 0);
+        audio.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,228 @@
+use serde_yaml::{Mapping, Value};
+
+use crate::file::song::{arrangement_file_stem, SongLoaderError, TabLoaderError};
+
+/// Version written to new `.tab` files.
+pub const TAB_FORMAT_VERSION: u32 = 1;
+/// Version written to new `song.metadata` files.
+pub const SONG_METADATA_VERSION: u32 = 2;
+
+pub const VERSION_KEY: &str = "version";
+pub const KIND_KEY: &str = "kind";
+pub const STRINGS_KIND: &str = "strings";
+pub const VOCALS_KIND: &str = "vocals";
+
+// Files written before versioning have no header and count as version 0.
+fn read_version(document: &Mapping) -> Result<u32, String> {
+    match document.get(VERSION_KEY) {
+        None => Ok(0),
+        Some(Value::Number(number)) => number
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| number.to_string()),
+        Some(other) => Err(serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string()),
+    }
+}
+
+/// The `version` and `kind` header that starts every `.tab` file.
+pub fn tab_header(kind: &str) -> Mapping {
+    let mut header = Mapping::new();
+    header.insert(VERSION_KEY.into(), TAB_FORMAT_VERSION.into());
+    header.insert(KIND_KEY.into(), kind.into());
+    header
+}
+
+/// Brings a `.tab` document up to [`TAB_FORMAT_VERSION`].
+pub fn migrate_tab(document: &mut Mapping) -> Result<(), TabLoaderError> {
+    let version = read_version(document).map_err(TabLoaderError::InvalidVersion)?;
+    if version > TAB_FORMAT_VERSION {
+        return Err(TabLoaderError::UnsupportedVersion {
+            found: version,
+            supported: TAB_FORMAT_VERSION,
+        });
+    }
+
+    // Version 0 had no kind; it was told from the top-level keys.
+    if version < 1 {
+        let kind = if document.contains_key(VOCALS_KIND) {
+            VOCALS_KIND
+        } else if document.contains_key("sections") {
+            STRINGS_KIND
+        } else {
+            return Err(TabLoaderError::UnknownLegacyKind);
+        };
+        document.insert(KIND_KEY.into(), kind.into());
+    }
+
+    document.insert(VERSION_KEY.into(), TAB_FORMAT_VERSION.into());
+    Ok(())
+}
+
+/// Brings a `song.metadata` document up to [`SONG_METADATA_VERSION`].
+pub fn migrate_song_metadata(document: &mut Mapping) -> Result<(), SongLoaderError> {
+    let version = read_version(document).map_err(SongLoaderError::InvalidVersion)?;
+    if version > SONG_METADATA_VERSION {
+        return Err(SongLoaderError::UnsupportedVersion {
+            found: version,
+            supported: SONG_METADATA_VERSION,
+        });
+    }
+
+    // Version 0 had no file per arrangement; it was the key, lowercased without spaces.
+    if version < 1 {
+        if let Some(Value::Mapping(arrangements)) = document.get_mut("arrangements") {
+            for (key, arrangement) in arrangements.iter_mut() {
+                let (Some(key), Value::Mapping(arrangement)) = (key.as_str(), arrangement) else {
+                    continue;
+                };
+                if !arrangement.contains_key("file") {
+                    let file = format!("{}.tab", arrangement_file_stem(key));
+                    arrangement.insert("file".into(), file.into());
+                }
+            }
+        }
+    }
+
+    // Version 1 and earlier always used these names for the song and preview audio.
+    if version < 2 {
+        if !document.contains_key("audio") {
+            document.insert("audio".into(), "song.wav".into());
+        }
+        if !document.contains_key("preview") {
+            document.insert("preview".into(), "preview.wav".into());
+        }
+    }
+
+    document.insert(VERSION_KEY.into(), SONG_METADATA_VERSION.into());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::song::{SongMetadata, Tab};
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn migrated_tab(yaml: &str) -> Result<Mapping, TabLoaderError> {
+        let mut document = mapping(yaml);
+        migrate_tab(&mut document).map(|_| document)
+    }
+
+    fn migrated_metadata(yaml: &str) -> Result<Mapping, SongLoaderError> {
+        let mut document = mapping(yaml);
+        migrate_song_metadata(&mut document).map(|_| document)
+    }
+
+    #[test]
+    fn legacy_tab_kind_comes_from_its_keys() {
+        let strings = migrated_tab("sections: []").unwrap();
+        assert_eq!(strings[KIND_KEY], STRINGS_KIND);
+        assert_eq!(strings[VERSION_KEY], TAB_FORMAT_VERSION);
+
+        let vocals = migrated_tab("vocals: []").unwrap();
+        assert_eq!(vocals[KIND_KEY], VOCALS_KIND);
+
+        assert!(matches!(
+            migrated_tab("chords: []"),
+            Err(TabLoaderError::UnknownLegacyKind)
+        ));
+    }
+
+    #[test]
+    fn legacy_tabs_still_load() {
+        let tab = Tab::from_yaml(b"sections: []\nchords: []\n").unwrap();
+        assert!(matches!(tab, Tab::Strings(_)));
+
+        let tab = Tab::from_yaml(b"vocals:\n  - { time: 1.0, length: 0.5, lyric: la }\n").unwrap();
+        let Tab::Vocals(vocals) = tab else {
+            panic!("expected vocals");
+        };
+        assert_eq!(vocals.vocals[0].lyric, "la");
+    }
+
+    #[test]
+    fn tab_versions_are_checked() {
+        assert!(matches!(
+            migrated_tab("version: 2\nkind: strings"),
+            Err(TabLoaderError::UnsupportedVersion {
+                found: 2,
+                supported: TAB_FORMAT_VERSION
+            })
+        ));
+        assert!(matches!(
+            migrated_tab("version: 1.5\nkind: strings"),
+            Err(TabLoaderError::InvalidVersion(version)) if version == "1.5"
+        ));
+        assert!(matches!(
+            migrated_tab("version: one\nkind: strings"),
+            Err(TabLoaderError::InvalidVersion(version)) if version == "one"
+        ));
+        assert!(matches!(
+            migrated_tab("version: -1\nkind: strings"),
+            Err(TabLoaderError::InvalidVersion(_))
+        ));
+    }
+
+    #[test]
+    fn legacy_metadata_gets_files_and_audio() {
+        let document = migrated_metadata(
+            "arrangements:\n  Lead Guitar: { name: Lead }\n  Bass: { name: Bass, file: low.tab }\n",
+        )
+        .unwrap();
+        assert_eq!(document[VERSION_KEY], SONG_METADATA_VERSION);
+        assert_eq!(document["audio"], "song.wav");
+        assert_eq!(document["preview"], "preview.wav");
+        let arrangements = &document["arrangements"];
+        assert_eq!(arrangements["Lead Guitar"]["file"], "leadguitar.tab");
+        assert_eq!(arrangements["Bass"]["file"], "low.tab");
+    }
+
+    #[test]
+    fn version_one_metadata_gets_audio() {
+        let document =
+            migrated_metadata("version: 1\narrangements:\n  Lead: { name: Lead, file: a.tab }\n")
+                .unwrap();
+        assert_eq!(document["audio"], "song.wav");
+        assert_eq!(document["preview"], "preview.wav");
+        assert_eq!(document["arrangements"]["Lead"]["file"], "a.tab");
+
+        let current =
+            migrated_metadata("version: 2\naudio: song.ogg\npreview: clip.mp3\n").unwrap();
+        assert_eq!(current["audio"], "song.ogg");
+        assert_eq!(current["preview"], "clip.mp3");
+    }
+
+    #[test]
+    fn metadata_versions_are_checked() {
+        assert!(matches!(
+            migrated_metadata("version: 3"),
+            Err(SongLoaderError::UnsupportedVersion {
+                found: 3,
+                supported: SONG_METADATA_VERSION
+            })
+        ));
+        assert!(matches!(
+            migrated_metadata("version: 2.0"),
+            Err(SongLoaderError::InvalidVersion(_))
+        ));
+    }
+
+    #[test]
+    fn legacy_metadata_fixture_loads() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/music_xml/lead/song.metadata"
+        );
+        let metadata = SongMetadata::from_yaml(&std::fs::read(path).unwrap()).unwrap();
+        assert_eq!(metadata.version, SONG_METADATA_VERSION);
+        assert_eq!(metadata.audio, "song.wav");
+        assert_eq!(metadata.preview, "preview.wav");
+        assert_eq!(metadata.arrangements["Lead"].file, "lead.tab");
+    }
+}
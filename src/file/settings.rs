@@ -2,6 +2,7 @@ use crate::file::config::AppConfig;
 use crate::states::StartupLatch;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -12,6 +13,14 @@ pub struct Settings {
     pub start_theme: String,
     #[serde(default)]
     pub window: WindowSettings,
+    #[serde(default)]
+    pub input: InputSettings,
+    #[serde(default)]
+    pub song_difficulty: HashMap<String, f32>,
+    #[serde(default)]
+    pub learn_levels: HashMap<String, Vec<usize>>,
+    #[serde(default = "default_show_lyric_ribbon")]
+    pub show_lyric_ribbon: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,11 +30,35 @@ pub struct WindowSettings {
     pub height: f32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    pub enabled: bool,
+    pub device: Option<String>,
+    pub wav_file: Option<PathBuf>,
+    pub latency_ms: f32,
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             start_theme: default_start_theme(),
             window: WindowSettings::default(),
+            input: InputSettings::default(),
+            song_difficulty: HashMap::new(),
+            learn_levels: HashMap::new(),
+            show_lyric_ribbon: default_show_lyric_ribbon(),
+        }
+    }
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device: None,
+            wav_file: None,
+            latency_ms: 0.0,
         }
     }
 }
@@ -43,6 +76,10 @@ fn default_start_theme() -> String {
     "default".to_string()
 }
 
+fn default_show_lyric_ribbon() -> bool {
+    true
+}
+
 pub fn load_or_create_settings(path: &PathBuf) -> Settings {
     if !path.exists() {
         warn!(
@@ -61,6 +98,22 @@ pub fn load_or_create_settings(path: &PathBuf) -> Settings {
     serde_yaml::from_str(&content).unwrap_or_else(|e| panic!("Failed to parse settings YAML: {e}"))
 }
 
+pub fn settings_path(config: &AppConfig) -> PathBuf {
+    PathBuf::from(&config.saves.directory).join(&config.saves.settings_file)
+}
+
+pub fn save_settings(settings: &Settings, config: &AppConfig) {
+    let path = settings_path(config);
+    match serde_yaml::to_string(settings) {
+        Ok(yaml) => {
+            if let Err(err) = fs::write(&path, yaml) {
+                error!("Failed to write settings file '{}': {err}", path.display());
+            }
+        }
+        Err(err) => error!("Failed to serialize settings: {err}"),
+    }
+}
+
 fn change_window(mut windows: Query<&mut Window>, settings: &Settings) {
     if let Ok(mut window) = windows.single_mut() {
         window
@@ -77,7 +130,7 @@ pub fn setup_settings(
     config: Res<AppConfig>,
     mut latch: ResMut<StartupLatch>,
 ) {
-    let path = PathBuf::from(&config.saves.directory).join(&config.saves.settings_file);
+    let path = settings_path(&config);
 
     if let Some(parent) = path.parent() {
         if !parent.exists() {
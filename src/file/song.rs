@@ -1,17 +1,22 @@
-use serde::{self, Deserialize, Deserializer};
+use serde::{self, Deserialize, Deserializer, Serialize};
 use serde_yaml::Value;
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use thiserror::Error;
 
 use bevy::{
-    asset::{io::Reader, Asset, AssetLoader, Handle, LoadContext, ReadAssetBytesError},
+    asset::{io::Reader, Asset, AssetLoader, Handle, LoadContext},
     prelude::*,
     reflect::TypePath,
 };
 use bevy_kira_audio::prelude::AudioSource as KiraAudioSource;
 
+use crate::file::library::{song_asset_path, LibraryRoot};
+use crate::file::package::{song_file_exists, PACKAGE_EXTENSION};
+use crate::file::schema;
+use crate::file::tab_cache::TabCache;
+
 #[derive(Asset, TypePath, Debug)]
 pub struct Song {
     pub metadata: SongMetadata,
@@ -25,7 +30,43 @@ pub enum Tab {
     Vocals(VocalTab),
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl Tab {
+    /// Parses a `.tab` document, migrating files written by older versions.
+    pub fn from_yaml(bytes: &[u8]) -> Result<Self, TabLoaderError> {
+        let Value::Mapping(mut document) = serde_yaml::from_slice(bytes)? else {
+            return Err(TabLoaderError::NotAMapping);
+        };
+        schema::migrate_tab(&mut document)?;
+        let kind = match document.get(schema::KIND_KEY) {
+            Some(Value::String(kind)) => kind.clone(),
+            Some(_) | None => return Err(TabLoaderError::MissingKind),
+        };
+        match kind.as_str() {
+            schema::STRINGS_KIND => Ok(Tab::Strings(serde_yaml::from_value(Value::Mapping(
+                document,
+            ))?)),
+            schema::VOCALS_KIND => Ok(Tab::Vocals(serde_yaml::from_value(Value::Mapping(
+                document,
+            ))?)),
+            _ => Err(TabLoaderError::UnknownKind(kind)),
+        }
+    }
+
+    /// Writes the tab with the current format header.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        let (kind, body) = match self {
+            Tab::Strings(tab) => (schema::STRINGS_KIND, serde_yaml::to_value(tab)?),
+            Tab::Vocals(tab) => (schema::VOCALS_KIND, serde_yaml::to_value(tab)?),
+        };
+        let mut document = schema::tab_header(kind);
+        if let Value::Mapping(body) = body {
+            document.extend(body);
+        }
+        serde_yaml::to_string(&document)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StringTab {
     pub sections: Vec<TabSection>,
     pub chords: Vec<TabChord>,
@@ -33,28 +74,28 @@ pub struct StringTab {
     pub note_charts: Vec<TabNoteChart>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TabSection {
     pub name: String,
     pub start_time: f32,
     pub end_time: f32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TabChord {
     pub name: String,
     pub fingers: Vec<i32>,
     pub frets: Vec<i32>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TabNoteChart {
     pub difficulty: i32,
     #[serde(default)]
     pub notes: Vec<TabNote>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TabNote {
     pub time: f32,
     #[serde(default)]
@@ -73,21 +114,23 @@ pub struct TabNote {
     pub tap: i32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VocalTab {
     pub vocals: Vec<VocalPhrase>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VocalPhrase {
     pub time: f32,
     pub length: f32,
     pub lyric: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SongArrangementMetadata {
     pub name: String,
+    /// Tab file of the arrangement, relative to the song folder.
+    pub file: String,
     pub capo_fret: Option<i32>,
     pub instrument: TabsInstrument,
     pub string_count: Option<i32>,
@@ -95,17 +138,22 @@ pub struct SongArrangementMetadata {
     pub techniques: Vec<Techniques>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SongMetadata {
+    pub version: u32,
     pub title: String,
     pub artist: String,
     pub album: String,
     pub year: i32,
     pub length: f32,
+    /// Audio streamed during gameplay, relative to the song folder.
+    pub audio: String,
+    /// Audio played in the song preview, relative to the song folder.
+    pub preview: String,
     pub arrangements: HashMap<String, SongArrangementMetadata>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Techniques {
     Slide,
     Bend,
@@ -124,17 +172,52 @@ pub enum Techniques {
     Arpeggio,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TabsInstrument {
     Guitar,
     Bass,
     Vocals,
 }
 
+impl SongMetadata {
+    /// Parses a `song.metadata` document, migrating files written by older versions.
+    pub fn from_yaml(bytes: &[u8]) -> Result<Self, SongLoaderError> {
+        let Value::Mapping(mut document) = serde_yaml::from_slice(bytes)? else {
+            return Err(SongLoaderError::NotAMapping);
+        };
+        schema::migrate_song_metadata(&mut document)?;
+        Ok(serde_yaml::from_value(Value::Mapping(document))?)
+    }
+
+    // The vocal arrangement to show alongside the arrangement under `key`, if the song has one.
+    pub fn lyrics_arrangement_for(&self, key: &str) -> Option<&SongArrangementMetadata> {
+        let selected = self.arrangements.get(key)?;
+        if selected.instrument == TabsInstrument::Vocals {
+            return None;
+        }
+        self.arrangements
+            .iter()
+            .filter(|(_, arrangement)| arrangement.instrument == TabsInstrument::Vocals)
+            .min_by(|a, b| a.0.cmp(b.0))
+            .map(|(_, arrangement)| arrangement)
+    }
+}
+
+/// The file name an arrangement key mapped to before arrangements named their file.
+pub fn arrangement_file_stem(arrangement_key: &str) -> String {
+    arrangement_key
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
 impl Song {
-    pub fn get_all_songs(root_folder: &Path) -> Vec<PathBuf> {
+    /// Paths of the `song.metadata` of every song under `root`, relative to its asset
+    /// source.
+    pub fn get_all_songs(root: &LibraryRoot) -> Vec<PathBuf> {
         let mut songs = Vec::new();
-        let full_root = Path::new("assets").join(root_folder);
+        let full_root = root.song_folder();
         if !full_root.exists() {
             warn!("Root folder does not exist: {}", full_root.display());
             return songs;
@@ -148,14 +231,16 @@ impl Song {
         if let Ok(entries) = fs::read_dir(full_root) {
             for entry in entries.filter_map(Result::ok) {
                 let path = entry.path();
-                if path.is_dir() {
-                    let metadata_path = path.join("song.metadata");
-                    if metadata_path.exists() {
-                        let relative_path = metadata_path
-                            .strip_prefix("assets")
-                            .unwrap_or(&metadata_path);
-                        songs.push(relative_path.to_path_buf());
-                    }
+                let metadata_path = path.join("song.metadata");
+                let is_package = path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|extension| extension == PACKAGE_EXTENSION);
+                if (path.is_dir() || is_package) && song_file_exists(&metadata_path) {
+                    let relative_path = metadata_path
+                        .strip_prefix(&root.directory)
+                        .unwrap_or(&metadata_path);
+                    songs.push(relative_path.to_path_buf());
                 }
             }
         }
@@ -183,11 +268,14 @@ pub enum SongLoaderError {
     #[error("Failed to parse song.metadata: {0}")]
     Yaml(#[from] serde_yaml::Error),
 
-    #[error("Missing parent directory for asset path")]
-    MissingParentDirectory,
+    #[error("song.metadata is not a YAML mapping")]
+    NotAMapping,
+
+    #[error("song.metadata has format version {found}, but only up to {supported} is supported")]
+    UnsupportedVersion { found: u32, supported: u32 },
 
-    #[error("Failed to read asset bytes: {0}")]
-    ReadBytes(#[from] ReadAssetBytesError),
+    #[error("song.metadata has an invalid format version: {0}")]
+    InvalidVersion(String),
 }
 
 impl AssetLoader for SongLoader {
@@ -200,23 +288,23 @@ impl AssetLoader for SongLoader {
     }
     async fn load(
         &self,
-        _reader: &mut dyn Reader,
+        reader: &mut dyn Reader,
         _settings: &(),
         load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
-        let metadata_path: PathBuf = load_context.path().to_path_buf();
-        let metadata_bytes = load_context.read_asset_bytes(metadata_path).await?;
-        let metadata: SongMetadata = serde_yaml::from_slice(&metadata_bytes)?;
-        let folder = load_context
-            .path()
-            .parent()
-            .ok_or(SongLoaderError::MissingParentDirectory)?
-            .to_path_buf();
+        // Read through `reader` rather than `read_asset_bytes`, which would make the file a
+        // dependency of itself and reload it endlessly once it changes.
+        let mut metadata_bytes = Vec::new();
+        reader.read_to_end(&mut metadata_bytes).await?;
+        let metadata = SongMetadata::from_yaml(&metadata_bytes)?;
+        // Siblings keep the metadata's asset source, so songs in other library roots
+        // load their files from there too.
+        let metadata_path = load_context.asset_path().clone_owned();
 
         let album_art: Handle<Image> = load_context
             .loader()
-            .load::<Image>(folder.join("album_art.png"));
-        let audio_preview_path = folder.join("preview.wav");
+            .load::<Image>(song_asset_path(&metadata_path, "album_art.png"));
+        let audio_preview_path = song_asset_path(&metadata_path, &metadata.preview);
         let audio_preview: Handle<KiraAudioSource> = load_context
             .loader()
             .load::<KiraAudioSource>(audio_preview_path);
@@ -229,19 +317,44 @@ impl AssetLoader for SongLoader {
     }
 }
 
+/// Loads `.tab` files, going through a [`TabCache`] when one is set so large charts skip
+/// YAML parsing after their first load.
 #[derive(Default)]
-pub struct TabLoader;
+pub struct TabLoader {
+    cache: Option<TabCache>,
+}
+
+impl TabLoader {
+    pub fn with_cache(cache: TabCache) -> Self {
+        TabLoader { cache: Some(cache) }
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum TabLoaderError {
-    #[error("Failed to read asset bytes: {0}")]
-    ReadBytes(#[from] ReadAssetBytesError),
+    #[error("I/O error while loading asset: {0}")]
+    Io(#[from] std::io::Error),
 
     #[error("Failed to parse tab file: {0}")]
     Yaml(#[from] serde_yaml::Error),
 
-    #[error("Unsupported tab format in {0}")]
-    UnsupportedFormat(String),
+    #[error("Tab file is not a YAML mapping")]
+    NotAMapping,
+
+    #[error("Tab file has format version {found}, but only up to {supported} is supported")]
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    #[error("Tab file has an invalid format version: {0}")]
+    InvalidVersion(String),
+
+    #[error("Tab file has no kind")]
+    MissingKind,
+
+    #[error("Unknown tab kind '{0}'")]
+    UnknownKind(String),
+
+    #[error("Unversioned tab file has neither 'sections' nor 'vocals'")]
+    UnknownLegacyKind,
 }
 
 impl AssetLoader for TabLoader {
@@ -255,30 +368,17 @@ impl AssetLoader for TabLoader {
 
     async fn load(
         &self,
-        _reader: &mut dyn Reader,
+        reader: &mut dyn Reader,
         _settings: &(),
         load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
-        let tab_path: PathBuf = load_context.path().to_path_buf();
-        let path_string = tab_path.to_string_lossy().to_string();
-        let tab_bytes = load_context.read_asset_bytes(tab_path).await?;
-        let document: Value = serde_yaml::from_slice(&tab_bytes)?;
-
-        let vocals_key = Value::String("vocals".to_string());
-        let sections_key = Value::String("sections".to_string());
-
-        let tab_asset = match &document {
-            Value::Mapping(map) if map.contains_key(&vocals_key) => {
-                let vocal_tab: VocalTab = serde_yaml::from_slice(&tab_bytes)?;
-                Tab::Vocals(vocal_tab)
-            }
-            Value::Mapping(map) if map.contains_key(&sections_key) => {
-                let string_tab: StringTab = serde_yaml::from_slice(&tab_bytes)?;
-                Tab::Strings(string_tab)
-            }
-            _ => return Err(TabLoaderError::UnsupportedFormat(path_string)),
-        };
+        let cache_key = load_context.asset_path().to_string();
+        let mut tab_bytes = Vec::new();
+        reader.read_to_end(&mut tab_bytes).await?;
 
-        Ok(tab_asset)
+        match &self.cache {
+            Some(cache) => cache.load_or_parse(&cache_key, &tab_bytes, Tab::from_yaml),
+            None => Tab::from_yaml(&tab_bytes),
+        }
     }
 }
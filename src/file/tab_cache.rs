@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::file::song::{StringTab, Tab, VocalTab};
+
+pub const TAB_CACHE_DIRECTORY: &str = "tab_cache";
+// Bumped whenever the tab structs change shape, so old caches are rebuilt.
+pub const TAB_CACHE_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 4] = b"TABC";
+const HASH_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + 4 + HASH_LEN;
+
+#[derive(Debug, Error)]
+pub enum TabCacheError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Corrupt tab cache: {0}")]
+    Encoding(#[from] bincode::Error),
+}
+
+// Same layout on both sides; the write side borrows so a loaded tab is not cloned.
+#[derive(Serialize)]
+enum CachedTabRef<'a> {
+    Strings(&'a StringTab),
+    Vocals(&'a VocalTab),
+}
+
+#[derive(Deserialize)]
+enum CachedTab {
+    Strings(StringTab),
+    Vocals(VocalTab),
+}
+
+// Layout: magic, format version (u32 LE), BLAKE3 hash of the `.tab` source bytes, then the
+// tab in bincode.
+pub fn encode_tab(tab: &Tab, source: &[u8]) -> Result<Vec<u8>, TabCacheError> {
+    let cached = match tab {
+        Tab::Strings(tab) => CachedTabRef::Strings(tab),
+        Tab::Vocals(tab) => CachedTabRef::Vocals(tab),
+    };
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&TAB_CACHE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(blake3::hash(source).as_bytes());
+    bincode::serialize_into(&mut bytes, &cached)?;
+    Ok(bytes)
+}
+
+// `None` means the cache is from another version or for different source bytes.
+pub fn decode_tab(bytes: &[u8], source: &[u8]) -> Result<Option<Tab>, TabCacheError> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Ok(None);
+    }
+    let (version, rest) = bytes[MAGIC.len()..].split_at(4);
+    if version != TAB_CACHE_VERSION.to_le_bytes() {
+        return Ok(None);
+    }
+    let (hash, payload) = rest.split_at(HASH_LEN);
+    if hash != blake3::hash(source).as_bytes() {
+        return Ok(None);
+    }
+    Ok(Some(match bincode::deserialize(payload)? {
+        CachedTab::Strings(tab) => Tab::Strings(tab),
+        CachedTab::Vocals(tab) => Tab::Vocals(tab),
+    }))
+}
+
+#[derive(Debug, Clone)]
+pub struct TabCache {
+    directory: PathBuf,
+}
+
+impl TabCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        TabCache {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn path_for(&self, asset_path: &str) -> PathBuf {
+        let key = blake3::hash(asset_path.as_bytes()).to_hex();
+        self.directory.join(format!("{key}.tabc"))
+    }
+
+    pub fn load(&self, asset_path: &str, source: &[u8]) -> Result<Option<Tab>, TabCacheError> {
+        match fs::read(self.path_for(asset_path)) {
+            Ok(bytes) => decode_tab(&bytes, source),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // A cache that can't be read is ignored and rebuilt from the parsed source.
+    pub fn load_or_parse<E>(
+        &self,
+        asset_path: &str,
+        source: &[u8],
+        parse: impl FnOnce(&[u8]) -> Result<Tab, E>,
+    ) -> Result<Tab, E> {
+        match self.load(asset_path, source) {
+            Ok(Some(tab)) => return Ok(tab),
+            Ok(None) => {}
+            Err(err) => warn!("Ignoring tab cache for {asset_path}: {err}"),
+        }
+        let tab = parse(source)?;
+        if let Err(err) = self.store(asset_path, source, &tab) {
+            warn!("Failed to write tab cache for {asset_path}: {err}");
+        }
+        Ok(tab)
+    }
+
+    pub fn store(&self, asset_path: &str, source: &[u8], tab: &Tab) -> Result<(), TabCacheError> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.path_for(asset_path);
+        // Written aside and renamed so a concurrent load never sees half a file.
+        let partial = path.with_extension("tabc.partial");
+        fs::write(&partial, encode_tab(tab, source)?)?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEAD: &[u8] = b"version: 1
+kind: strings
+sections:
+  - { name: Intro, start_time: 0.0, end_time: 2.5 }
+chords: []
+note_charts:
+  - difficulty: 0
+    notes:
+      - { time: 0.5, techniques: [Bend], string: 2, fret: 7, chord_index: -1, anchor_fret: -1, sustain: 0.25, slide_to: -1, slide_unpitch_to: -1, vibrato: 0, max_bend: 1.0, slap: -1, pluck: -1, tap: 0 }
+";
+
+    fn lead() -> Tab {
+        Tab::from_yaml(LEAD).unwrap()
+    }
+
+    fn temp_cache(name: &str) -> TabCache {
+        let directory =
+            std::env::temp_dir().join(format!("tabs-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        TabCache::new(directory)
+    }
+
+    #[test]
+    fn encoded_tabs_decode_unchanged() {
+        let tab = lead();
+        let bytes = encode_tab(&tab, LEAD).unwrap();
+        let decoded = decode_tab(&bytes, LEAD).unwrap().unwrap();
+        assert_eq!(format!("{decoded:?}"), format!("{tab:?}"));
+    }
+
+    #[test]
+    fn caches_of_other_source_bytes_are_rejected() {
+        let bytes = encode_tab(&lead(), LEAD).unwrap();
+        let mut edited = LEAD.to_vec();
+        edited.extend_from_slice(b"# edited\n");
+        assert!(decode_tab(&bytes, &edited).unwrap().is_none());
+    }
+
+    #[test]
+    fn caches_with_another_header_are_rejected() {
+        let bytes = encode_tab(&lead(), LEAD).unwrap();
+
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert!(decode_tab(&magic, LEAD).unwrap().is_none());
+
+        let mut version = bytes.clone();
+        version[MAGIC.len()..MAGIC.len() + 4]
+            .copy_from_slice(&(TAB_CACHE_VERSION + 1).to_le_bytes());
+        assert!(decode_tab(&version, LEAD).unwrap().is_none());
+
+        assert!(decode_tab(&bytes[..HEADER_LEN - 1], LEAD)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn broken_caches_fall_back_to_the_source() {
+        let cache = temp_cache("broken");
+        let tab = lead();
+        cache.store("song/lead.tab", LEAD, &tab).unwrap();
+        let path = cache.path_for("song/lead.tab");
+        let bytes = fs::read(&path).unwrap();
+
+        // Truncated payload, then garbage after a valid header.
+        for broken in [
+            bytes[..bytes.len() - 4].to_vec(),
+            [&bytes[..HEADER_LEN], &[0xff; 16][..]].concat(),
+        ] {
+            fs::write(&path, broken).unwrap();
+            assert!(cache.load("song/lead.tab", LEAD).is_err());
+            let loaded = cache
+                .load_or_parse("song/lead.tab", LEAD, Tab::from_yaml)
+                .unwrap();
+            assert_eq!(format!("{loaded:?}"), format!("{tab:?}"));
+            // The parsed tab replaced the broken cache.
+            assert!(cache.load("song/lead.tab", LEAD).unwrap().is_some());
+        }
+        let _ = fs::remove_dir_all(&cache.directory);
+    }
+}
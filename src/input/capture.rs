@@ -0,0 +1,436 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use thiserror::Error;
+
+use crate::input::pitch::{PitchAnalyzer, PitchFrame};
+
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(50);
+const WAV_CHUNK_SECONDS: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    Device(Option<String>),
+    WavFile(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub captured_at: Instant,
+    pub frame: PitchFrame,
+}
+
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("no input device available{}", .0.as_ref().map(|name| format!(" named '{name}'")).unwrap_or_default())]
+    NoDevice(Option<String>),
+
+    #[error("failed to enumerate input devices: {0}")]
+    Devices(#[from] cpal::DevicesError),
+
+    #[error("failed to query input device configuration: {0}")]
+    DeviceConfig(#[from] cpal::DefaultStreamConfigError),
+
+    #[error("failed to build input stream: {0}")]
+    BuildStream(#[from] cpal::BuildStreamError),
+
+    #[error("failed to start input stream: {0}")]
+    PlayStream(#[from] cpal::PlayStreamError),
+
+    #[error("unsupported input sample format {0:?}")]
+    UnsupportedSampleFormat(SampleFormat),
+
+    #[error("failed to open WAV input {path}: {source}")]
+    WavOpen {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to decode WAV input {path}: {source}")]
+    WavDecode {
+        path: PathBuf,
+        #[source]
+        source: symphonia::core::errors::Error,
+    },
+
+    #[error("WAV input {0} has no audio track")]
+    WavNoTrack(PathBuf),
+
+    #[error("failed to spawn input capture thread: {0}")]
+    Spawn(#[from] std::io::Error),
+
+    #[error("input capture thread stopped before it was ready")]
+    WorkerStopped,
+}
+
+#[derive(Resource)]
+pub struct InputCapture {
+    frames: Mutex<Receiver<CapturedFrame>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    source: InputSource,
+    latency: f32,
+}
+
+impl InputCapture {
+    pub fn start(source: InputSource) -> Result<Self, CaptureError> {
+        let (frame_sender, frame_receiver) = mpsc::channel();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let worker_source = source.clone();
+        let worker_stop = stop.clone();
+        let worker = thread::Builder::new()
+            .name("tabs-input-capture".to_string())
+            .spawn(move || match worker_source {
+                InputSource::Device(name) => {
+                    run_device_capture(name, frame_sender, ready_sender, worker_stop)
+                }
+                InputSource::WavFile(path) => {
+                    run_wav_capture(&path, frame_sender, ready_sender, worker_stop)
+                }
+            })?;
+
+        match ready_receiver.recv() {
+            Ok(Ok(())) => Ok(Self {
+                frames: Mutex::new(frame_receiver),
+                stop,
+                worker: Some(worker),
+                source,
+                latency: 0.0,
+            }),
+            Ok(Err(err)) => {
+                let _ = worker.join();
+                Err(err)
+            }
+            Err(_) => {
+                let _ = worker.join();
+                Err(CaptureError::WorkerStopped)
+            }
+        }
+    }
+
+    pub fn source(&self) -> &InputSource {
+        &self.source
+    }
+
+    pub fn latency(&self) -> f32 {
+        self.latency
+    }
+
+    pub fn set_latency(&mut self, seconds: f32) {
+        self.latency = seconds.max(0.0);
+    }
+
+    pub fn drain_frames(&self) -> Vec<CapturedFrame> {
+        match self.frames.lock() {
+            Ok(receiver) => receiver.try_iter().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl Drop for InputCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+pub fn list_input_devices() -> Result<Vec<String>, CaptureError> {
+    let host = cpal::default_host();
+    Ok(host
+        .input_devices()?
+        .filter_map(|device| device.name().ok())
+        .collect())
+}
+
+fn run_device_capture(
+    device_name: Option<String>,
+    frames: Sender<CapturedFrame>,
+    ready: Sender<Result<(), CaptureError>>,
+    stop: Arc<AtomicBool>,
+) {
+    let (sample_sender, sample_receiver) = mpsc::channel::<(Instant, Vec<f32>)>();
+    let (stream, sample_rate) = match open_device_stream(device_name, sample_sender) {
+        Ok(opened) => opened,
+        Err(err) => {
+            let _ = ready.send(Err(err));
+            return;
+        }
+    };
+    if let Err(err) = stream.play() {
+        let _ = ready.send(Err(err.into()));
+        return;
+    }
+    let _ = ready.send(Ok(()));
+
+    let mut analyzer = PitchAnalyzer::new(sample_rate);
+    let mut pending = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        match sample_receiver.recv_timeout(RECEIVE_TIMEOUT) {
+            Ok((arrived_at, samples)) => {
+                analyzer.push(&samples, &mut pending);
+                if !forward_frames(&mut pending, &analyzer, arrived_at, &frames) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    drop(stream);
+}
+
+fn open_device_stream(
+    device_name: Option<String>,
+    samples: Sender<(Instant, Vec<f32>)>,
+) -> Result<(cpal::Stream, u32), CaptureError> {
+    let host = cpal::default_host();
+    let device = match &device_name {
+        Some(name) => host
+            .input_devices()?
+            .find(|device| device.name().map(|n| &n == name).unwrap_or(false)),
+        None => host.default_input_device(),
+    }
+    .ok_or_else(|| CaptureError::NoDevice(device_name.clone()))?;
+
+    let supported = device.default_input_config()?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let channels = config.channels.max(1) as usize;
+    let sample_rate = config.sample_rate.0;
+
+    info!(
+        "Opening input device '{}' ({} Hz, {} channel(s), {:?})",
+        device.name().unwrap_or_default(),
+        sample_rate,
+        channels,
+        sample_format
+    );
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, channels, samples)?,
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, channels, samples)?,
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, channels, samples)?,
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, channels, samples)?,
+        other => return Err(CaptureError::UnsupportedSampleFormat(other)),
+    };
+
+    Ok((stream, sample_rate))
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    channels: usize,
+    samples: Sender<(Instant, Vec<f32>)>,
+) -> Result<cpal::Stream, CaptureError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let mono: Vec<f32> = data
+                .chunks(channels)
+                .map(|frame| {
+                    frame
+                        .iter()
+                        .map(|sample| sample.to_sample::<f32>())
+                        .sum::<f32>()
+                        / channels as f32
+                })
+                .collect();
+            let _ = samples.send((Instant::now(), mono));
+        },
+        |err| error!("Input stream error: {err}"),
+        None,
+    )?;
+    Ok(stream)
+}
+
+fn run_wav_capture(
+    path: &Path,
+    frames: Sender<CapturedFrame>,
+    ready: Sender<Result<(), CaptureError>>,
+    stop: Arc<AtomicBool>,
+) {
+    // Only the headers are probed before signalling, packets are decoded as they are played.
+    let mut reader = match WavReader::open(path) {
+        Ok(reader) => reader,
+        Err(err) => {
+            let _ = ready.send(Err(err));
+            return;
+        }
+    };
+    let _ = ready.send(Ok(()));
+    let sample_rate = reader.sample_rate;
+    info!(
+        "Using WAV file {} as input device ({} Hz)",
+        path.display(),
+        sample_rate
+    );
+
+    let chunk_len = ((sample_rate as f64 * WAV_CHUNK_SECONDS) as usize).max(1);
+    let mut analyzer = PitchAnalyzer::new(sample_rate);
+    let mut pending = Vec::new();
+    let mut samples = Vec::new();
+    let mut played = 0;
+    let mut finished = false;
+    let started = Instant::now();
+
+    while !finished || !samples.is_empty() {
+        while !finished && samples.len() < chunk_len {
+            match reader.next_samples(&mut samples) {
+                Ok(more) => finished = !more,
+                Err(err) => {
+                    warn!("Stopped reading WAV input: {err}");
+                    finished = true;
+                }
+            }
+        }
+        let chunk: Vec<f32> = samples.drain(..chunk_len.min(samples.len())).collect();
+        if chunk.is_empty() || stop.load(Ordering::Relaxed) {
+            return;
+        }
+        played += chunk.len();
+        let due = Duration::from_secs_f64(played as f64 / sample_rate as f64);
+        if let Some(wait) = due.checked_sub(started.elapsed()) {
+            thread::sleep(wait);
+        }
+        analyzer.push(&chunk, &mut pending);
+        if !forward_frames(&mut pending, &analyzer, Instant::now(), &frames) {
+            return;
+        }
+    }
+}
+
+fn forward_frames(
+    pending: &mut Vec<PitchFrame>,
+    analyzer: &PitchAnalyzer,
+    arrived_at: Instant,
+    frames: &Sender<CapturedFrame>,
+) -> bool {
+    let received = analyzer.received_seconds();
+    for frame in pending.drain(..) {
+        let age = Duration::from_secs_f64((received - frame.time).max(0.0));
+        let captured_at = arrived_at.checked_sub(age).unwrap_or(arrived_at);
+        if frames.send(CapturedFrame { captured_at, frame }).is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn decode_wav_mono(path: &Path) -> Result<(Vec<f32>, u32), CaptureError> {
+    let mut reader = WavReader::open(path)?;
+    let mut mono = Vec::new();
+    while reader.next_samples(&mut mono)? {}
+    Ok((mono, reader.sample_rate))
+}
+
+struct WavReader {
+    path: PathBuf,
+    format: Box<dyn symphonia::core::formats::FormatReader>,
+    decoder: Box<dyn symphonia::core::codecs::Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+}
+
+impl WavReader {
+    fn open(path: &Path) -> Result<Self, CaptureError> {
+        use symphonia::core::codecs::DecoderOptions;
+        use symphonia::core::formats::FormatOptions;
+        use symphonia::core::io::MediaSourceStream;
+        use symphonia::core::meta::MetadataOptions;
+        use symphonia::core::probe::Hint;
+
+        let file = std::fs::File::open(path).map_err(|source| CaptureError::WavOpen {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let decode_error = |source| CaptureError::WavDecode {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("wav");
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(decode_error)?;
+        let format = probed.format;
+        let track = format
+            .default_track()
+            .ok_or_else(|| CaptureError::WavNoTrack(path.to_path_buf()))?;
+        let track_id = track.id;
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or_else(|| CaptureError::WavNoTrack(path.to_path_buf()))?;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(decode_error)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            decoder,
+            track_id,
+            sample_rate,
+        })
+    }
+
+    // Appends the next packet as mono samples, returns false at the end of the file.
+    fn next_samples(&mut self, mono: &mut Vec<f32>) -> Result<bool, CaptureError> {
+        use symphonia::core::audio::SampleBuffer;
+        use symphonia::core::errors::Error as SymphoniaError;
+
+        let decode_error = |source| CaptureError::WavDecode {
+            path: self.path.clone(),
+            source,
+        };
+        let packet = loop {
+            match self.format.next_packet() {
+                Ok(packet) if packet.track_id() == self.track_id => break packet,
+                Ok(_) => continue,
+                Err(SymphoniaError::IoError(err))
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(false);
+                }
+                Err(err) => return Err(decode_error(err)),
+            }
+        };
+        let decoded = self.decoder.decode(&packet).map_err(decode_error)?;
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        mono.extend(
+            buffer
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+        Ok(true)
+    }
+}
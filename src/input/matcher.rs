@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::components::TimelineNote;
+use crate::file::song::{SongArrangementMetadata, TabsInstrument};
+use crate::input::pitch::{DetectedPitch, PitchFrame};
+use crate::input::{NoteHitEvent, NoteMissEvent};
+
+pub const HIT_WINDOW_SECONDS: f32 = 0.15;
+const PITCH_TOLERANCE_SEMITONES: f32 = 0.5;
+const MIN_CONFIDENCE: f32 = 0.5;
+const ONSET_RMS_RATIO: f32 = 1.3;
+
+const GUITAR_OPEN_STRINGS: [i32; 8] = [30, 35, 40, 45, 50, 55, 59, 64];
+const BASS_OPEN_STRINGS: [i32; 6] = [23, 28, 33, 38, 43, 48];
+
+/// MIDI pitches of the open strings in standard tuning, lowest string first.
+pub fn standard_open_strings(instrument: TabsInstrument, string_count: usize) -> Vec<i32> {
+    let (standard, default_count, standard_low): (&[i32], usize, usize) = match instrument {
+        TabsInstrument::Guitar => (&GUITAR_OPEN_STRINGS, 6, 2),
+        TabsInstrument::Bass => (&BASS_OPEN_STRINGS, 4, 1),
+        TabsInstrument::Vocals => return Vec::new(),
+    };
+    // Extended-range instruments add strings below the standard low string.
+    let lowest = standard_low.saturating_sub(string_count.saturating_sub(default_count));
+    (0..string_count)
+        .map(|string| {
+            standard.get(lowest + string).copied().unwrap_or_else(|| {
+                let extra = (lowest + string + 1 - standard.len()) as i32;
+                standard[standard.len() - 1] + 5 * extra
+            })
+        })
+        .collect()
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct InstrumentTuning {
+    pub open_strings: Vec<i32>,
+    pub capo_fret: i32,
+}
+
+impl InstrumentTuning {
+    pub fn from_arrangement(arrangement: &SongArrangementMetadata) -> Self {
+        let default_count = match arrangement.instrument {
+            TabsInstrument::Guitar => 6,
+            TabsInstrument::Bass => 4,
+            TabsInstrument::Vocals => return Self::default(),
+        };
+        let string_count = arrangement
+            .string_count
+            .map(|count| count.max(0) as usize)
+            .unwrap_or(default_count);
+
+        let offsets = arrangement.string_semitone_offset.as_deref().unwrap_or(&[]);
+        let open_strings = standard_open_strings(arrangement.instrument, string_count)
+            .into_iter()
+            .enumerate()
+            .map(|(string, base)| base + offsets.get(string).copied().unwrap_or(0))
+            .collect();
+
+        Self {
+            open_strings,
+            capo_fret: arrangement.capo_fret.unwrap_or(0).max(0),
+        }
+    }
+
+    // Frets are absolute; an open string with a capo sounds at the capo fret.
+    pub fn note_midi(&self, string_index: usize, fret: i32) -> Option<i32> {
+        let open = *self.open_strings.get(string_index)?;
+        Some(open + fret.max(self.capo_fret))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct JudgedKey {
+    time_bits: u32,
+    string_index: usize,
+    fret: i32,
+}
+
+impl JudgedKey {
+    fn new(note: &TimelineNote) -> Self {
+        Self {
+            time_bits: note.time.to_bits(),
+            string_index: note.string_index,
+            fret: note.fret,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct NoteMatcher {
+    judged: HashMap<JudgedKey, f32>,
+    previous_pitches: Vec<i32>,
+    previous_rms: f32,
+}
+
+impl NoteMatcher {
+    pub fn reset(&mut self) {
+        self.judged.clear();
+        self.previous_pitches.clear();
+        self.previous_rms = 0.0;
+    }
+
+    pub fn observe(
+        &mut self,
+        song_time: f32,
+        frame: &PitchFrame,
+        notes: &[TimelineNote],
+        tuning: &InstrumentTuning,
+    ) -> Vec<NoteHitEvent> {
+        let detected: Vec<DetectedPitch> = frame
+            .monophonic
+            .iter()
+            .chain(frame.polyphonic.iter())
+            .filter(|pitch| pitch.confidence >= MIN_CONFIDENCE)
+            .copied()
+            .collect();
+        let rounded: Vec<i32> = detected
+            .iter()
+            .map(|pitch| pitch.midi().round() as i32)
+            .collect();
+        let reattacked = frame.rms > self.previous_rms * ONSET_RMS_RATIO;
+        let onsets: Vec<DetectedPitch> = detected
+            .iter()
+            .zip(rounded.iter())
+            .filter(|(_, midi)| reattacked || !self.previous_pitches.contains(midi))
+            .map(|(pitch, _)| *pitch)
+            .collect();
+        self.previous_pitches = rounded;
+        self.previous_rms = frame.rms;
+
+        if onsets.is_empty() {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<&TimelineNote> = notes
+            .iter()
+            .filter(|note| (note.time - song_time).abs() <= HIT_WINDOW_SECONDS)
+            .filter(|note| !self.judged.contains_key(&JudgedKey::new(note)))
+            .collect();
+        candidates.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut hits = Vec::new();
+        for note in candidates {
+            let Some(expected) = tuning.note_midi(note.string_index, note.fret) else {
+                continue;
+            };
+            let bend = note.max_bend.max(0.0);
+            let matched = onsets.iter().find(|pitch| {
+                let delta = pitch.midi() - expected as f32;
+                delta >= -PITCH_TOLERANCE_SEMITONES && delta <= bend + PITCH_TOLERANCE_SEMITONES
+            });
+            let Some(pitch) = matched else {
+                continue;
+            };
+            if hits
+                .iter()
+                .any(|hit: &NoteHitEvent| hit.note.string_index == note.string_index)
+            {
+                continue;
+            }
+
+            self.judged.insert(JudgedKey::new(note), note.time);
+            hits.push(NoteHitEvent {
+                note: note.clone(),
+                played_time: song_time,
+                detected_frequency: pitch.frequency,
+            });
+        }
+
+        hits
+    }
+
+    pub fn expire(&mut self, current_time: f32, notes: &[TimelineNote]) -> Vec<NoteMissEvent> {
+        let mut misses = Vec::new();
+        for note in notes {
+            if current_time - note.time <= HIT_WINDOW_SECONDS {
+                continue;
+            }
+            let key = JudgedKey::new(note);
+            if self.judged.contains_key(&key) {
+                continue;
+            }
+            self.judged.insert(key, note.time);
+            misses.push(NoteMissEvent { note: note.clone() });
+        }
+
+        // Notes older than the feed window never come back unless playback is rewound,
+        // which resets the matcher.
+        if let Some(oldest) = notes.iter().map(|note| note.time).reduce(f32::min) {
+            self.judged.retain(|_, time| *time >= oldest);
+        }
+        misses.sort_by(|a, b| a.note.time.total_cmp(&b.note.time));
+        misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::pitch::analyze_samples;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn note(time: f32, string_index: usize, fret: i32) -> TimelineNote {
+        TimelineNote {
+            time,
+            sustain: 0.0,
+            string_index,
+            fret,
+            techniques: Vec::new(),
+            additional_frets: Vec::new(),
+            slide_target: None,
+            slide_unpitched_target: None,
+            max_bend: 0.0,
+            chord_index: None,
+        }
+    }
+
+    fn guitar() -> InstrumentTuning {
+        InstrumentTuning {
+            open_strings: standard_open_strings(TabsInstrument::Guitar, 6),
+            capo_fret: 0,
+        }
+    }
+
+    // Silence with a sine of `frequency` starting at `start` seconds.
+    fn played(frequency: f32, start: f32, seconds: f32) -> Vec<f32> {
+        let start = (start * SAMPLE_RATE as f32) as usize;
+        let len = (seconds * SAMPLE_RATE as f32) as usize;
+        (0..len)
+            .map(|i| match i.checked_sub(start) {
+                Some(t) => {
+                    0.5 * (2.0 * std::f32::consts::PI * frequency * t as f32 / SAMPLE_RATE as f32)
+                        .sin()
+                }
+                None => 0.0,
+            })
+            .collect()
+    }
+
+    fn judge(samples: &[f32], notes: &[TimelineNote]) -> (Vec<NoteHitEvent>, Vec<NoteMissEvent>) {
+        let tuning = guitar();
+        let mut matcher = NoteMatcher::default();
+        let mut hits = Vec::new();
+        let mut misses = Vec::new();
+        for frame in analyze_samples(samples, SAMPLE_RATE) {
+            let time = frame.time as f32;
+            hits.extend(matcher.observe(time, &frame, notes, &tuning));
+            misses.extend(matcher.expire(time, notes));
+        }
+        (hits, misses)
+    }
+
+    #[test]
+    fn standard_tuning_matches_guitar_and_bass() {
+        assert_eq!(
+            standard_open_strings(TabsInstrument::Guitar, 6),
+            [40, 45, 50, 55, 59, 64]
+        );
+        assert_eq!(
+            standard_open_strings(TabsInstrument::Bass, 4),
+            [28, 33, 38, 43]
+        );
+        assert_eq!(standard_open_strings(TabsInstrument::Guitar, 7)[0], 35);
+    }
+
+    #[test]
+    fn capo_raises_open_strings_only() {
+        let tuning = InstrumentTuning {
+            capo_fret: 2,
+            ..guitar()
+        };
+        assert_eq!(tuning.note_midi(0, 0), Some(42));
+        assert_eq!(tuning.note_midi(0, 5), Some(45));
+        assert_eq!(tuning.note_midi(6, 0), None);
+    }
+
+    #[test]
+    fn note_played_on_time_is_a_hit() {
+        // Open A string, 110 Hz.
+        let notes = [note(1.0, 1, 0)];
+        let (hits, misses) = judge(&played(110.0, 1.0, 2.0), &notes);
+
+        assert_eq!(hits.len(), 1);
+        assert!((hits[0].played_time - 1.0).abs() <= HIT_WINDOW_SECONDS);
+        assert!((hits[0].detected_frequency - 110.0).abs() < 2.0);
+        assert!(misses.is_empty());
+    }
+
+    #[test]
+    fn wrong_pitch_is_a_miss() {
+        // Fret 2 on the A string is B2; an open A is played instead.
+        let notes = [note(1.0, 1, 2)];
+        let (hits, misses) = judge(&played(110.0, 1.0, 2.0), &notes);
+
+        assert!(hits.is_empty());
+        assert_eq!(misses.len(), 1);
+    }
+
+    #[test]
+    fn note_played_outside_the_window_is_a_miss() {
+        let notes = [note(1.0, 1, 0)];
+        let (hits, misses) = judge(&played(110.0, 1.0 + 3.0 * HIT_WINDOW_SECONDS, 2.0), &notes);
+
+        assert!(hits.is_empty());
+        assert_eq!(misses.len(), 1);
+    }
+
+    #[test]
+    fn each_note_is_judged_once() {
+        let notes = [note(1.0, 1, 0), note(1.5, 1, 0)];
+        let mut samples = played(110.0, 1.0, 1.4);
+        samples.extend(played(110.0, 0.1, 1.0));
+        let (hits, misses) = judge(&samples, &notes);
+
+        assert_eq!(hits.len(), 2);
+        assert!(misses.is_empty());
+    }
+}
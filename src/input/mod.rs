@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+pub mod capture;
+pub use capture::{list_input_devices, CaptureError, CapturedFrame, InputCapture, InputSource};
+
+pub mod pitch;
+pub use pitch::{analyze_samples, DetectedPitch, PitchAnalyzer, PitchDetector, PitchFrame};
+
+pub mod matcher;
+pub use matcher::{standard_open_strings, InstrumentTuning, NoteMatcher, HIT_WINDOW_SECONDS};
+
+use crate::components::{StringTimelineFeed, TimelineNote};
+use crate::file::{Settings, Song};
+use crate::scenes::gameplay::{track_timeline, PlaybackSeeked, SongPlayback};
+use crate::scenes::song_selection::SongSelectState;
+use crate::states::{AppState, GameState};
+
+#[derive(Message, Clone)]
+pub struct NoteHitEvent {
+    pub note: TimelineNote,
+    pub played_time: f32,
+    pub detected_frequency: f32,
+}
+
+#[derive(Message, Clone)]
+pub struct NoteMissEvent {
+    pub note: TimelineNote,
+}
+
+pub struct InputCapturePlugin;
+
+impl Plugin for InputCapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<NoteHitEvent>()
+            .add_message::<NoteMissEvent>()
+            .init_resource::<NoteMatcher>()
+            .init_resource::<InstrumentTuning>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                start_input_capture,
+            )
+            .add_systems(OnExit(AppState::Gameplay), stop_input_capture)
+            .add_systems(
+                Update,
+                (reset_matcher_on_seek, match_input_to_notes)
+                    .chain()
+                    .after(track_timeline)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<InputCapture>),
+            );
+    }
+}
+
+pub fn start_input_capture(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    selected_song: Res<SongSelectState>,
+    songs: Res<Assets<Song>>,
+    mut tuning: ResMut<InstrumentTuning>,
+    mut matcher: ResMut<NoteMatcher>,
+) {
+    matcher.reset();
+    commands.remove_resource::<InputCapture>();
+
+    let arrangement = selected_song
+        .selected_song
+        .as_ref()
+        .and_then(|handle| songs.get(handle))
+        .zip(selected_song.selected_instrument.as_deref())
+        .and_then(|(song, key)| song.metadata.arrangements.get(key));
+    *tuning = arrangement
+        .map(InstrumentTuning::from_arrangement)
+        .unwrap_or_default();
+
+    if !settings.input.enabled {
+        return;
+    }
+    if tuning.open_strings.is_empty() {
+        info!("Selected arrangement has no strings to score; input capture disabled");
+        return;
+    }
+
+    let source = match &settings.input.wav_file {
+        Some(path) => InputSource::WavFile(path.clone()),
+        None => InputSource::Device(settings.input.device.clone()),
+    };
+    match InputCapture::start(source) {
+        Ok(mut capture) => {
+            capture.set_latency(settings.input.latency_ms / 1000.0);
+            info!("Input capture started from {:?}", capture.source());
+            commands.insert_resource(capture);
+        }
+        Err(err) => warn!("Input capture unavailable: {err}"),
+    }
+}
+
+pub fn stop_input_capture(mut commands: Commands, mut matcher: ResMut<NoteMatcher>) {
+    commands.remove_resource::<InputCapture>();
+    matcher.reset();
+}
+
+pub fn reset_matcher_on_seek(
+    mut seeks: MessageReader<PlaybackSeeked>,
+    mut matcher: ResMut<NoteMatcher>,
+) {
+    if seeks.read().count() > 0 {
+        matcher.reset();
+    }
+}
+
+pub fn match_input_to_notes(
+    capture: Res<InputCapture>,
+    feed: Res<StringTimelineFeed>,
+    song_clock: Res<SongPlayback>,
+    tuning: Res<InstrumentTuning>,
+    mut matcher: ResMut<NoteMatcher>,
+    mut hits: MessageWriter<NoteHitEvent>,
+    mut misses: MessageWriter<NoteMissEvent>,
+) {
+    // Wall-clock delays cover less song time when playback is slowed down.
+    let rate = song_clock.playback_rate();
+    let latency = capture.latency() * rate;
+    for captured in capture.drain_frames() {
+        let age = captured.captured_at.elapsed().as_secs_f32() * rate;
+        let song_time = feed.current_time - age - latency;
+        hits.write_batch(matcher.observe(song_time, &captured.frame, &feed.notes, &tuning));
+    }
+    misses.write_batch(matcher.expire(feed.current_time - latency, &feed.notes));
+}
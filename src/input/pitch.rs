@@ -0,0 +1,515 @@
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::sync::Arc;
+
+const MIN_FREQUENCY: f32 = 30.0;
+const MAX_FREQUENCY: f32 = 1400.0;
+const FRAME_SECONDS: f32 = 0.064;
+const HOPS_PER_FRAME: usize = 4;
+const SILENCE_RMS: f32 = 0.01;
+const YIN_THRESHOLD: f32 = 0.12;
+const YIN_FALLBACK_THRESHOLD: f32 = 0.35;
+const POLY_FFT_SIZE: usize = 16384;
+const POLY_MAX_FREQUENCY: f32 = 5000.0;
+const POLY_MAX_PEAKS: usize = 64;
+const POLY_MAX_HARMONICS: usize = 8;
+const POLY_MAX_NOTES: usize = 6;
+const POLY_MATCH_CENTS: f32 = 40.0;
+const POLY_RELATIVE_THRESHOLD: f32 = 0.3;
+const POLY_PEAK_FLOOR_RATIO: f32 = 0.02;
+const POLY_LOWEST_MIDI: i32 = 28;
+const POLY_HIGHEST_MIDI: i32 = 88;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedPitch {
+    pub frequency: f32,
+    pub confidence: f32,
+}
+
+impl DetectedPitch {
+    pub fn midi(&self) -> f32 {
+        frequency_to_midi(self.frequency)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PitchFrame {
+    pub time: f64,
+    pub rms: f32,
+    pub monophonic: Option<DetectedPitch>,
+    pub polyphonic: Vec<DetectedPitch>,
+}
+
+pub fn frequency_to_midi(frequency: f32) -> f32 {
+    69.0 + 12.0 * (frequency.max(f32::EPSILON) / 440.0).log2()
+}
+
+pub fn midi_to_frequency(midi: f32) -> f32 {
+    440.0 * 2f32.powf((midi - 69.0) / 12.0)
+}
+
+#[derive(Clone, Copy)]
+struct SpectralPeak {
+    frequency: f32,
+    amplitude: f32,
+}
+
+pub struct PitchDetector {
+    sample_rate: u32,
+    frame_len: usize,
+    yin_forward: Arc<dyn Fft<f32>>,
+    yin_inverse: Arc<dyn Fft<f32>>,
+    poly_forward: Arc<dyn Fft<f32>>,
+    yin_signal: Vec<Complex32>,
+    yin_window: Vec<Complex32>,
+    poly_buffer: Vec<Complex32>,
+    hann: Vec<f32>,
+}
+
+impl PitchDetector {
+    pub fn new(sample_rate: u32, frame_len: usize) -> Self {
+        let mut planner = FftPlanner::new();
+        let yin_size = (frame_len * 2).next_power_of_two();
+        let poly_size = POLY_FFT_SIZE.max(frame_len.next_power_of_two());
+        let hann = (0..frame_len)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * i as f32 / frame_len.max(2) as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+
+        Self {
+            sample_rate,
+            frame_len,
+            yin_forward: planner.plan_fft_forward(yin_size),
+            yin_inverse: planner.plan_fft_inverse(yin_size),
+            poly_forward: planner.plan_fft_forward(poly_size),
+            yin_signal: vec![Complex32::default(); yin_size],
+            yin_window: vec![Complex32::default(); yin_size],
+            poly_buffer: vec![Complex32::default(); poly_size],
+            hann,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn frame_len(&self) -> usize {
+        self.frame_len
+    }
+
+    // YIN with the difference function computed through an FFT cross-correlation.
+    pub fn detect_monophonic(&mut self, frame: &[f32]) -> Option<DetectedPitch> {
+        let frame = &frame[..frame.len().min(self.frame_len)];
+        if rms(frame) < SILENCE_RMS {
+            return None;
+        }
+
+        let sample_rate = self.sample_rate as f32;
+        let max_lag = ((sample_rate / MIN_FREQUENCY).ceil() as usize).min(frame.len() / 2);
+        let min_lag = ((sample_rate / MAX_FREQUENCY).floor() as usize).max(2);
+        if max_lag <= min_lag + 2 {
+            return None;
+        }
+        let window = frame.len() - max_lag;
+
+        for (i, slot) in self.yin_signal.iter_mut().enumerate() {
+            *slot = Complex32::new(frame.get(i).copied().unwrap_or(0.0), 0.0);
+        }
+        for (i, slot) in self.yin_window.iter_mut().enumerate() {
+            let value = if i < window { frame[i] } else { 0.0 };
+            *slot = Complex32::new(value, 0.0);
+        }
+        self.yin_forward.process(&mut self.yin_signal);
+        self.yin_forward.process(&mut self.yin_window);
+        for (signal, window) in self.yin_signal.iter_mut().zip(self.yin_window.iter()) {
+            *signal *= window.conj();
+        }
+        self.yin_inverse.process(&mut self.yin_signal);
+        let scale = 1.0 / self.yin_signal.len() as f32;
+
+        let mut prefix = Vec::with_capacity(frame.len() + 1);
+        prefix.push(0.0f32);
+        for sample in frame {
+            let last = *prefix.last().unwrap_or(&0.0);
+            prefix.push(last + sample * sample);
+        }
+        let window_energy = prefix[window];
+
+        let mut cmnd = vec![1.0f32; max_lag + 1];
+        let mut running_sum = 0.0f32;
+        for (lag, value) in cmnd.iter_mut().enumerate().skip(1) {
+            let shifted_energy = prefix[lag + window] - prefix[lag];
+            let correlation = self.yin_signal[lag].re * scale;
+            let difference = (window_energy + shifted_energy - 2.0 * correlation).max(0.0);
+            running_sum += difference;
+            *value = if running_sum > f32::EPSILON {
+                difference * lag as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        let mut chosen = None;
+        let mut lag = min_lag;
+        while lag < max_lag {
+            if cmnd[lag] < YIN_THRESHOLD {
+                while lag + 1 < max_lag && cmnd[lag + 1] < cmnd[lag] {
+                    lag += 1;
+                }
+                chosen = Some(lag);
+                break;
+            }
+            lag += 1;
+        }
+
+        let lag = match chosen {
+            Some(lag) => lag,
+            None => {
+                let (lag, value) = cmnd[min_lag..max_lag]
+                    .iter()
+                    .enumerate()
+                    .min_by(|a, b| a.1.total_cmp(b.1))
+                    .map(|(offset, value)| (offset + min_lag, *value))?;
+                if value > YIN_FALLBACK_THRESHOLD {
+                    return None;
+                }
+                lag
+            }
+        };
+
+        let refined_lag = parabolic_offset(cmnd[lag - 1], cmnd[lag], cmnd[lag + 1]) + lag as f32;
+        if refined_lag <= 0.0 {
+            return None;
+        }
+
+        Some(DetectedPitch {
+            frequency: sample_rate / refined_lag,
+            confidence: (1.0 - cmnd[lag]).clamp(0.0, 1.0),
+        })
+    }
+
+    // Iterative harmonic-sum estimation: pick the most salient fundamental, remove its
+    // smoothed partials from the peak list and repeat until the residual is too weak.
+    pub fn detect_polyphonic(&mut self, frame: &[f32]) -> Vec<DetectedPitch> {
+        let frame = &frame[..frame.len().min(self.frame_len)];
+        if rms(frame) < SILENCE_RMS {
+            return Vec::new();
+        }
+
+        for (i, slot) in self.poly_buffer.iter_mut().enumerate() {
+            let value = if i < frame.len() {
+                frame[i] * self.hann[i]
+            } else {
+                0.0
+            };
+            *slot = Complex32::new(value, 0.0);
+        }
+        self.poly_forward.process(&mut self.poly_buffer);
+
+        let mut peaks = self.spectral_peaks();
+        if peaks.is_empty() {
+            return Vec::new();
+        }
+
+        let mut pitches = Vec::new();
+        let mut first_salience = None;
+        while pitches.len() < POLY_MAX_NOTES {
+            let best = (POLY_LOWEST_MIDI..=POLY_HIGHEST_MIDI)
+                .filter(|midi| {
+                    !pitches
+                        .iter()
+                        .any(|pitch: &DetectedPitch| (pitch.midi() - *midi as f32).abs() < 0.5)
+                })
+                .map(|midi| {
+                    let f0 = midi_to_frequency(midi as f32);
+                    (f0, harmonic_salience(&peaks, f0))
+                })
+                .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+
+            let Some((f0, (salience, fundamental))) = best else {
+                break;
+            };
+            if salience <= f32::EPSILON {
+                break;
+            }
+            let reference = *first_salience.get_or_insert(salience);
+            if salience < reference * POLY_RELATIVE_THRESHOLD {
+                break;
+            }
+
+            pitches.push(DetectedPitch {
+                frequency: fundamental.unwrap_or(f0),
+                confidence: (salience / reference).clamp(0.0, 1.0),
+            });
+            subtract_partials(&mut peaks, fundamental.unwrap_or(f0));
+        }
+
+        pitches
+    }
+
+    fn spectral_peaks(&self) -> Vec<SpectralPeak> {
+        let bin_hz = self.sample_rate as f32 / self.poly_buffer.len() as f32;
+        let last_bin = ((POLY_MAX_FREQUENCY / bin_hz) as usize).min(self.poly_buffer.len() / 2 - 1);
+        let magnitudes: Vec<f32> = self.poly_buffer[..=last_bin + 1]
+            .iter()
+            .map(|bin| bin.norm())
+            .collect();
+        let strongest = magnitudes.iter().copied().fold(0.0f32, f32::max);
+        let floor = strongest * POLY_PEAK_FLOOR_RATIO;
+
+        let mut peaks: Vec<SpectralPeak> = (2..last_bin)
+            .filter(|&k| {
+                magnitudes[k] > floor
+                    && magnitudes[k] > magnitudes[k - 1]
+                    && magnitudes[k] >= magnitudes[k + 1]
+            })
+            .map(|k| {
+                let offset = parabolic_offset(
+                    magnitudes[k - 1].max(f32::EPSILON).ln(),
+                    magnitudes[k].max(f32::EPSILON).ln(),
+                    magnitudes[k + 1].max(f32::EPSILON).ln(),
+                );
+                SpectralPeak {
+                    frequency: (k as f32 + offset) * bin_hz,
+                    amplitude: magnitudes[k],
+                }
+            })
+            .collect();
+
+        peaks.sort_by(|a, b| b.amplitude.total_cmp(&a.amplitude));
+        peaks.truncate(POLY_MAX_PEAKS);
+        peaks
+    }
+}
+
+pub struct PitchAnalyzer {
+    detector: PitchDetector,
+    buffer: Vec<f32>,
+    hop: usize,
+    consumed: u64,
+}
+
+impl PitchAnalyzer {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = ((sample_rate as f32 * FRAME_SECONDS) as usize).next_power_of_two();
+        Self {
+            detector: PitchDetector::new(sample_rate, frame_len),
+            buffer: Vec::with_capacity(frame_len * 2),
+            hop: (frame_len / HOPS_PER_FRAME).max(1),
+            consumed: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.detector.sample_rate()
+    }
+
+    pub fn push(&mut self, samples: &[f32], frames: &mut Vec<PitchFrame>) {
+        self.buffer.extend_from_slice(samples);
+        let frame_len = self.detector.frame_len();
+        let sample_rate = self.detector.sample_rate() as f64;
+
+        while self.buffer.len() >= frame_len {
+            let frame = &self.buffer[..frame_len];
+            let level = rms(frame);
+            let monophonic = self.detector.detect_monophonic(frame);
+            let polyphonic = self.detector.detect_polyphonic(frame);
+            let center = self.consumed + (frame_len / 2) as u64;
+            frames.push(PitchFrame {
+                time: center as f64 / sample_rate,
+                rms: level,
+                monophonic,
+                polyphonic,
+            });
+            self.buffer.drain(..self.hop);
+            self.consumed += self.hop as u64;
+        }
+    }
+
+    pub fn received_seconds(&self) -> f64 {
+        (self.consumed + self.buffer.len() as u64) as f64 / self.detector.sample_rate() as f64
+    }
+}
+
+pub fn analyze_samples(samples: &[f32], sample_rate: u32) -> Vec<PitchFrame> {
+    let mut analyzer = PitchAnalyzer::new(sample_rate);
+    let mut frames = Vec::new();
+    analyzer.push(samples, &mut frames);
+    frames
+}
+
+fn harmonic_salience(peaks: &[SpectralPeak], f0: f32) -> (f32, Option<f32>) {
+    let mut salience = 0.0;
+    let mut low_partial_found = false;
+    let mut fundamental = None;
+
+    for harmonic in 1..=POLY_MAX_HARMONICS {
+        let target = f0 * harmonic as f32;
+        if target > POLY_MAX_FREQUENCY {
+            break;
+        }
+        let Some(peak) = matching_peak(peaks, target) else {
+            continue;
+        };
+        if harmonic <= 2 {
+            low_partial_found = true;
+        }
+        if harmonic == 1 {
+            fundamental = Some(peak.frequency);
+        }
+        let weight = (f0 + 27.0) / (harmonic as f32 * f0 + 320.0);
+        salience += weight * peak.amplitude;
+    }
+
+    if low_partial_found {
+        (salience, fundamental)
+    } else {
+        (0.0, None)
+    }
+}
+
+fn subtract_partials(peaks: &mut [SpectralPeak], f0: f32) {
+    let amplitudes: Vec<Option<f32>> = (1..=POLY_MAX_HARMONICS + 1)
+        .map(|harmonic| matching_peak(peaks, f0 * harmonic as f32).map(|peak| peak.amplitude))
+        .collect();
+
+    for harmonic in 1..=POLY_MAX_HARMONICS {
+        let target = f0 * harmonic as f32;
+        let Some(index) = matching_peak_index(peaks, target) else {
+            continue;
+        };
+        let own = peaks[index].amplitude;
+        let neighbours: Vec<f32> = [harmonic.checked_sub(2), Some(harmonic)]
+            .into_iter()
+            .flatten()
+            .filter_map(|idx| amplitudes.get(idx).copied().flatten())
+            .collect();
+        let smoothed = if harmonic == 1 || neighbours.is_empty() {
+            own
+        } else {
+            own.min(neighbours.iter().sum::<f32>() / neighbours.len() as f32)
+        };
+        peaks[index].amplitude = (own - smoothed).max(0.0);
+    }
+}
+
+fn matching_peak(peaks: &[SpectralPeak], target: f32) -> Option<SpectralPeak> {
+    matching_peak_index(peaks, target).map(|index| peaks[index])
+}
+
+fn matching_peak_index(peaks: &[SpectralPeak], target: f32) -> Option<usize> {
+    peaks
+        .iter()
+        .enumerate()
+        .filter(|(_, peak)| {
+            peak.amplitude > 0.0
+                && (1200.0 * (peak.frequency / target).log2()).abs() < POLY_MATCH_CENTS
+        })
+        .max_by(|a, b| a.1.amplitude.total_cmp(&b.1.amplitude))
+        .map(|(index, _)| index)
+}
+
+fn parabolic_offset(left: f32, center: f32, right: f32) -> f32 {
+    let denominator = left - 2.0 * center + right;
+    if denominator.abs() <= f32::EPSILON {
+        0.0
+    } else {
+        (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn tone(frequencies: &[f32], seconds: f32) -> Vec<f32> {
+        let len = (SAMPLE_RATE as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                frequencies
+                    .iter()
+                    .map(|f| {
+                        // A few decaying partials, closer to a plucked string than a pure sine.
+                        (1..=4)
+                            .map(|h| {
+                                (2.0 * std::f32::consts::PI * f * h as f32 * t).sin() / h as f32
+                            })
+                            .sum::<f32>()
+                    })
+                    .sum::<f32>()
+                    * 0.2
+            })
+            .collect()
+    }
+
+    fn sine(frequency: f32, seconds: f32) -> Vec<f32> {
+        let len = (SAMPLE_RATE as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| {
+                0.5 * (2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLE_RATE as f32).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn yin_finds_the_frequency_of_a_sine() {
+        let mut detector = PitchDetector::new(SAMPLE_RATE, 4096);
+        for frequency in [41.2, 82.41, 110.0, 196.0, 440.0, 987.8] {
+            let pitch = detector
+                .detect_monophonic(&sine(frequency, 0.1))
+                .unwrap_or_else(|| panic!("no pitch for {frequency} Hz"));
+            let cents = 1200.0 * (pitch.frequency / frequency).log2();
+            assert!(
+                cents.abs() < 5.0,
+                "{frequency} Hz detected as {}",
+                pitch.frequency
+            );
+            assert!(pitch.confidence > 0.8);
+        }
+    }
+
+    #[test]
+    fn yin_ignores_silence() {
+        let mut detector = PitchDetector::new(SAMPLE_RATE, 4096);
+        assert!(detector.detect_monophonic(&vec![0.0; 4096]).is_none());
+        assert!(detector.detect_polyphonic(&vec![0.0; 4096]).is_empty());
+    }
+
+    #[test]
+    fn polyphonic_detection_separates_two_notes() {
+        let mut detector = PitchDetector::new(SAMPLE_RATE, 4096);
+        // A2 and E3, the root and fifth of an A power chord.
+        let pitches = detector.detect_polyphonic(&tone(&[110.0, 164.81], 0.1));
+        let midis: Vec<i32> = pitches.iter().map(|p| p.midi().round() as i32).collect();
+        assert!(midis.contains(&45), "{midis:?}");
+        assert!(midis.contains(&52), "{midis:?}");
+    }
+
+    #[test]
+    fn analyzer_frames_follow_the_input() {
+        let mut samples = vec![0.0; SAMPLE_RATE as usize / 2];
+        samples.extend(sine(220.0, 0.5));
+        let frames = analyze_samples(&samples, SAMPLE_RATE);
+
+        assert!(frames.windows(2).all(|pair| pair[0].time < pair[1].time));
+        let first_pitched = frames
+            .iter()
+            .find(|frame| frame.monophonic.is_some())
+            .expect("the tone is detected");
+        assert!((first_pitched.time - 0.5).abs() < 0.1);
+        let last = frames.last().and_then(|frame| frame.monophonic).unwrap();
+        assert!((last.midi() - 57.0).abs() < 0.1);
+    }
+}
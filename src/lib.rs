@@ -1,8 +1,11 @@
 pub mod audio;
 pub mod components;
+pub mod convert;
 pub mod debug;
 pub mod file;
+pub mod input;
 pub mod scenes;
+pub mod scoring;
 pub mod shaders;
 pub mod states;
 pub mod widgets;
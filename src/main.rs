@@ -8,7 +8,7 @@ use bevy_kira_audio::prelude::AudioPlugin as KiraAudioPlugin;
 use tabs_app::shaders::RegisterShadersPlugin;
 use tabs_app::states::{AppState, GameplayPlugin, SongSelectPlugin, StartupPlugin};
 use tabs_app::widgets::UiLayerPlugin;
-use tabs_app::{file::config::ConfigPlugin, states::GameState};
+use tabs_app::{file::config::ConfigPlugin, file::package::SongPackagePlugin, states::GameState};
 
 #[cfg(not(feature = "production"))]
 use tabs_app::debug::DebugPlugin;
@@ -19,6 +19,7 @@ fn main() {
             ConfigPlugin,
             #[cfg(not(feature = "production"))]
             DebugPlugin,
+            SongPackagePlugin,
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
@@ -1,67 +1,194 @@
-use crate::audio::StreamingAudio;
+use crate::audio::{SongStream, StreamingAudio, MAX_STRETCH_RATE, MIN_STRETCH_RATE};
 use crate::components::{
-    clamp_block_duration, default_block_duration, visible_block_count, StringTimelineFeed,
-    TimelineNote,
+    clamp_block_duration, default_block_duration, lyric_lines, visible_block_count, LyricDisplay,
+    StringTimelineFeed, TimelineChord, TimelineNote, VocalTimelineFeed,
 };
-use crate::file::song::{StringTab, TabNote, TabNoteChart, VocalPhrase};
-use crate::file::Tab;
+use crate::file::library::{song_asset_path, LibraryRoots};
+use crate::file::package::song_file_exists;
+use crate::file::song::{StringTab, TabNote, TabNoteChart};
+use crate::file::{Song, Tab};
+use crate::scenes::learn::LearnMode;
 use crate::scenes::song_selection::SongSelectState;
-use crate::states::GameState;
+use crate::scoring::GameplaySession;
+use crate::states::{AppState, GameState};
+use bevy::asset::LoadState;
 use bevy::prelude::*;
-use kira::sound::streaming::StreamingSoundHandle;
-use kira::sound::FromFileError;
 use kira::sound::PlaybackState;
 use kira::Tween;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::time::Instant;
 
-const DEFAULT_DIFFICULTY_PERCENT: f32 = 100.0;
 const MIN_NOTES_FOR_TEMPO: usize = 8;
 const MIN_INTERVAL_SECONDS: f32 = 0.01;
 const MAX_INTERVAL_SECONDS: f32 = 4.0;
 const BEATS_PER_BLOCK: f32 = 4.0;
 const MIN_DIFF_SECONDS: f32 = 0.0001;
+const SONG_END_GRACE_SECONDS: f32 = 1.0;
 
 #[derive(Resource, Default)]
 pub struct GameplayAssets {
     audio_path: Option<PathBuf>,
     tab_handle: Handle<Tab>,
+    lyrics_handle: Option<Handle<Tab>>,
 }
 
-#[derive(Resource, Default)]
+impl GameplayAssets {
+    pub fn tab_handle(&self) -> &Handle<Tab> {
+        &self.tab_handle
+    }
+
+    pub fn lyrics_handle(&self) -> Option<&Handle<Tab>> {
+        self.lyrics_handle.as_ref()
+    }
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct SeekRequest {
+    pub time: f32,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlaybackSeeked {
+    pub from: f32,
+    pub to: f32,
+}
+
+#[derive(Resource)]
 pub struct SongPlayback {
-    stream_handle: Option<StreamingSoundHandle<FromFileError>>,
+    stream: Option<SongStream>,
     reference_origin: Option<(Instant, f32)>,
     last_logged_state: Option<PlaybackState>,
+    paused_at: Option<f32>,
+    pending_seek: Option<f32>,
+    playback_rate: f32,
+    preserve_pitch: bool,
+}
+
+impl Default for SongPlayback {
+    fn default() -> Self {
+        Self {
+            stream: None,
+            reference_origin: None,
+            last_logged_state: None,
+            paused_at: None,
+            pending_seek: None,
+            playback_rate: 1.0,
+            preserve_pitch: true,
+        }
+    }
 }
 
 impl SongPlayback {
     pub fn reset(&mut self) {
-        if let Some(mut handle) = self.stream_handle.take() {
-            handle.stop(Tween::default());
+        if let Some(mut stream) = self.stream.take() {
+            stream.handle.stop(Tween::default());
         }
         self.reference_origin = None;
         self.last_logged_state = None;
+        self.paused_at = None;
+        self.pending_seek = None;
+        self.playback_rate = 1.0;
+        self.preserve_pitch = true;
     }
 
-    pub fn mark_streaming(&mut self, handle: StreamingSoundHandle<FromFileError>) {
+    pub fn mark_streaming(&mut self, stream: SongStream) {
         self.reference_origin = Some((Instant::now(), 0.0));
-        self.stream_handle = Some(handle);
+        self.playback_rate = stream.rate();
+        self.stream = Some(stream);
         self.last_logged_state = None;
+        self.paused_at = None;
+        self.pending_seek = None;
+    }
+
+    pub fn has_finished(&self) -> bool {
+        self.stream.as_ref().is_some_and(SongStream::has_finished)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn playback_rate(&self) -> f32 {
+        self.playback_rate
+    }
+
+    // The clock is frozen at the last reported time so the timeline doesn't move while paused.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_some() {
+            return;
+        }
+        let position = self.current_time().unwrap_or(0.0);
+        if let Some(stream) = self.stream.as_mut() {
+            stream.handle.pause(Tween::default());
+        }
+        self.paused_at = Some(position);
+    }
+
+    pub fn resume(&mut self) {
+        let Some(position) = self.paused_at.take() else {
+            return;
+        };
+        if let Some(stream) = self.stream.as_mut() {
+            stream.handle.resume(Tween::default());
+        }
+        self.reference_origin = Some((Instant::now(), position));
+    }
+
+    // kira applies the seek on the audio thread, so the reported position lags behind for a
+    // moment; until it catches up the reference clock stands in and smoothing restarts at `time`.
+    pub fn seek_to(&mut self, time: f32) {
+        let time = time.max(0.0);
+        if let Some(stream) = self.stream.as_mut() {
+            stream.seek_to(time as f64);
+        }
+        if self.paused_at.is_some() {
+            self.paused_at = Some(time);
+        }
+        self.reference_origin = Some((Instant::now(), time));
+        self.pending_seek = self.stream.as_ref().map(|_| time);
+    }
+
+    pub fn set_playback_rate(&mut self, rate: f32, preserve_pitch: bool) {
+        let rate = rate.clamp(MIN_STRETCH_RATE, MAX_STRETCH_RATE);
+        if (rate - self.playback_rate).abs() <= f32::EPSILON
+            && preserve_pitch == self.preserve_pitch
+        {
+            return;
+        }
+        if let Some(position) = self.reference_time() {
+            self.reference_origin = Some((Instant::now(), position));
+        }
+        if let Some(stream) = self.stream.as_mut() {
+            stream.set_rate(rate, preserve_pitch);
+        }
+        self.playback_rate = rate;
+        self.preserve_pitch = preserve_pitch;
+    }
+
+    fn reference_time(&self) -> Option<f32> {
+        self.reference_origin.map(|(origin, position)| {
+            position + origin.elapsed().as_secs_f32() * self.playback_rate
+        })
     }
 
     pub fn current_time(&mut self) -> Option<f32> {
         const SMOOTHING_ALPHA: f32 = 0.15;
+        const SEEK_SETTLE_SECONDS: f32 = 0.25;
 
-        if let Some(handle) = self.stream_handle.as_mut() {
-            if let Some(error) = handle.pop_error() {
+        if let Some(position) = self.paused_at {
+            return Some(position);
+        }
+
+        if let Some(stream) = self.stream.as_mut() {
+            if let Some(error) = stream.handle.pop_error() {
                 error!("Streaming decode error: {error}");
-                handle.stop(Tween::default());
-                self.stream_handle = None;
+                stream.handle.stop(Tween::default());
+                self.stream = None;
+                self.pending_seek = None;
             } else {
-                let state = handle.state();
+                let state = stream.handle.state();
                 if matches!(
                     state,
                     PlaybackState::Playing
@@ -75,7 +202,14 @@ impl SongPlayback {
                         info!("Streaming state {:?}", state);
                         self.last_logged_state = Some(state);
                     }
-                    let raw_position = handle.position() as f32;
+                    let raw_position = stream.position() as f32;
+                    if let Some(target) = self.pending_seek {
+                        let expected = self.reference_time().unwrap_or(target);
+                        if (raw_position - expected).abs() > SEEK_SETTLE_SECONDS {
+                            return Some(expected);
+                        }
+                        self.pending_seek = None;
+                    }
                     let smoothed = if let Some((_, previous)) = self.reference_origin {
                         previous + (raw_position - previous) * SMOOTHING_ALPHA
                     } else {
@@ -93,8 +227,7 @@ impl SongPlayback {
             }
         }
 
-        self.reference_origin
-            .map(|(origin, position)| position + origin.elapsed().as_secs_f32())
+        self.reference_time()
     }
 }
 
@@ -129,47 +262,63 @@ pub fn setup_loading_ui(mut commands: Commands) {
 pub fn start_loading_assets(
     mut loading: ResMut<GameplayAssets>,
     selected_song: Res<SongSelectState>,
+    songs: Res<Assets<Song>>,
     asset_server: Res<AssetServer>,
-    mut gameplay_state: ResMut<NextState<GameState>>,
+    roots: Res<LibraryRoots>,
+    (mut gameplay_state, mut app_state): (
+        ResMut<NextState<GameState>>,
+        ResMut<NextState<AppState>>,
+    ),
     mut song_clock: ResMut<SongPlayback>,
 ) {
     gameplay_state.set(GameState::Loading);
     song_clock.reset();
     loading.audio_path = None;
-    let song_metadata_path = if let Some(song_handle) = &selected_song.selected_song {
-        let metadata_path = if let Some(path) = song_handle.path() {
-            path.path()
-        } else {
-            panic!("Provided song has an invalid path!");
-        };
-        metadata_path
-    } else {
-        panic!("Provided no song!");
+    loading.lyrics_handle = None;
+    let Some(song_handle) = &selected_song.selected_song else {
+        return back_to_song_select(&mut app_state, "No song selected".to_string());
     };
-
-    let instrument_name = if let Some(instrument) = &selected_song.selected_instrument {
-        instrument
-    } else {
-        panic!("No instrument provided for the song!");
+    let Some(song_metadata_path) = song_handle.path() else {
+        return back_to_song_select(
+            &mut app_state,
+            "Selected song has no asset path".to_string(),
+        );
+    };
+    let Some(instrument_name) = &selected_song.selected_instrument else {
+        return back_to_song_select(
+            &mut app_state,
+            format!("No arrangement selected for '{song_metadata_path}'"),
+        );
+    };
+    let Some(song_metadata) = songs.get(song_handle).map(|song| &song.metadata) else {
+        return back_to_song_select(
+            &mut app_state,
+            format!("Song '{song_metadata_path}' is not loaded"),
+        );
+    };
+    let Some(arrangement) = song_metadata.arrangements.get(instrument_name) else {
+        return back_to_song_select(
+            &mut app_state,
+            format!("Arrangement '{instrument_name}' is not in '{song_metadata_path}'"),
+        );
     };
 
-    let song_folder_path = song_metadata_path.parent().unwrap();
-
-    let audio_relative_path = song_folder_path.join("song.wav");
-    let instrument_file = format!("{}.tab", instrument_name);
-    let instrument_path = song_folder_path.join(instrument_file);
+    let audio_asset_path = song_asset_path(song_metadata_path, &song_metadata.audio);
+    let instrument_path = song_asset_path(song_metadata_path, &arrangement.file);
 
-    let audio_full_path = if audio_relative_path.is_absolute() {
-        audio_relative_path.clone()
-    } else {
-        Path::new("assets").join(&audio_relative_path)
+    // The audio is streamed from disk rather than loaded as an asset.
+    let Some(audio_full_path) = roots.file_path(&audio_asset_path) else {
+        return back_to_song_select(
+            &mut app_state,
+            format!("Song '{song_metadata_path}' is not in a library root"),
+        );
     };
 
-    info!("Song folder {}", song_folder_path.display());
-    info!("Audio path {}", audio_relative_path.display());
-    info!("Instrument path {}", instrument_path.display());
+    info!("Song metadata {song_metadata_path}");
+    info!("Audio path {}", audio_full_path.display());
+    info!("Instrument path {instrument_path}");
 
-    if !audio_full_path.exists() {
+    if !song_file_exists(&audio_full_path) {
         warn!("Audio file does not exist at {}", audio_full_path.display());
     }
 
@@ -177,17 +326,43 @@ pub fn start_loading_assets(
 
     let tab_handle: Handle<Tab> = asset_server.load(instrument_path);
     loading.tab_handle = tab_handle;
+
+    if let Some(lyrics) = song_metadata.lyrics_arrangement_for(instrument_name) {
+        let lyrics_path = song_asset_path(song_metadata_path, &lyrics.file);
+        let lyrics_exist = roots
+            .file_path(&lyrics_path)
+            .is_some_and(|path| song_file_exists(&path));
+        if lyrics_exist {
+            info!("Lyrics path {lyrics_path}");
+            loading.lyrics_handle = Some(asset_server.load(lyrics_path));
+        }
+    }
+}
+
+// Gameplay can't start without these, so the player goes back to pick another song.
+fn back_to_song_select(app_state: &mut NextState<AppState>, message: String) {
+    error!("{message}");
+    app_state.set(AppState::SongSelect);
 }
 
 pub fn check_loading_progress(
     loading: Res<GameplayAssets>,
     asset_server: Res<AssetServer>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut app_state: ResMut<NextState<AppState>>,
 ) {
+    if let LoadState::Failed(err) = asset_server.load_state(&loading.tab_handle) {
+        return back_to_song_select(&mut app_state, format!("Failed to load tab: {err}"));
+    }
     let tab_ready = asset_server.load_state(&loading.tab_handle).is_loaded();
     let audio_ready = loading.audio_path.is_some();
+    // Lyrics are optional, so a broken vocals file shouldn't keep the song from starting.
+    let lyrics_ready = loading.lyrics_handle.as_ref().is_none_or(|handle| {
+        let state = asset_server.load_state(handle);
+        state.is_loaded() || state.is_failed()
+    });
 
-    if tab_ready && audio_ready {
+    if tab_ready && audio_ready && lyrics_ready {
         next_state.set(GameState::InGame);
     }
 }
@@ -197,12 +372,52 @@ pub fn update_loading_ui(mut query: Query<&mut Visibility, With<LoadingUI>>) {
     }
 }
 
+pub fn cleanup_gameplay(
+    mut commands: Commands,
+    loading_ui: Query<Entity, With<LoadingUI>>,
+    mut assets: ResMut<GameplayAssets>,
+    mut song_clock: ResMut<SongPlayback>,
+    mut gameplay_state: ResMut<NextState<GameState>>,
+) {
+    song_clock.reset();
+    *assets = GameplayAssets::default();
+    for entity in &loading_ui {
+        commands.entity(entity).despawn();
+    }
+    gameplay_state.set(GameState::Loading);
+}
+
 pub fn start_game_session(
     assets: Res<GameplayAssets>,
+    selected_song: Res<SongSelectState>,
+    songs: Res<Assets<Song>>,
+    tabs: Res<Assets<Tab>>,
     mut song_clock: ResMut<SongPlayback>,
     mut streaming_audio: ResMut<StreamingAudio>,
+    mut timeline: ResMut<StringTimelineFeed>,
 ) {
     song_clock.reset();
+    *timeline = StringTimelineFeed::default();
+
+    if let Some(Tab::Strings(tab)) = tabs.get(&assets.tab_handle) {
+        let metadata_length = selected_song
+            .selected_song
+            .as_ref()
+            .and_then(|handle| songs.get(handle))
+            .map(|song| song.metadata.length)
+            .unwrap_or_default();
+        let last_section_end = tab
+            .sections
+            .iter()
+            .map(|section| section.end_time)
+            .fold(0.0, f32::max);
+        timeline.song_length = if metadata_length > 0.0 {
+            metadata_length
+        } else {
+            last_section_end
+        };
+        timeline.set_sections(tab.sections.clone());
+    }
 
     let Some(audio_path) = assets.audio_path.as_ref() else {
         warn!("No audio path available to start gameplay audio");
@@ -210,21 +425,96 @@ pub fn start_game_session(
     };
 
     match streaming_audio.play_from_path(audio_path) {
-        Ok(handle) => {
-            let state = handle.state();
+        Ok(stream) => {
+            let state = stream.handle.state();
             info!(
                 "Started streaming {} (initial state {:?})",
                 audio_path.display(),
                 state
             );
-            song_clock.mark_streaming(handle);
+            song_clock.mark_streaming(stream);
         }
         Err(err) => error!("Failed to start streaming audio: {err}"),
     }
 }
 
+/// Picks up the playing `.tab` being rewritten on disk. The asset server has already
+/// swapped in the new chart under the same handle, so playback carries on from where it
+/// is and only what was taken from the old chart is rebuilt.
+pub fn reload_gameplay_tab(
+    mut events: MessageReader<AssetEvent<Tab>>,
+    assets: Res<GameplayAssets>,
+    tabs: Res<Assets<Tab>>,
+    mut timeline: ResMut<StringTimelineFeed>,
+    mut vocal_feed: ResMut<VocalTimelineFeed>,
+    mut session: ResMut<GameplaySession>,
+    mut learn: ResMut<LearnMode>,
+) {
+    let tab_id = assets.tab_handle.id();
+    let lyrics_id = assets.lyrics_handle.as_ref().map(Handle::id);
+    if !events
+        .read()
+        .any(|event| event.is_modified(tab_id) || lyrics_id.is_some_and(|id| event.is_modified(id)))
+    {
+        return;
+    }
+
+    if let Some(Tab::Strings(tab)) = tabs.get(tab_id) {
+        timeline.set_sections(tab.sections.clone());
+        session.set_sections(&tab.sections);
+        learn.reload(tab);
+    }
+    vocal_feed.reset_lines();
+    info!("Reloaded tab at {:.3}s", timeline.current_time);
+    timeline.block_duration_locked = false;
+    let current_time = timeline.current_time;
+    timeline.mark_seek(current_time);
+}
+
+pub fn apply_seek_requests(
+    mut requests: MessageReader<SeekRequest>,
+    mut song_clock: ResMut<SongPlayback>,
+    mut timeline: ResMut<StringTimelineFeed>,
+    mut seeked: MessageWriter<PlaybackSeeked>,
+) {
+    let Some(request) = requests.read().last().copied() else {
+        return;
+    };
+    let from = timeline.current_time;
+    song_clock.seek_to(request.time);
+    timeline.mark_seek(request.time);
+    seeked.write(PlaybackSeeked {
+        from,
+        to: request.time,
+    });
+}
+
+pub fn detect_song_end(
+    selected_song: Res<SongSelectState>,
+    songs: Res<Assets<Song>>,
+    timeline: Res<StringTimelineFeed>,
+    song_clock: Res<SongPlayback>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let song_length = selected_song
+        .selected_song
+        .as_ref()
+        .and_then(|handle| songs.get(handle))
+        .map(|song| song.metadata.length)
+        .filter(|length| *length > 0.0);
+    let past_length =
+        song_length.is_some_and(|length| timeline.current_time >= length + SONG_END_GRACE_SECONDS);
+
+    if song_clock.has_finished() || past_length {
+        info!("Song finished at {:.3}s", timeline.current_time);
+        next_state.set(AppState::Results);
+    }
+}
+
 pub fn track_timeline(
     assets: Res<GameplayAssets>,
+    selected_song: Res<SongSelectState>,
+    learn: Res<LearnMode>,
     mut song_clock: ResMut<SongPlayback>,
     tabs: Res<Assets<Tab>>,
     mut timeline: ResMut<StringTimelineFeed>,
@@ -251,13 +541,18 @@ pub fn track_timeline(
 
     match tab {
         Tab::Strings(tab_data) => {
-            let charts = select_charts_up_to(tab_data, DEFAULT_DIFFICULTY_PERCENT);
+            let base_threshold = difficulty_threshold(tab_data, selected_song.difficulty_percent);
+            let charts = match learn.max_difficulty() {
+                Some(learn_max) => select_charts_up_to(tab_data, learn_max.max(base_threshold)),
+                None => select_charts_up_to(tab_data, base_threshold),
+            };
             if charts.is_empty() {
                 timeline.block_duration = default_block_duration();
                 timeline.block_duration_locked = false;
                 update_timeline_window(&mut timeline, current_time);
                 timeline.string_count = 0;
                 timeline.notes.clear();
+                timeline.chords.clear();
                 return;
             }
 
@@ -288,14 +583,31 @@ pub fn track_timeline(
             sorted_charts.sort_by_key(|chart| chart.difficulty);
 
             let mut merged: HashMap<(u32, i32, i32), TabNote> = HashMap::new();
+            // The last note group before the window decides whether the first visible chord
+            // repeats it.
+            let mut lead_in: Option<(f32, Option<usize>)> = None;
             for chart in sorted_charts {
                 for note in &chart.notes {
                     if note.string < 0 {
                         continue;
                     }
+                    let threshold = learn.difficulty_at(note.time).unwrap_or(base_threshold);
+                    if chart.difficulty > threshold {
+                        continue;
+                    }
                     let note_start = note.time;
                     let note_end = (note.time + note.sustain.max(0.0)).max(note_start);
                     if note_end < window_start {
+                        let chord = usize::try_from(note.chord_index).ok();
+                        lead_in = match lead_in {
+                            Some((time, previous))
+                                if time > note.time
+                                    || (time == note.time && previous.is_some()) =>
+                            {
+                                Some((time, previous))
+                            }
+                            _ => Some((note.time, chord)),
+                        };
                         continue;
                     }
                     if note_start > window_end {
@@ -350,40 +662,77 @@ pub fn track_timeline(
                     slide_target: (note.slide_to >= 0).then_some(note.slide_to),
                     slide_unpitched_target: (note.slide_unpitch_to >= 0)
                         .then_some(note.slide_unpitch_to),
+                    max_bend: note.max_bend,
+                    chord_index: usize::try_from(note.chord_index).ok(),
                 });
             }
 
+            timeline.chords = collect_timeline_chords(tab_data, &visible_notes, lead_in);
             timeline.string_count = string_count;
             timeline.notes = visible_notes;
+            timeline.section_status = learn.status_at(current_time);
         }
-        Tab::Vocals(vocals) => {
+        Tab::Vocals(_) => {
             timeline.block_duration = default_block_duration();
             timeline.block_duration_locked = false;
             update_timeline_window(&mut timeline, current_time);
             timeline.string_count = 0;
             timeline.notes.clear();
-            let mut upcoming: Vec<(f32, &VocalPhrase)> = vocals
-                .vocals
-                .iter()
-                .filter(|phrase| phrase.time >= current_time)
-                .map(|phrase| (phrase.time - current_time, phrase))
-                .collect();
+            timeline.chords.clear();
+        }
+    }
+}
 
-            upcoming.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
-            upcoming.truncate(3);
+pub fn track_vocal_timeline(
+    assets: Res<GameplayAssets>,
+    tabs: Res<Assets<Tab>>,
+    timeline: Res<StringTimelineFeed>,
+    mut vocal_feed: ResMut<VocalTimelineFeed>,
+) {
+    let (vocals, display) = match tabs.get(&assets.tab_handle) {
+        Some(Tab::Vocals(vocals)) => (vocals, LyricDisplay::Karaoke),
+        _ => match assets
+            .lyrics_handle
+            .as_ref()
+            .and_then(|handle| tabs.get(handle))
+        {
+            Some(Tab::Vocals(vocals)) => (vocals, LyricDisplay::Ribbon),
+            _ => return,
+        },
+    };
+    if vocal_feed.lines.is_empty() {
+        vocal_feed.lines = lyric_lines(&vocals.vocals);
+        vocal_feed.display = display;
+    }
+    vocal_feed.current_time = timeline.current_time;
+}
 
-            if upcoming.is_empty() {
-                info!("[TabSync] t={:.3}s | no upcoming lyrics", current_time);
-            } else {
-                let lyric_summary = upcoming
-                    .iter()
-                    .map(|(delta, phrase)| format!("+{:.3}s '{}'", delta, phrase.lyric))
-                    .collect::<Vec<_>>()
-                    .join(" | ");
-                info!("[TabSync] t={:.3}s | {}", current_time, lyric_summary);
-            }
+// `notes` must be sorted by time. Notes struck together with the same chord index form one chord.
+fn collect_timeline_chords(
+    tab: &StringTab,
+    notes: &[TimelineNote],
+    lead_in: Option<(f32, Option<usize>)>,
+) -> Vec<TimelineChord> {
+    let mut chords = Vec::new();
+    let mut previous = lead_in.map(|(_, chord_index)| chord_index);
+
+    for group in notes.chunk_by(|a, b| a.time.to_bits() == b.time.to_bits()) {
+        let chord_index = group.iter().find_map(|note| note.chord_index);
+        let shape = chord_index.and_then(|index| Some((index, tab.chords.get(index)?)));
+        if let Some((index, shape)) = shape {
+            chords.push(TimelineChord {
+                time: group[0].time,
+                chord_index: index,
+                name: shape.name.clone(),
+                fingers: shape.fingers.clone(),
+                frets: shape.frets.clone(),
+                repeated: previous == Some(chord_index),
+            });
         }
+        previous = Some(chord_index);
     }
+
+    chords
 }
 
 fn determine_initial_block_duration(charts: &[&TabNoteChart]) -> f32 {
@@ -468,37 +817,35 @@ fn update_timeline_window(timeline: &mut StringTimelineFeed, current_time: f32)
     timeline.window_end = window_start + window_length;
 }
 
-fn select_charts_up_to<'a>(tab: &'a StringTab, difficulty_percent: f32) -> Vec<&'a TabNoteChart> {
-    if tab.note_charts.is_empty() {
-        return Vec::new();
-    }
-
-    let mut charts: Vec<&TabNoteChart> = tab.note_charts.iter().collect();
-    charts.sort_by_key(|chart| chart.difficulty);
-
-    let min_difficulty = charts
+fn difficulty_threshold(tab: &StringTab, difficulty_percent: f32) -> i32 {
+    let min_difficulty = tab
+        .note_charts
         .iter()
         .map(|chart| chart.difficulty)
         .min()
         .unwrap_or(0);
-    let max_difficulty = charts
+    let max_difficulty = tab
+        .note_charts
         .iter()
         .map(|chart| chart.difficulty)
         .max()
         .unwrap_or(min_difficulty);
 
     let clamped = difficulty_percent.clamp(0.0, 100.0);
-    let mut threshold = if max_difficulty <= 0 {
+    let threshold = if max_difficulty <= 0 {
         min_difficulty
     } else {
         ((max_difficulty as f32) * (clamped / 100.0)).ceil() as i32
     };
-    if threshold < min_difficulty {
-        threshold = min_difficulty;
-    }
+    threshold.max(min_difficulty)
+}
 
-    charts
-        .into_iter()
+fn select_charts_up_to(tab: &StringTab, threshold: i32) -> Vec<&TabNoteChart> {
+    let mut charts: Vec<&TabNoteChart> = tab
+        .note_charts
+        .iter()
         .filter(|chart| chart.difficulty <= threshold)
-        .collect()
+        .collect();
+    charts.sort_by_key(|chart| chart.difficulty);
+    charts
 }
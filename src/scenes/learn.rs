@@ -0,0 +1,314 @@
+use bevy::prelude::*;
+
+use crate::components::{SectionTrend, StringTimelineFeed, TimelineSectionStatus};
+use crate::file::settings::save_settings;
+use crate::file::song::StringTab;
+use crate::file::{AppConfig, Settings, Tab};
+use crate::input::HIT_WINDOW_SECONDS;
+use crate::scenes::gameplay::{GameplayAssets, PlaybackSeeked};
+use crate::scenes::song_selection::SongSelectState;
+use crate::scoring::{AccuracyStats, GameplaySession};
+
+const LEVEL_UP_HIT_RATE: f32 = 0.9;
+const LEVEL_DOWN_HIT_RATE: f32 = 0.6;
+const MIN_JUDGED_NOTES_PER_PASS: u32 = 4;
+
+struct LearnSection {
+    name: String,
+    start_time: f32,
+    end_time: f32,
+    level: usize,
+    trend: SectionTrend,
+    pass_start: Option<AccuracyStats>,
+}
+
+impl LearnSection {
+    fn contains(&self, time: f32) -> bool {
+        time >= self.start_time && time < self.end_time
+    }
+}
+
+// Each section climbs or drops through the chart difficulties depending on how the
+// player did the last time they played through it.
+#[derive(Resource, Default)]
+pub struct LearnMode {
+    progress_key: Option<String>,
+    difficulties: Vec<i32>,
+    sections: Vec<LearnSection>,
+}
+
+impl LearnMode {
+    pub fn new(tab: &StringTab, saved_levels: &[usize]) -> Self {
+        let mut difficulties: Vec<i32> = tab
+            .note_charts
+            .iter()
+            .map(|chart| chart.difficulty)
+            .collect();
+        difficulties.sort_unstable();
+        difficulties.dedup();
+        let max_level = difficulties.len().saturating_sub(1);
+
+        let mut sections: Vec<LearnSection> = tab
+            .sections
+            .iter()
+            .map(|section| LearnSection {
+                name: section.name.clone(),
+                start_time: section.start_time,
+                end_time: section.end_time,
+                level: 0,
+                trend: SectionTrend::Steady,
+                pass_start: None,
+            })
+            .collect();
+        sections.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        for (section, level) in sections.iter_mut().zip(saved_levels) {
+            section.level = (*level).min(max_level);
+        }
+
+        Self {
+            progress_key: None,
+            difficulties,
+            sections,
+        }
+    }
+
+    // Levels stay with the sections in start-time order; passes in progress start over.
+    pub fn reload(&mut self, tab: &StringTab) {
+        if !self.is_active() {
+            return;
+        }
+        let progress_key = self.progress_key.take();
+        *self = LearnMode::new(tab, &self.levels());
+        self.progress_key = progress_key;
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.difficulties.is_empty()
+    }
+
+    pub fn levels(&self) -> Vec<usize> {
+        self.sections.iter().map(|section| section.level).collect()
+    }
+
+    pub fn max_difficulty(&self) -> Option<i32> {
+        self.sections
+            .iter()
+            .filter_map(|section| self.difficulties.get(section.level))
+            .max()
+            .copied()
+    }
+
+    pub fn difficulty_at(&self, time: f32) -> Option<i32> {
+        let section = self
+            .sections
+            .iter()
+            .find(|section| section.contains(time))?;
+        self.difficulties.get(section.level).copied()
+    }
+
+    pub fn status_at(&self, time: f32) -> Option<TimelineSectionStatus> {
+        let section = self
+            .sections
+            .iter()
+            .find(|section| section.contains(time))?;
+        Some(TimelineSectionStatus {
+            name: section.name.clone(),
+            level: section.level,
+            level_count: self.difficulties.len(),
+            trend: section.trend,
+        })
+    }
+
+    // Section stats in the session and the learn sections share the same start-time ordering.
+    pub fn update(&mut self, current_time: f32, session_sections: &[AccuracyStats]) {
+        let level_count = self.difficulties.len();
+        for (section, stats) in self.sections.iter_mut().zip(session_sections) {
+            if section.contains(current_time) {
+                if section.pass_start.is_none() {
+                    section.pass_start = Some(*stats);
+                }
+                continue;
+            }
+
+            // Give the matcher time to judge the last notes of the section before scoring the pass.
+            if current_time < section.end_time + HIT_WINDOW_SECONDS {
+                continue;
+            }
+            if let Some(pass_start) = section.pass_start.take() {
+                section.finish_pass(stats.since(&pass_start), level_count);
+            }
+        }
+    }
+
+    // A practice loop jumping back from the end of a section completes its pass, any other
+    // seek abandons the pass in progress.
+    pub fn seeked(&mut self, from: f32, session_sections: &[AccuracyStats]) {
+        let level_count = self.difficulties.len();
+        for (section, stats) in self.sections.iter_mut().zip(session_sections) {
+            let Some(pass_start) = section.pass_start.take() else {
+                continue;
+            };
+            if from >= section.end_time {
+                section.finish_pass(stats.since(&pass_start), level_count);
+            }
+        }
+    }
+}
+
+impl LearnSection {
+    fn finish_pass(&mut self, pass: AccuracyStats, level_count: usize) {
+        if pass.judged() < MIN_JUDGED_NOTES_PER_PASS {
+            return;
+        }
+        let hit_rate = pass.hit_rate();
+        self.trend = if hit_rate >= LEVEL_UP_HIT_RATE && self.level + 1 < level_count {
+            self.level += 1;
+            SectionTrend::Up
+        } else if hit_rate < LEVEL_DOWN_HIT_RATE && self.level > 0 {
+            self.level -= 1;
+            SectionTrend::Down
+        } else {
+            SectionTrend::Steady
+        };
+        info!(
+            "Learn mode: '{}' pass hit rate {:.0}%, now level {}/{}",
+            self.name,
+            hit_rate * 100.0,
+            self.level + 1,
+            level_count
+        );
+    }
+}
+
+fn learn_progress_key(selected_song: &SongSelectState) -> Option<String> {
+    let song_key = selected_song.song_key()?;
+    let instrument = selected_song.selected_instrument.as_ref()?;
+    Some(format!("{song_key}/{instrument}"))
+}
+
+pub fn start_learn_mode(
+    selected_song: Res<SongSelectState>,
+    assets: Res<GameplayAssets>,
+    tabs: Res<Assets<Tab>>,
+    settings: Res<Settings>,
+    mut learn: ResMut<LearnMode>,
+) {
+    let tab = match tabs.get(assets.tab_handle()) {
+        Some(Tab::Strings(tab)) if selected_song.learn_mode => tab,
+        _ => {
+            *learn = LearnMode::default();
+            return;
+        }
+    };
+
+    let progress_key = learn_progress_key(&selected_song);
+    let saved_levels = progress_key
+        .as_ref()
+        .and_then(|key| settings.learn_levels.get(key))
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    *learn = LearnMode::new(tab, saved_levels);
+    learn.progress_key = progress_key;
+}
+
+pub fn update_learn_mode(
+    session: Res<GameplaySession>,
+    timeline: Res<StringTimelineFeed>,
+    mut seeks: MessageReader<PlaybackSeeked>,
+    mut learn: ResMut<LearnMode>,
+) {
+    if !learn.is_active() {
+        seeks.clear();
+        return;
+    }
+    let section_stats: Vec<AccuracyStats> = session
+        .sections
+        .iter()
+        .map(|section| section.stats)
+        .collect();
+    for seek in seeks.read() {
+        learn.seeked(seek.from, &section_stats);
+    }
+    learn.update(timeline.current_time, &section_stats);
+}
+
+pub fn save_learn_progress(
+    learn: Res<LearnMode>,
+    mut settings: ResMut<Settings>,
+    config: Res<AppConfig>,
+) {
+    let Some(key) = learn.progress_key.clone() else {
+        return;
+    };
+    if !learn.is_active() {
+        return;
+    }
+    settings.learn_levels.insert(key, learn.levels());
+    save_settings(&settings, &config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::song::{TabNoteChart, TabSection};
+
+    fn looped_tab() -> StringTab {
+        StringTab {
+            sections: vec![
+                TabSection {
+                    name: "Verse".to_string(),
+                    start_time: 0.0,
+                    end_time: 10.0,
+                },
+                TabSection {
+                    name: "Chorus".to_string(),
+                    start_time: 10.0,
+                    end_time: 20.0,
+                },
+            ],
+            chords: Vec::new(),
+            note_charts: [1, 2, 3]
+                .into_iter()
+                .map(|difficulty| TabNoteChart {
+                    difficulty,
+                    notes: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn perfect(count: u32) -> AccuracyStats {
+        AccuracyStats {
+            perfect: count,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn looping_a_section_levels_it_up_on_every_wrap() {
+        let mut learn = LearnMode::new(&looped_tab(), &[]);
+        let mut verse = AccuracyStats::default();
+
+        for pass in 1..=2 {
+            learn.update(0.5, &[verse, AccuracyStats::default()]);
+            verse = perfect(5 * pass);
+            // The loop seeks back as soon as the timeline reaches the section end.
+            learn.update(10.0, &[verse, AccuracyStats::default()]);
+            learn.seeked(10.0, &[verse, AccuracyStats::default()]);
+            assert_eq!(learn.levels(), [pass as usize, 0]);
+        }
+        // Already at the top difficulty.
+        learn.update(0.5, &[verse, AccuracyStats::default()]);
+        learn.seeked(10.0, &[perfect(15), AccuracyStats::default()]);
+        assert_eq!(learn.levels(), [2, 0]);
+    }
+
+    #[test]
+    fn seeking_out_of_a_section_abandons_the_pass() {
+        let mut learn = LearnMode::new(&looped_tab(), &[]);
+        learn.update(0.5, &[AccuracyStats::default(); 2]);
+        learn.seeked(5.0, &[perfect(8), AccuracyStats::default()]);
+        learn.update(30.0, &[perfect(8), AccuracyStats::default()]);
+        assert_eq!(learn.levels(), [0, 0]);
+    }
+}
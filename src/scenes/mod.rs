@@ -3,12 +3,21 @@ use bevy::prelude::*;
 pub mod song_selection;
 
 pub use song_selection::{
-    check_song_assets_ready, cleanup_song_preview, handle_close_preview_input, setup_song_preview,
-    setup_song_select, transition_preview_to_gameplay,
+    cleanup_song_preview, handle_close_preview_input, open_pending_song_preview,
+    persist_song_difficulty, refresh_song_list, setup_song_preview, setup_song_select,
+    transition_preview_to_gameplay, watch_song_library,
 };
 
 pub mod gameplay;
 
+pub mod learn;
+
+pub mod pause;
+
+pub mod practice;
+
+pub mod results;
+
 use crate::widgets::UiLayer;
 
 #[derive(Resource, Debug, Clone)]
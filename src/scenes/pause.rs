@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::scenes::gameplay::SongPlayback;
+use crate::scenes::MainCamera;
+use crate::states::{AppState, GameState};
+use crate::widgets::{ButtonStyle, ButtonType, GenericButton, UiBorder, UiContext, UiLayer};
+
+const COUNT_IN_SECONDS: f32 = 3.0;
+
+#[derive(Component)]
+pub struct PauseMenu;
+
+#[derive(Component)]
+pub struct PauseMenuButtons;
+
+#[derive(Component)]
+pub struct CountInLabel;
+
+#[derive(Resource)]
+pub struct ResumeCountIn {
+    remaining: f32,
+}
+
+pub fn handle_pause_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Pause);
+    }
+}
+
+pub fn pause_gameplay(mut song_clock: ResMut<SongPlayback>) {
+    song_clock.pause();
+}
+
+pub fn setup_pause_menu(mut commands: Commands, main_camera: Res<MainCamera>, ctx: UiContext) {
+    let theme = ctx
+        .themes
+        .get(&ctx.settings.start_theme)
+        .expect("Theme not found");
+
+    let button_style = ButtonStyle {
+        stretch: true,
+        color: theme.primary,
+        hover_color: theme.primary.lighter(0.2),
+        press_color: theme.primary.darker(0.2),
+        label_color: theme.text_third,
+        font_size: 28.0,
+        padding: UiRect::all(Val::Px(8.0)),
+        margin: UiRect::vertical(Val::Px(6.0)),
+        border: Some(UiBorder {
+            size: UiRect::all(Val::Px(0.0)),
+            color: Color::BLACK,
+            radius: BorderRadius::all(Val::Px(10.0)),
+        }),
+        ..default()
+    };
+
+    commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .insert(PauseMenu)
+        .insert(UiTargetCamera(main_camera.ui_camera))
+        .insert(BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)))
+        .insert(ZIndex(UiLayer::Menus.base_z()))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Paused"),
+                TextColor(theme.text_primary),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new(""),
+                TextColor(theme.text_primary),
+                TextFont {
+                    font_size: 96.0,
+                    ..default()
+                },
+                Visibility::Hidden,
+                CountInLabel,
+            ));
+
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(320.0),
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(16.0)),
+                        margin: UiRect::top(Val::Px(16.0)),
+                        ..default()
+                    },
+                    BackgroundColor(theme.background_paper),
+                    BorderRadius::all(Val::Px(10.0)),
+                    PauseMenuButtons,
+                ))
+                .with_children(|buttons| {
+                    let resume_btn =
+                        GenericButton::builder(ButtonType::Labeled(String::from("Resume")))
+                            .style(button_style.clone())
+                            .spawn(buttons, &ctx);
+                    buttons.commands().entity(resume_btn).observe(
+                        |_: On<Pointer<Click>>, mut commands: Commands| {
+                            commands.insert_resource(ResumeCountIn {
+                                remaining: COUNT_IN_SECONDS,
+                            });
+                        },
+                    );
+
+                    let restart_btn =
+                        GenericButton::builder(ButtonType::Labeled(String::from("Restart")))
+                            .style(button_style.clone())
+                            .spawn(buttons, &ctx);
+                    buttons.commands().entity(restart_btn).observe(
+                        |_: On<Pointer<Click>>,
+                         mut song_clock: ResMut<SongPlayback>,
+                         mut next_state: ResMut<NextState<GameState>>| {
+                            song_clock.reset();
+                            next_state.set(GameState::Loading);
+                        },
+                    );
+
+                    let quit_btn = GenericButton::builder(ButtonType::Labeled(String::from(
+                        "Quit to song select",
+                    )))
+                    .style(button_style.clone())
+                    .spawn(buttons, &ctx);
+                    buttons.commands().entity(quit_btn).observe(
+                        |_: On<Pointer<Click>>, mut next_state: ResMut<NextState<AppState>>| {
+                            next_state.set(AppState::SongSelect);
+                        },
+                    );
+                });
+        });
+}
+
+pub fn handle_pause_menu_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    count_in: Option<Res<ResumeCountIn>>,
+    mut commands: Commands,
+) {
+    if keys.just_pressed(KeyCode::Escape) && count_in.is_none() {
+        commands.insert_resource(ResumeCountIn {
+            remaining: COUNT_IN_SECONDS,
+        });
+    }
+}
+
+pub fn tick_resume_count_in(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut count_in: ResMut<ResumeCountIn>,
+    mut song_clock: ResMut<SongPlayback>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut label: Query<(&mut Text, &mut Visibility), With<CountInLabel>>,
+    mut buttons: Query<&mut Visibility, (With<PauseMenuButtons>, Without<CountInLabel>)>,
+) {
+    count_in.remaining -= time.delta_secs();
+
+    if count_in.remaining <= 0.0 {
+        commands.remove_resource::<ResumeCountIn>();
+        song_clock.resume();
+        next_state.set(GameState::InGame);
+        return;
+    }
+
+    for mut visibility in &mut buttons {
+        *visibility = Visibility::Hidden;
+    }
+    for (mut text, mut visibility) in &mut label {
+        *visibility = Visibility::Inherited;
+        text.0 = format!("{}", count_in.remaining.ceil() as i32);
+    }
+}
+
+pub fn cleanup_pause_menu(mut commands: Commands, menus: Query<Entity, With<PauseMenu>>) {
+    commands.remove_resource::<ResumeCountIn>();
+    for entity in &menus {
+        commands.entity(entity).despawn();
+    }
+}
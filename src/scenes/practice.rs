@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+
+use crate::components::StringTimelineFeed;
+use crate::scenes::gameplay::{start_game_session, track_timeline, SeekRequest, SongPlayback};
+use crate::scenes::MainCamera;
+use crate::scoring::GameplaySession;
+use crate::states::{AppState, GameState};
+
+const MIN_SPEED: f32 = 0.5;
+const MAX_SPEED: f32 = 1.0;
+const SPEED_STEP: f32 = 0.1;
+const MIN_LOOP_SECONDS: f32 = 0.5;
+const HUD_TOP_PX: f32 = 8.0;
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeMode>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                (
+                    apply_practice_speed.after(start_game_session),
+                    setup_practice_hud,
+                ),
+            )
+            .add_systems(OnEnter(GameState::Loading), cleanup_practice_hud)
+            .add_systems(
+                OnExit(AppState::Gameplay),
+                (cleanup_practice_hud, reset_practice_mode),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_practice_input,
+                    apply_practice_loop.after(track_timeline),
+                    update_practice_hud,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct PracticeHud;
+
+#[derive(Resource, Debug, Clone)]
+pub struct PracticeMode {
+    pub loop_start: Option<f32>,
+    pub loop_end: Option<f32>,
+    pub loop_name: Option<String>,
+    pub speed: f32,
+    pub preserve_pitch: bool,
+}
+
+impl Default for PracticeMode {
+    fn default() -> Self {
+        Self {
+            loop_start: None,
+            loop_end: None,
+            loop_name: None,
+            speed: MAX_SPEED,
+            preserve_pitch: true,
+        }
+    }
+}
+
+impl PracticeMode {
+    pub fn loop_range(&self) -> Option<(f32, f32)> {
+        let start = self.loop_start?;
+        let end = self.loop_end?;
+        (end - start >= MIN_LOOP_SECONDS).then_some((start, end))
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.loop_range().is_some() || self.speed < MAX_SPEED
+    }
+
+    pub fn clear_loop(&mut self) {
+        self.loop_start = None;
+        self.loop_end = None;
+        self.loop_name = None;
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        // Round to the step so repeated nudges don't accumulate float error.
+        let steps = (speed / SPEED_STEP).round();
+        self.speed = (steps * SPEED_STEP).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        match (self.loop_range(), self.loop_start) {
+            (Some((start, end)), _) => {
+                let name = self.loop_name.as_deref().unwrap_or("A/B");
+                parts.push(format!("Loop {name} {start:.1}s - {end:.1}s"));
+            }
+            (None, Some(start)) => parts.push(format!("Loop A {start:.1}s - set B with ]")),
+            (None, None) => {}
+        }
+        if self.speed < MAX_SPEED {
+            let pitch = if self.preserve_pitch {
+                "pitch kept"
+            } else {
+                "pitch follows"
+            };
+            parts.push(format!("Speed {:.0}% ({pitch})", self.speed * 100.0));
+        }
+        parts.join("   ")
+    }
+}
+
+pub fn handle_practice_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    timeline: Res<StringTimelineFeed>,
+    session: Res<GameplaySession>,
+    mut practice: ResMut<PracticeMode>,
+    mut song_clock: ResMut<SongPlayback>,
+) {
+    let current_time = timeline.current_time;
+
+    if keys.just_pressed(KeyCode::KeyL) {
+        if practice.loop_range().is_some() {
+            practice.clear_loop();
+        } else if let Some(section) = session.section_at(current_time) {
+            practice.loop_start = Some(section.start_time);
+            practice.loop_end = Some(section.end_time);
+            practice.loop_name = Some(section.name.clone());
+        }
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        practice.loop_start = Some(current_time);
+        practice.loop_end = None;
+        practice.loop_name = None;
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        if let Some(start) = practice.loop_start {
+            if current_time - start >= MIN_LOOP_SECONDS {
+                practice.loop_end = Some(current_time);
+                practice.loop_name = None;
+            }
+        }
+    }
+    if keys.just_pressed(KeyCode::Backspace) {
+        practice.clear_loop();
+    }
+
+    let speed = practice.speed;
+    if keys.just_pressed(KeyCode::Minus) {
+        practice.set_speed(speed - SPEED_STEP);
+    }
+    if keys.just_pressed(KeyCode::Equal) {
+        practice.set_speed(speed + SPEED_STEP);
+    }
+    if keys.just_pressed(KeyCode::KeyP) {
+        practice.preserve_pitch = !practice.preserve_pitch;
+    }
+    if practice.is_changed() {
+        song_clock.set_playback_rate(practice.speed, practice.preserve_pitch);
+    }
+}
+
+pub fn apply_practice_loop(
+    timeline: Res<StringTimelineFeed>,
+    practice: Res<PracticeMode>,
+    mut seeks: MessageWriter<SeekRequest>,
+) {
+    let Some((start, end)) = practice.loop_range() else {
+        return;
+    };
+    if timeline.current_time >= end {
+        seeks.write(SeekRequest { time: start });
+    }
+}
+
+pub fn apply_practice_speed(practice: Res<PracticeMode>, mut song_clock: ResMut<SongPlayback>) {
+    song_clock.set_playback_rate(practice.speed, practice.preserve_pitch);
+}
+
+pub fn reset_practice_mode(mut practice: ResMut<PracticeMode>) {
+    *practice = PracticeMode::default();
+}
+
+pub fn setup_practice_hud(mut commands: Commands, main_camera: Res<MainCamera>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(12.0),
+            top: Val::Px(HUD_TOP_PX),
+            ..default()
+        },
+        Text::new(""),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.95, 0.85, 0.4)),
+        UiTargetCamera(main_camera.ui_camera),
+        ZIndex(1),
+        PracticeHud,
+    ));
+}
+
+pub fn update_practice_hud(
+    practice: Res<PracticeMode>,
+    timeline: Res<StringTimelineFeed>,
+    mut hud: Query<(&mut Text, &mut Node), With<PracticeHud>>,
+) {
+    let summary = practice.summary();
+    // Stay below anything docked above the timeline, like the lyric ribbon.
+    let top = Val::Px(HUD_TOP_PX + timeline.header_height());
+    for (mut text, mut node) in &mut hud {
+        if text.0 != summary {
+            text.0 = summary.clone();
+        }
+        if node.top != top {
+            node.top = top;
+        }
+    }
+}
+
+pub fn cleanup_practice_hud(mut commands: Commands, hud: Query<Entity, With<PracticeHud>>) {
+    for entity in &hud {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+
+use crate::file::Song;
+use crate::scenes::song_selection::SongSelectState;
+use crate::scenes::MainCamera;
+use crate::scoring::{AccuracyStats, GameplaySession};
+use crate::states::AppState;
+use crate::widgets::{
+    ButtonStyle, ButtonType, GenericButton, ScrollContainer, ScrollContainerStyle, UiBorder,
+    UiContext, UiLayer,
+};
+
+#[derive(Component)]
+pub struct ResultsScreen;
+
+fn format_accuracy(stats: &AccuracyStats) -> String {
+    if stats.judged() == 0 {
+        return String::from("-");
+    }
+    format!("{:.1}%", stats.accuracy() * 100.0)
+}
+
+pub fn setup_results_screen(
+    mut commands: Commands,
+    session: Res<GameplaySession>,
+    selected_song: Res<SongSelectState>,
+    songs: Res<Assets<Song>>,
+    main_camera: Res<MainCamera>,
+    ctx: UiContext,
+) {
+    let theme = ctx
+        .themes
+        .get(&ctx.settings.start_theme)
+        .expect("Theme not found");
+
+    let song_title = selected_song
+        .selected_song
+        .as_ref()
+        .and_then(|handle| songs.get(handle))
+        .map(|song| format!("{} - {}", song.metadata.artist, song.metadata.title))
+        .unwrap_or_default();
+
+    let summary = [
+        (String::from("Score"), session.score.to_string()),
+        (String::from("Accuracy"), format_accuracy(&session.totals)),
+        (
+            String::from("Notes hit"),
+            format!("{} / {}", session.totals.hits(), session.totals.judged()),
+        ),
+        (
+            String::from("Longest streak"),
+            session.longest_streak.to_string(),
+        ),
+    ];
+
+    let button_style = ButtonStyle {
+        color: theme.primary,
+        hover_color: theme.primary.lighter(0.2),
+        press_color: theme.primary.darker(0.2),
+        label_color: theme.text_third,
+        font_size: 28.0,
+        padding: UiRect::axes(Val::Px(16.0), Val::Px(5.0)),
+        margin: UiRect::horizontal(Val::Px(8.0)),
+        border: Some(UiBorder {
+            size: UiRect::all(Val::Px(0.0)),
+            color: Color::BLACK,
+            radius: BorderRadius::all(Val::Px(10.0)),
+        }),
+        ..default()
+    };
+
+    commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .insert(ResultsScreen)
+        .insert(UiTargetCamera(main_camera.ui_camera))
+        .insert(BackgroundColor(theme.background_default))
+        .insert(ZIndex(UiLayer::Menus.base_z()))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Results"),
+                TextColor(theme.text_primary),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Text::new(song_title),
+                TextColor(theme.text_secondary),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(Node {
+                    width: Val::Percent(60.0),
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    margin: UiRect::vertical(Val::Px(16.0)),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (label, value) in summary {
+                        row.spawn(Node {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|cell| {
+                            cell.spawn((
+                                Text::new(value),
+                                TextColor(theme.text_primary),
+                                TextFont {
+                                    font_size: 32.0,
+                                    ..default()
+                                },
+                            ));
+                            cell.spawn((
+                                Text::new(label),
+                                TextColor(theme.text_secondary),
+                                TextFont {
+                                    font_size: 14.0,
+                                    ..default()
+                                },
+                            ));
+                        });
+                    }
+                });
+
+            parent
+                .spawn(Node {
+                    width: Val::Percent(60.0),
+                    height: Val::Percent(50.0),
+                    ..default()
+                })
+                .with_children(|sections| {
+                    ScrollContainer::builder()
+                        .style(ScrollContainerStyle {
+                            background_color: theme.background_paper,
+                            scrollbar_color: theme.primary,
+                            scrollbar_width: 6.0,
+                            padding: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        })
+                        .build()
+                        .spawn(sections, &ctx, |container| {
+                            for section in &session.sections {
+                                container
+                                    .spawn(Node {
+                                        width: Val::Percent(100.0),
+                                        flex_direction: FlexDirection::Row,
+                                        justify_content: JustifyContent::SpaceBetween,
+                                        padding: UiRect::vertical(Val::Px(4.0)),
+                                        ..default()
+                                    })
+                                    .with_children(|row| {
+                                        row.spawn((
+                                            Text::new(section.name.clone()),
+                                            TextColor(theme.text_primary),
+                                            TextFont {
+                                                font_size: 18.0,
+                                                ..default()
+                                            },
+                                        ));
+                                        row.spawn((
+                                            Text::new(format!(
+                                                "{} / {}   {}",
+                                                section.stats.hits(),
+                                                section.stats.judged(),
+                                                format_accuracy(&section.stats)
+                                            )),
+                                            TextColor(theme.text_secondary),
+                                            TextFont {
+                                                font_size: 18.0,
+                                                ..default()
+                                            },
+                                        ));
+                                    });
+                            }
+                        });
+                });
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    margin: UiRect::top(Val::Px(16.0)),
+                    ..default()
+                })
+                .with_children(|buttons| {
+                    let retry_btn =
+                        GenericButton::builder(ButtonType::Labeled(String::from("Retry")))
+                            .style(button_style.clone())
+                            .spawn(buttons, &ctx);
+                    buttons.commands().entity(retry_btn).observe(
+                        |_: On<Pointer<Click>>, mut next_state: ResMut<NextState<AppState>>| {
+                            next_state.set(AppState::Gameplay);
+                        },
+                    );
+
+                    let back_btn =
+                        GenericButton::builder(ButtonType::Labeled(String::from("Back")))
+                            .style(button_style.clone())
+                            .spawn(buttons, &ctx);
+                    buttons.commands().entity(back_btn).observe(
+                        |_: On<Pointer<Click>>, mut next_state: ResMut<NextState<AppState>>| {
+                            next_state.set(AppState::SongSelect);
+                        },
+                    );
+                });
+        });
+}
+
+pub fn cleanup_results_screen(mut commands: Commands, screens: Query<Entity, With<ResultsScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,11 +1,19 @@
 use bevy::prelude::*;
 
-use std::path::Path;
+use bevy::asset::LoadState;
 
+use bevy::asset::AssetPath;
+
+use crate::file::library::{
+    load_library_index, LibraryIndex, LibraryRefresh, LibraryRoots, LibraryWatcher,
+};
+use crate::file::settings::save_settings;
+use crate::file::{AppConfig, Settings};
 use crate::states::AppState;
 use crate::widgets::{
     ButtonStyle, ButtonType, Card, CardStyle, GenericButton, ScrollContainer, ScrollContainerStyle,
-    Selectable, SelectableButton, SelectableStyle, SelectableType, UiBorder, UiContext, UiLayer,
+    Selectable, SelectableButton, SelectableStyle, SelectableType, Slider, SliderChangedEvent,
+    SliderStyle, UiBorder, UiContext, UiLayer,
 };
 use crate::{file::Song, widgets::SelectedEvent};
 
@@ -13,20 +21,37 @@ use crate::shaders::BlurMaterial;
 
 use crate::scenes::MainCamera;
 
+pub const DEFAULT_DIFFICULTY_PERCENT: f32 = 100.0;
+const DIFFICULTY_STEP_PERCENT: f32 = 5.0;
+
+/// A song picked from the list whose metadata is still loading; the preview opens once
+/// it has.
 #[derive(Resource)]
-pub struct SongHandles {
-    handles: Vec<Handle<Song>>,
+pub struct PendingSongPreview {
+    handle: Handle<Song>,
 }
 
 #[derive(Resource)]
 pub struct SongSelectState {
     pub selected_song: Option<Handle<Song>>,
+    /// Key of the chosen arrangement in the song metadata.
     pub selected_instrument: Option<String>,
+    pub difficulty_percent: f32,
+    pub learn_mode: bool,
+}
+
+impl SongSelectState {
+    // Songs are identified by their folder so per-song settings survive metadata edits.
+    // Songs outside the asset folder keep their source in the key.
+    pub fn song_key(&self) -> Option<String> {
+        let path = self.selected_song.as_ref()?.path()?;
+        Some(path.parent()?.to_string())
+    }
 }
 
 #[derive(Component)]
-pub struct SongHandle {
-    handle: Handle<Song>,
+pub struct LibrarySong {
+    metadata_path: AssetPath<'static>,
 }
 
 #[derive(Component)]
@@ -35,54 +60,76 @@ pub struct SongPreview;
 #[derive(Component)]
 pub struct SongList;
 
-pub fn setup_song_select(mut commands: Commands, ctx: UiContext) {
-    let root_dir = Path::new(&ctx.config.paths.song_directory);
-    let song_handles: Vec<Handle<Song>> = Song::get_all_songs(root_dir)
-        .into_iter()
-        .map(|path| ctx.asset_server.load(path))
-        .collect();
+pub fn setup_song_select(
+    mut commands: Commands,
+    ctx: UiContext,
+    roots: Res<LibraryRoots>,
+    main_camera: Res<MainCamera>,
+) {
+    let library = load_library_index(&ctx.config);
+    build_song_ui(&mut commands, &ctx, &library, &main_camera);
+    commands.insert_resource(LibraryRefresh::start(library.clone(), &ctx.config, &roots));
+    commands.insert_resource(library);
+}
 
-    commands.insert_resource(SongHandles {
-        handles: song_handles,
-    });
+// Changes seen while a refresh is running are picked up once it is done.
+pub fn watch_song_library(
+    mut commands: Commands,
+    watcher: Res<LibraryWatcher>,
+    library: Res<LibraryIndex>,
+    refresh: Option<Res<LibraryRefresh>>,
+    roots: Res<LibraryRoots>,
+    ctx: UiContext,
+) {
+    if refresh.is_none() && watcher.take_changed() {
+        commands.insert_resource(LibraryRefresh::start(library.clone(), &ctx.config, &roots));
+    }
 }
 
-pub fn check_song_assets_ready(
-    asset_server: Res<AssetServer>,
-    song_handles: Option<Res<SongHandles>>,
-    songs: Res<Assets<Song>>,
+pub fn refresh_song_list(
     mut commands: Commands,
+    mut refresh: ResMut<LibraryRefresh>,
+    song_lists: Query<Entity, With<SongList>>,
     ctx: UiContext,
     main_camera: Res<MainCamera>,
 ) {
-    let song_handles = match song_handles {
-        Some(handles) => handles,
-        None => {
-            return;
-        }
+    let Some(refreshed) = refresh.poll() else {
+        return;
+    };
+    commands.remove_resource::<LibraryRefresh>();
+    let Some(library) = refreshed else {
+        return;
     };
-    let all_loaded = song_handles
-        .handles
-        .iter()
-        .all(|handle| asset_server.is_loaded_with_dependencies(handle));
-
-    if all_loaded {
-        build_song_ui(
-            &mut commands,
-            &ctx,
-            &song_handles.handles,
-            &songs,
-            &main_camera,
-        );
-        commands.remove_resource::<SongHandles>();
+    for entity in &song_lists {
+        commands.entity(entity).despawn();
+    }
+    build_song_ui(&mut commands, &ctx, &library, &main_camera);
+    commands.insert_resource(library);
+}
+
+pub fn open_pending_song_preview(
+    mut commands: Commands,
+    pending: Res<PendingSongPreview>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    match asset_server.load_state(&pending.handle) {
+        LoadState::Loaded => {
+            commands.remove_resource::<PendingSongPreview>();
+            next_state.set(AppState::SongPreview);
+        }
+        LoadState::Failed(err) => {
+            error!("Failed to load song: {err}");
+            commands.remove_resource::<PendingSongPreview>();
+        }
+        _ => {}
     }
 }
 
 fn build_song_ui(
     commands: &mut Commands,
     ctx: &UiContext,
-    song_handles: &[Handle<Song>],
-    songs: &Res<Assets<Song>>,
+    library: &LibraryIndex,
     main_camera: &Res<MainCamera>,
 ) {
     let theme = ctx
@@ -117,63 +164,63 @@ fn build_song_ui(
                 })
                 .build()
                 .spawn(parent, ctx, |container| {
-                    for handle in song_handles {
-                        if let Some(song) = songs.get(handle) {
-                            let texture_handle = song.album_art.clone();
-                            let card_entity =
-                                Card::builder(&song.metadata.title, &song.metadata.artist)
-                                    .image(texture_handle)
-                                    .style(CardStyle {
-                                        background_color: theme.background_paper,
-                                        text_color: theme.text_secondary,
-                                        ..default()
-                                    })
-                                    .spawn(container, ctx, |_parent| {});
-
-                            container
-                                .commands()
-                                .entity(card_entity)
-                                .insert(SongHandle {
-                                    handle: handle.clone(),
+                    for entry in library.songs() {
+                        // Art is only requested here, so cards show up straight away and
+                        // fill in their image once it has loaded.
+                        let texture_handle: Handle<Image> =
+                            ctx.asset_server.load(entry.asset_path("album_art.png"));
+                        let card_entity =
+                            Card::builder(&entry.metadata.title, &entry.metadata.artist)
+                                .image(texture_handle)
+                                .style(CardStyle {
+                                    background_color: theme.background_paper,
+                                    text_color: theme.text_secondary,
+                                    ..default()
                                 })
-                                .observe(
-                                    |trigger: On<Pointer<Over>>,
-                                     mut cmds: Commands,
-                                     ctx: UiContext| {
-                                        let e = trigger.entity;
-                                        let theme =
-                                            ctx.themes.get(&ctx.settings.start_theme).unwrap();
-                                        cmds.entity(e).insert(BoxShadow::new(
-                                            theme.primary.with_alpha(0.5),
-                                            Val::Percent(0.0),
-                                            Val::Percent(0.0),
-                                            Val::Percent(0.0),
-                                            Val::Px(4.0),
-                                        ));
-                                    },
-                                )
-                                .observe(|trigger: On<Pointer<Out>>, mut cmds: Commands| {
+                                .spawn(container, ctx, |_parent| {});
+
+                        container
+                            .commands()
+                            .entity(card_entity)
+                            .insert(LibrarySong {
+                                metadata_path: entry.metadata_path.clone(),
+                            })
+                            .observe(
+                                |trigger: On<Pointer<Over>>, mut cmds: Commands, ctx: UiContext| {
                                     let e = trigger.entity;
-                                    cmds.entity(e).remove::<BoxShadow>();
-                                })
-                                .observe({
-                                    |
-                                        trigger: On<Pointer<Press>>,
-                                        mut cmds: Commands,
-                                        song_handle: Query<&SongHandle>,
-                                        mut next_state: ResMut<NextState<AppState>>
-                                    | {
-                                        let e = trigger.entity;
-                                        if let Ok(song_handle) = song_handle.get(e) {
-                                            cmds.insert_resource(SongSelectState {
-                                                selected_song: Some(song_handle.handle.clone()),
-                                                selected_instrument: None
-                                            });
-                                            next_state.set(AppState::SongPreview);
-                                        }
+                                    let theme = ctx.themes.get(&ctx.settings.start_theme).unwrap();
+                                    cmds.entity(e).insert(BoxShadow::new(
+                                        theme.primary.with_alpha(0.5),
+                                        Val::Percent(0.0),
+                                        Val::Percent(0.0),
+                                        Val::Percent(0.0),
+                                        Val::Px(4.0),
+                                    ));
+                                },
+                            )
+                            .observe(|trigger: On<Pointer<Out>>, mut cmds: Commands| {
+                                let e = trigger.entity;
+                                cmds.entity(e).remove::<BoxShadow>();
+                            })
+                            .observe({
+                                |trigger: On<Pointer<Press>>,
+                                 mut cmds: Commands,
+                                 library_song: Query<&LibrarySong>,
+                                 asset_server: Res<AssetServer>| {
+                                    let e = trigger.entity;
+                                    if let Ok(library_song) = library_song.get(e) {
+                                        let handle: Handle<Song> =
+                                            asset_server.load(library_song.metadata_path.clone());
+                                        cmds.insert_resource(SongSelectState {
+                                            selected_song: Some(handle.clone()),
+                                            selected_instrument: None,
+                                            difficulty_percent: DEFAULT_DIFFICULTY_PERCENT,
+                                            learn_mode: false,
+                                        });
+                                        cmds.insert_resource(PendingSongPreview { handle });
                                     }
-                                });
-                        }
+                                }
+                            });
                     }
                 });
         });
@@ -185,7 +232,7 @@ pub fn setup_song_preview(
     _asset_server: Res<AssetServer>,
     songs: Res<Assets<Song>>,
     mut next_state: ResMut<NextState<AppState>>,
-    selected_song: Res<SongSelectState>,
+    mut selected_song: ResMut<SongSelectState>,
     _blur_materials: ResMut<Assets<BlurMaterial>>,
     main_camera: Res<MainCamera>,
     ctx: UiContext,
@@ -199,6 +246,12 @@ pub fn setup_song_preview(
         }
     };
 
+    let difficulty_percent = selected_song
+        .song_key()
+        .and_then(|key| ctx.settings.song_difficulty.get(&key).copied())
+        .unwrap_or(DEFAULT_DIFFICULTY_PERCENT);
+    selected_song.difficulty_percent = difficulty_percent;
+
     let theme = ctx
         .themes
         .get(&ctx.settings.start_theme)
@@ -333,38 +386,118 @@ pub fn setup_song_preview(
                                             });
                                         }
 
-                                        let instrument_selection_ent = Selectable::builder(
-                                            SelectableType::Radio,
-                                            &selectable_buttons,
-                                            &vec![0],
-                                        )
-                                        .style(SelectableStyle {
-                                            border: UiBorder {
-                                                size: UiRect::all(Val::Px(1.0)),
-                                                color: theme.third_light,
-                                                radius: BorderRadius::all(Val::Px(10.0)),
-                                            },
-                                            button_style: selectable_button_style,
-                                            width: Val::Percent(100.0),
-                                            ..default()
-                                        })
-                                        .spawn(details, &ctx);
-
-                                        details.commands().entity(instrument_selection_ent).observe(
-                                            |trigger: On<SelectedEvent>,
-                                             mut selected_song: ResMut<SongSelectState>| {
-                                            if trigger.event().selected {
-                                                let instrument_id = trigger.event().id.clone();
-                                                let file_name: String = instrument_id
-                                                    .chars()
-                                                    .filter(|c| !c.is_whitespace())
-                                                    .flat_map(|c| c.to_lowercase())
-                                                    .collect();
-
-                                                selected_song.selected_instrument = Some(file_name);
-
-                                            }
-                                        });
+                                        details
+                                            .spawn(Node {
+                                                width: Val::Percent(100.0),
+                                                flex_direction: FlexDirection::Row,
+                                                align_items: AlignItems::Center,
+                                                column_gap: Val::Px(16.0),
+                                                ..default()
+                                            })
+                                            .with_children(|options| {
+                                                let instrument_selection_ent = Selectable::builder(
+                                                    SelectableType::Radio,
+                                                    &selectable_buttons,
+                                                    &vec![0],
+                                                )
+                                                .style(SelectableStyle {
+                                                    border: UiBorder {
+                                                        size: UiRect::all(Val::Px(1.0)),
+                                                        color: theme.third_light,
+                                                        radius: BorderRadius::all(Val::Px(10.0)),
+                                                    },
+                                                    button_style: selectable_button_style,
+                                                    width: Val::Percent(60.0),
+                                                    ..default()
+                                                })
+                                                .spawn(options, &ctx);
+
+                                                options.commands().entity(instrument_selection_ent).observe(
+                                                    |trigger: On<SelectedEvent>,
+                                                     mut selected_song: ResMut<SongSelectState>| {
+                                                    if trigger.event().selected {
+                                                        selected_song.selected_instrument =
+                                                            Some(trigger.event().id.clone());
+
+                                                    }
+                                                });
+
+                                                options
+                                                    .spawn(Node {
+                                                        width: Val::Percent(40.0),
+                                                        flex_direction: FlexDirection::Column,
+                                                        ..default()
+                                                    })
+                                                    .with_children(|difficulty| {
+                                                        difficulty.spawn((
+                                                            Text::new("Difficulty"),
+                                                            TextColor(theme.text_secondary),
+                                                            TextFont {
+                                                                font_size: 14.0,
+                                                                ..default()
+                                                            },
+                                                        ));
+
+                                                        let difficulty_slider = Slider::builder(
+                                                            0.0,
+                                                            100.0,
+                                                            difficulty_percent,
+                                                        )
+                                                        .step(DIFFICULTY_STEP_PERCENT)
+                                                        .label_suffix("%")
+                                                        .style(SliderStyle {
+                                                            track_color: theme.third_light.darker(0.1),
+                                                            fill_color: theme.primary,
+                                                            handle_color: theme.primary.lighter(0.2),
+                                                            label_color: theme.text_secondary,
+                                                            ..default()
+                                                        })
+                                                        .spawn(difficulty, &ctx);
+
+                                                        difficulty.commands().entity(difficulty_slider).observe(
+                                                            |trigger: On<SliderChangedEvent>,
+                                                             mut selected_song: ResMut<SongSelectState>| {
+                                                                selected_song.difficulty_percent =
+                                                                    trigger.event().value;
+                                                            },
+                                                        );
+
+                                                        let learn_buttons = vec![SelectableButton {
+                                                            button_type: ButtonType::Labeled(String::from("Learn mode")),
+                                                            id: String::from("learn"),
+                                                        }];
+                                                        let learn_toggle = Selectable::builder(
+                                                            SelectableType::Checkbox,
+                                                            &learn_buttons,
+                                                            &vec![],
+                                                        )
+                                                        .style(SelectableStyle {
+                                                            border: UiBorder {
+                                                                size: UiRect::all(Val::Px(1.0)),
+                                                                color: theme.third_light,
+                                                                radius: BorderRadius::all(Val::Px(10.0)),
+                                                            },
+                                                            button_style: ButtonStyle {
+                                                                color: theme.third_light.darker(0.1),
+                                                                hover_color: theme.third_light.lighter(0.1),
+                                                                press_color: theme.third_light.darker(0.2),
+                                                                label_color: theme.text_secondary,
+                                                                font_size: 14.0,
+                                                                ..default()
+                                                            },
+                                                            width: Val::Percent(100.0),
+                                                            ..default()
+                                                        })
+                                                        .spawn(difficulty, &ctx);
+
+                                                        difficulty.commands().entity(learn_toggle).observe(
+                                                            |trigger: On<SelectedEvent>,
+                                                             mut selected_song: ResMut<SongSelectState>| {
+                                                                selected_song.learn_mode = trigger.event().selected;
+                                                            },
+                                                        );
+                                                    });
+                                            });
 
                                         details
                                             .spawn(Node {
@@ -431,3 +564,20 @@ pub fn transition_preview_to_gameplay(
         commands.entity(entity).despawn();
     }
 }
+
+pub fn persist_song_difficulty(
+    selected_song: Res<SongSelectState>,
+    mut settings: ResMut<Settings>,
+    config: Res<AppConfig>,
+) {
+    let Some(key) = selected_song.song_key() else {
+        return;
+    };
+    if settings.song_difficulty.get(&key) == Some(&selected_song.difficulty_percent) {
+        return;
+    }
+    settings
+        .song_difficulty
+        .insert(key, selected_song.difficulty_percent);
+    save_settings(&settings, &config);
+}
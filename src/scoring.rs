@@ -0,0 +1,529 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::file::song::{TabSection, Techniques};
+use crate::file::Tab;
+use crate::input::{NoteHitEvent, NoteMissEvent};
+use crate::scenes::gameplay::GameplayAssets;
+use crate::scenes::practice::PracticeMode;
+use crate::states::GameState;
+
+const PERFECT_WINDOW_SECONDS: f32 = 0.04;
+const GOOD_WINDOW_SECONDS: f32 = 0.08;
+const NOTES_PER_MULTIPLIER_STEP: u32 = 10;
+const MAX_MULTIPLIER: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Judgement {
+    Perfect,
+    Good,
+    Early,
+    Late,
+    Miss,
+}
+
+impl Judgement {
+    pub fn from_offset(offset: Option<f32>) -> Self {
+        match offset {
+            None => Judgement::Miss,
+            Some(offset) if offset.abs() <= PERFECT_WINDOW_SECONDS => Judgement::Perfect,
+            Some(offset) if offset.abs() <= GOOD_WINDOW_SECONDS => Judgement::Good,
+            Some(offset) if offset < 0.0 => Judgement::Early,
+            Some(_) => Judgement::Late,
+        }
+    }
+
+    pub fn is_hit(self) -> bool {
+        self != Judgement::Miss
+    }
+
+    pub fn points(self) -> u64 {
+        match self {
+            Judgement::Perfect => 100,
+            Judgement::Good => 70,
+            Judgement::Early | Judgement::Late => 40,
+            Judgement::Miss => 0,
+        }
+    }
+
+    fn accuracy_weight(self) -> f32 {
+        match self {
+            Judgement::Perfect => 1.0,
+            Judgement::Good => 0.75,
+            Judgement::Early | Judgement::Late => 0.5,
+            Judgement::Miss => 0.0,
+        }
+    }
+}
+
+// Anything that can judge a chart note (microphone matcher, MIDI input, replays, ...)
+// feeds the session through this trait.
+pub trait HitEvent {
+    fn note_time(&self) -> f32;
+    fn played_time(&self) -> Option<f32>;
+    fn techniques(&self) -> &[Techniques];
+
+    fn timing_offset(&self) -> Option<f32> {
+        self.played_time().map(|played| played - self.note_time())
+    }
+}
+
+impl HitEvent for NoteHitEvent {
+    fn note_time(&self) -> f32 {
+        self.note.time
+    }
+
+    fn played_time(&self) -> Option<f32> {
+        Some(self.played_time)
+    }
+
+    fn techniques(&self) -> &[Techniques] {
+        &self.note.techniques
+    }
+}
+
+impl HitEvent for NoteMissEvent {
+    fn note_time(&self) -> f32 {
+        self.note.time
+    }
+
+    fn played_time(&self) -> Option<f32> {
+        None
+    }
+
+    fn techniques(&self) -> &[Techniques] {
+        &self.note.techniques
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AccuracyStats {
+    pub perfect: u32,
+    pub good: u32,
+    pub early: u32,
+    pub late: u32,
+    pub miss: u32,
+}
+
+impl AccuracyStats {
+    pub fn record(&mut self, judgement: Judgement) {
+        match judgement {
+            Judgement::Perfect => self.perfect += 1,
+            Judgement::Good => self.good += 1,
+            Judgement::Early => self.early += 1,
+            Judgement::Late => self.late += 1,
+            Judgement::Miss => self.miss += 1,
+        }
+    }
+
+    pub fn since(&self, earlier: &AccuracyStats) -> AccuracyStats {
+        AccuracyStats {
+            perfect: self.perfect.saturating_sub(earlier.perfect),
+            good: self.good.saturating_sub(earlier.good),
+            early: self.early.saturating_sub(earlier.early),
+            late: self.late.saturating_sub(earlier.late),
+            miss: self.miss.saturating_sub(earlier.miss),
+        }
+    }
+
+    pub fn judged(&self) -> u32 {
+        self.hits() + self.miss
+    }
+
+    pub fn hits(&self) -> u32 {
+        self.perfect + self.good + self.early + self.late
+    }
+
+    pub fn hit_rate(&self) -> f32 {
+        match self.judged() {
+            0 => 0.0,
+            judged => self.hits() as f32 / judged as f32,
+        }
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        let judged = self.judged();
+        if judged == 0 {
+            return 0.0;
+        }
+        let weighted = self.perfect as f32 * Judgement::Perfect.accuracy_weight()
+            + self.good as f32 * Judgement::Good.accuracy_weight()
+            + self.early as f32 * Judgement::Early.accuracy_weight()
+            + self.late as f32 * Judgement::Late.accuracy_weight();
+        weighted / judged as f32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SectionStats {
+    pub name: String,
+    pub start_time: f32,
+    pub end_time: f32,
+    pub stats: AccuracyStats,
+}
+
+impl SectionStats {
+    pub fn contains(&self, time: f32) -> bool {
+        time >= self.start_time && time < self.end_time
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteJudgement {
+    pub note_time: f32,
+    pub offset: Option<f32>,
+    pub judgement: Judgement,
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct GameplaySession {
+    pub score: u64,
+    pub combo: u32,
+    pub longest_streak: u32,
+    pub totals: AccuracyStats,
+    pub sections: Vec<SectionStats>,
+    pub techniques: HashMap<Techniques, AccuracyStats>,
+    pub judgements: Vec<NoteJudgement>,
+}
+
+impl GameplaySession {
+    pub fn new(sections: &[TabSection]) -> Self {
+        let mut sections: Vec<SectionStats> = sections
+            .iter()
+            .map(|section| SectionStats {
+                name: section.name.clone(),
+                start_time: section.start_time,
+                end_time: section.end_time,
+                stats: AccuracyStats::default(),
+            })
+            .collect();
+        sections.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+        Self {
+            sections,
+            ..Default::default()
+        }
+    }
+
+    // A reloaded chart can move its sections; their stats are rebuilt from the judgements so far.
+    pub fn set_sections(&mut self, sections: &[TabSection]) {
+        self.sections = Self::new(sections).sections;
+        for index in 0..self.judgements.len() {
+            let NoteJudgement {
+                note_time,
+                judgement,
+                ..
+            } = self.judgements[index];
+            if let Some(section) = self.section_at_mut(note_time) {
+                section.stats.record(judgement);
+            }
+        }
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.combo / NOTES_PER_MULTIPLIER_STEP).min(MAX_MULTIPLIER)
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        self.totals.accuracy()
+    }
+
+    pub fn section_at(&self, time: f32) -> Option<&SectionStats> {
+        self.sections.iter().find(|section| section.contains(time))
+    }
+
+    fn section_at_mut(&mut self, time: f32) -> Option<&mut SectionStats> {
+        self.sections
+            .iter_mut()
+            .find(|section| section.contains(time))
+    }
+
+    // Only the section stats take the judgement, the run totals stay as they were.
+    pub fn record_section(&mut self, event: &(impl HitEvent + ?Sized)) -> Judgement {
+        let judgement = Judgement::from_offset(event.timing_offset());
+        if let Some(section) = self.section_at_mut(event.note_time()) {
+            section.stats.record(judgement);
+        }
+        judgement
+    }
+
+    pub fn record(&mut self, event: &(impl HitEvent + ?Sized)) -> Judgement {
+        let offset = event.timing_offset();
+        let judgement = Judgement::from_offset(offset);
+
+        if judgement.is_hit() {
+            self.combo += 1;
+            self.longest_streak = self.longest_streak.max(self.combo);
+            self.score += judgement.points() * self.multiplier() as u64;
+        } else {
+            self.combo = 0;
+        }
+
+        self.totals.record(judgement);
+        let note_time = event.note_time();
+        if let Some(section) = self.section_at_mut(note_time) {
+            section.stats.record(judgement);
+        }
+        for technique in event.techniques() {
+            self.techniques
+                .entry(*technique)
+                .or_default()
+                .record(judgement);
+        }
+        self.judgements.push(NoteJudgement {
+            note_time,
+            offset,
+            judgement,
+        });
+
+        judgement
+    }
+}
+
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameplaySession>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                start_gameplay_session,
+            )
+            .add_systems(
+                Update,
+                record_note_judgements.run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+pub fn start_gameplay_session(
+    assets: Res<GameplayAssets>,
+    tabs: Res<Assets<Tab>>,
+    mut session: ResMut<GameplaySession>,
+) {
+    *session = match tabs.get(assets.tab_handle()) {
+        Some(Tab::Strings(tab)) => GameplaySession::new(&tab.sections),
+        _ => GameplaySession::default(),
+    };
+}
+
+pub fn record_note_judgements(
+    mut session: ResMut<GameplaySession>,
+    practice: Res<PracticeMode>,
+    mut hits: MessageReader<NoteHitEvent>,
+    mut misses: MessageReader<NoteMissEvent>,
+) {
+    let looping = practice.loop_range().is_some();
+    let mut events: Vec<&dyn HitEvent> = hits
+        .read()
+        .map(|hit| hit as &dyn HitEvent)
+        .chain(misses.read().map(|miss| miss as &dyn HitEvent))
+        .collect();
+    events.sort_by(|a, b| a.note_time().total_cmp(&b.note_time()));
+    for event in events {
+        // A looped passage would count towards the run again on every pass, but learn mode
+        // still needs its section stats.
+        if looping {
+            session.record_section(event);
+        } else {
+            session.record(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Played {
+        time: f32,
+        offset: Option<f32>,
+        techniques: Vec<Techniques>,
+    }
+
+    impl HitEvent for Played {
+        fn note_time(&self) -> f32 {
+            self.time
+        }
+
+        fn played_time(&self) -> Option<f32> {
+            self.offset.map(|offset| self.time + offset)
+        }
+
+        fn techniques(&self) -> &[Techniques] {
+            &self.techniques
+        }
+    }
+
+    fn hit(time: f32, offset: f32) -> Played {
+        Played {
+            time,
+            offset: Some(offset),
+            techniques: Vec::new(),
+        }
+    }
+
+    fn miss(time: f32) -> Played {
+        Played {
+            time,
+            offset: None,
+            techniques: Vec::new(),
+        }
+    }
+
+    fn section(name: &str, start_time: f32, end_time: f32) -> TabSection {
+        TabSection {
+            name: name.to_string(),
+            start_time,
+            end_time,
+        }
+    }
+
+    #[test]
+    fn judgements_follow_the_timing_windows() {
+        assert_eq!(Judgement::from_offset(Some(0.0)), Judgement::Perfect);
+        assert_eq!(Judgement::from_offset(Some(-0.04)), Judgement::Perfect);
+        assert_eq!(Judgement::from_offset(Some(0.06)), Judgement::Good);
+        assert_eq!(Judgement::from_offset(Some(-0.12)), Judgement::Early);
+        assert_eq!(Judgement::from_offset(Some(0.12)), Judgement::Late);
+        assert_eq!(Judgement::from_offset(None), Judgement::Miss);
+    }
+
+    #[test]
+    fn multiplier_steps_every_ten_notes_up_to_the_cap() {
+        let mut session = GameplaySession::default();
+        let mut multipliers = Vec::new();
+        for index in 0..45 {
+            session.record(&hit(index as f32, 0.0));
+            multipliers.push(session.multiplier());
+        }
+
+        assert_eq!(multipliers[8], 1);
+        assert_eq!(multipliers[9], 2);
+        assert_eq!(multipliers[19], 3);
+        assert_eq!(multipliers[29], 4);
+        assert_eq!(multipliers[44], MAX_MULTIPLIER);
+        // Notes 1-9 at x1, 10-19 at x2, 20-29 at x3 and the remaining 16 at x4.
+        assert_eq!(session.score, 100 * (9 + 2 * 10 + 3 * 10 + 4 * 16));
+        assert_eq!(session.combo, 45);
+    }
+
+    #[test]
+    fn a_miss_resets_the_combo_but_keeps_the_streak() {
+        let mut session = GameplaySession::default();
+        for index in 0..12 {
+            session.record(&hit(index as f32, 0.0));
+        }
+        assert_eq!(session.multiplier(), 2);
+
+        assert_eq!(session.record(&miss(12.0)), Judgement::Miss);
+        assert_eq!(session.combo, 0);
+        assert_eq!(session.multiplier(), 1);
+        assert_eq!(session.longest_streak, 12);
+
+        let score = session.score;
+        session.record(&hit(13.0, 0.06));
+        assert_eq!(session.combo, 1);
+        assert_eq!(session.score, score + Judgement::Good.points());
+    }
+
+    #[test]
+    fn accuracy_is_weighted_by_judgement() {
+        let mut session = GameplaySession::default();
+        session.record(&hit(0.0, 0.0));
+        session.record(&hit(1.0, 0.06));
+        session.record(&hit(2.0, -0.12));
+        session.record(&miss(3.0));
+
+        assert_eq!(
+            session.totals,
+            AccuracyStats {
+                perfect: 1,
+                good: 1,
+                early: 1,
+                late: 0,
+                miss: 1,
+            }
+        );
+        assert!((session.accuracy() - (1.0 + 0.75 + 0.5) / 4.0).abs() < 1e-6);
+        assert!((session.totals.hit_rate() - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn judgements_are_split_by_section() {
+        let mut session =
+            GameplaySession::new(&[section("Chorus", 10.0, 20.0), section("Verse", 0.0, 10.0)]);
+        assert_eq!(session.sections[0].name, "Verse");
+
+        session.record(&hit(2.0, 0.0));
+        session.record(&miss(4.0));
+        session.record(&hit(12.0, 0.0));
+        session.record(&hit(25.0, 0.0));
+
+        let verse = &session.sections[0].stats;
+        assert_eq!((verse.perfect, verse.miss), (1, 1));
+        let chorus = &session.sections[1].stats;
+        assert_eq!((chorus.perfect, chorus.miss), (1, 0));
+        // Notes outside every section still count towards the totals.
+        assert_eq!(session.totals.judged(), 4);
+        assert_eq!(
+            session.section_at(12.0).map(|s| s.name.as_str()),
+            Some("Chorus")
+        );
+    }
+
+    #[test]
+    fn new_sections_are_scored_from_earlier_judgements() {
+        let mut session = GameplaySession::new(&[section("Verse", 0.0, 10.0)]);
+        session.record(&hit(2.0, 0.0));
+        session.record(&miss(6.0));
+        session.record(&hit(12.0, 0.0));
+
+        session.set_sections(&[section("Outro", 5.0, 15.0), section("Intro", 0.0, 5.0)]);
+        let names: Vec<&str> = session.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Intro", "Outro"]);
+        assert_eq!(session.sections[0].stats.judged(), 1);
+        let outro = &session.sections[1].stats;
+        assert_eq!((outro.perfect, outro.miss), (1, 1));
+        assert_eq!(session.totals.judged(), 3);
+    }
+
+    #[test]
+    fn section_only_judgements_leave_the_run_alone() {
+        let mut session = GameplaySession::new(&[section("Verse", 0.0, 10.0)]);
+        session.record(&hit(1.0, 0.0));
+        session.record_section(&hit(2.0, 0.0));
+        session.record_section(&miss(3.0));
+
+        let verse = &session.sections[0].stats;
+        assert_eq!((verse.perfect, verse.miss), (2, 1));
+        assert_eq!(session.totals.judged(), 1);
+        assert_eq!(session.combo, 1);
+        assert_eq!(session.score, Judgement::Perfect.points());
+        assert_eq!(session.judgements.len(), 1);
+    }
+
+    #[test]
+    fn judgements_are_split_by_technique() {
+        let mut session = GameplaySession::default();
+        session.record(&Played {
+            time: 0.0,
+            offset: Some(0.0),
+            techniques: vec![Techniques::Bend, Techniques::Vibrato],
+        });
+        session.record(&Played {
+            time: 1.0,
+            offset: None,
+            techniques: vec![Techniques::Bend],
+        });
+
+        let bend = session.techniques[&Techniques::Bend];
+        assert_eq!((bend.perfect, bend.miss), (1, 1));
+        assert_eq!(session.techniques[&Techniques::Vibrato].perfect, 1);
+        assert!(!session.techniques.contains_key(&Techniques::Slide));
+        assert_eq!(session.judgements.len(), 2);
+    }
+}
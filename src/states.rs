@@ -1,18 +1,33 @@
 use crate::audio::StreamingAudio;
-use crate::components::StringTimelinePlugin;
+use crate::components::{SectionNavigatorPlugin, StringTimelinePlugin, VocalTimelinePlugin};
+use crate::file::library::{watch_library_roots, LibraryIndex, LibraryRefresh, LibraryWatcher};
 use crate::file::settings::setup_settings;
+use crate::file::tab_cache::TAB_CACHE_DIRECTORY;
 use crate::file::theme::setup_theme;
-use crate::file::{Song, SongLoader, Tab, TabLoader};
+use crate::file::{AppConfig, Song, SongLoader, Tab, TabCache, TabLoader};
+use crate::input::InputCapturePlugin;
 use crate::scenes::gameplay::{
-    check_loading_progress, setup_loading_ui, start_game_session, start_loading_assets,
-    track_timeline, update_loading_ui, GameplayAssets, SongPlayback,
+    apply_seek_requests, check_loading_progress, cleanup_gameplay, detect_song_end,
+    reload_gameplay_tab, setup_loading_ui, start_game_session, start_loading_assets,
+    track_timeline, track_vocal_timeline, update_loading_ui, GameplayAssets, PlaybackSeeked,
+    SeekRequest, SongPlayback,
 };
+use crate::scenes::learn::{save_learn_progress, start_learn_mode, update_learn_mode, LearnMode};
+use crate::scenes::pause::{
+    cleanup_pause_menu, handle_pause_input, handle_pause_menu_input, pause_gameplay,
+    setup_pause_menu, tick_resume_count_in, ResumeCountIn,
+};
+use crate::scenes::practice::PracticePlugin;
+use crate::scenes::results::{cleanup_results_screen, setup_results_screen};
 use crate::scenes::{
-    check_song_assets_ready, cleanup_song_preview, handle_close_preview_input, setup_camera,
-    setup_song_preview, setup_song_select, song_selection::SongHandles,
-    transition_preview_to_gameplay,
+    cleanup_song_preview, handle_close_preview_input, open_pending_song_preview,
+    persist_song_difficulty, refresh_song_list, setup_camera, setup_song_preview,
+    setup_song_select, song_selection::PendingSongPreview, transition_preview_to_gameplay,
+    watch_song_library,
 };
+use crate::scoring::{record_note_judgements, ScoringPlugin};
 use bevy::prelude::*;
+use std::path::Path;
 
 #[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
 pub enum AppState {
@@ -22,6 +37,7 @@ pub enum AppState {
     SongSelect,
     SongPreview,
     Gameplay,
+    Results,
 }
 
 #[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
@@ -53,11 +69,17 @@ pub struct StartupPlugin;
 
 impl Plugin for StartupPlugin {
     fn build(&self, app: &mut App) {
+        let tab_loader = match app.world().get_resource::<AppConfig>() {
+            Some(config) => TabLoader::with_cache(TabCache::new(
+                Path::new(&config.saves.directory).join(TAB_CACHE_DIRECTORY),
+            )),
+            None => TabLoader::default(),
+        };
         app.insert_resource(StartupLatch::default())
             .init_asset::<Song>()
             .init_asset_loader::<SongLoader>()
             .init_asset::<Tab>()
-            .init_asset_loader::<TabLoader>()
+            .register_asset_loader(tab_loader)
             .add_systems(OnEnter(AppState::InitialLoad), setup_theme)
             .add_systems(OnEnter(AppState::InitialLoad), setup_settings)
             .add_systems(OnEnter(AppState::InitialLoad), setup_camera)
@@ -79,12 +101,37 @@ impl Plugin for SongSelectPlugin {
             },
             setup_song_select,
         )
+        .add_systems(
+            OnTransition {
+                exited: AppState::Gameplay,
+                entered: AppState::SongSelect,
+            },
+            setup_song_select,
+        )
+        .add_systems(
+            OnTransition {
+                exited: AppState::Results,
+                entered: AppState::SongSelect,
+            },
+            setup_song_select,
+        )
         .add_systems(
             Update,
-            check_song_assets_ready
-                .run_if(resource_exists::<SongHandles>)
-                .run_if(in_state(AppState::SongSelect))
-                .after(setup_song_select),
+            open_pending_song_preview
+                .run_if(resource_exists::<PendingSongPreview>)
+                .run_if(in_state(AppState::SongSelect)),
+        )
+        .add_systems(Startup, watch_library_roots)
+        .add_systems(
+            Update,
+            (
+                watch_song_library
+                    .run_if(resource_exists::<LibraryWatcher>)
+                    .run_if(resource_exists::<LibraryIndex>),
+                refresh_song_list.run_if(resource_exists::<LibraryRefresh>),
+            )
+                .chain()
+                .run_if(in_state(AppState::SongSelect)),
         )
         .add_systems(OnEnter(AppState::SongPreview), setup_song_preview)
         .add_systems(OnExit(AppState::SongPreview), cleanup_song_preview)
@@ -93,7 +140,7 @@ impl Plugin for SongSelectPlugin {
                 exited: AppState::SongPreview,
                 entered: AppState::Gameplay,
             },
-            transition_preview_to_gameplay,
+            (transition_preview_to_gameplay, persist_song_difficulty),
         )
         .add_systems(
             Update,
@@ -109,7 +156,15 @@ impl Plugin for GameplayPlugin {
         app.init_resource::<StreamingAudio>()
             .init_resource::<GameplayAssets>()
             .init_resource::<SongPlayback>()
+            .init_resource::<LearnMode>()
+            .add_message::<SeekRequest>()
+            .add_message::<PlaybackSeeked>()
             .add_plugins(StringTimelinePlugin)
+            .add_plugins(VocalTimelinePlugin)
+            .add_plugins(SectionNavigatorPlugin)
+            .add_plugins(InputCapturePlugin)
+            .add_plugins(ScoringPlugin)
+            .add_plugins(PracticePlugin)
             .add_systems(OnEnter(AppState::Gameplay), setup_loading_ui)
             .add_systems(OnEnter(AppState::Gameplay), start_loading_assets)
             .add_systems(
@@ -124,7 +179,68 @@ impl Plugin for GameplayPlugin {
                 },
                 update_loading_ui,
             )
-            .add_systems(OnEnter(GameState::InGame), start_game_session)
-            .add_systems(Update, track_timeline.run_if(in_state(GameState::InGame)));
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                (start_game_session, start_learn_mode),
+            )
+            .add_systems(
+                OnExit(AppState::Gameplay),
+                (save_learn_progress, cleanup_gameplay),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Pause,
+                    entered: GameState::Loading,
+                },
+                save_learn_progress,
+            )
+            .add_systems(
+                Update,
+                update_learn_mode
+                    .after(record_note_judgements)
+                    .after(apply_seek_requests)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (apply_seek_requests, track_timeline, track_vocal_timeline)
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                Update,
+                reload_gameplay_tab
+                    .before(track_timeline)
+                    .run_if(in_state(AppState::Gameplay)),
+            )
+            .add_systems(
+                Update,
+                detect_song_end
+                    .after(track_timeline)
+                    .run_if(in_state(GameState::InGame).and(in_state(AppState::Gameplay))),
+            )
+            .add_systems(
+                Update,
+                handle_pause_input
+                    .run_if(in_state(GameState::InGame).and(in_state(AppState::Gameplay))),
+            )
+            .add_systems(
+                OnEnter(GameState::Pause),
+                (pause_gameplay, setup_pause_menu),
+            )
+            .add_systems(OnExit(GameState::Pause), cleanup_pause_menu)
+            .add_systems(OnEnter(AppState::Results), setup_results_screen)
+            .add_systems(OnExit(AppState::Results), cleanup_results_screen)
+            .add_systems(
+                Update,
+                (
+                    handle_pause_menu_input,
+                    tick_resume_count_in.run_if(resource_exists::<ResumeCountIn>),
+                )
+                    .run_if(in_state(GameState::Pause)),
+            );
     }
 }
@@ -20,6 +20,9 @@ pub use scrollable_container::{ScrollContainer, ScrollContainerStyle, ScrollbarM
 pub mod ui_window;
 pub use ui_window::{UiWindow, UiWindowOptions, UiWindowStyle};
 
+pub mod slider;
+pub use slider::{Slider, SliderChangedEvent, SliderStyle};
+
 pub mod card_button;
 pub use card_button::{Card, CardStyle};
 
@@ -41,6 +44,7 @@ impl Plugin for UiLayerPlugin {
                     button::remove_active_listener,
                     selectable::active_change_listener,
                     selectable::active_removed_listener,
+                    slider::update_slider_visuals,
                 ),
             )
             .add_plugins(scrollable_container::ScrollContainerPlugin);
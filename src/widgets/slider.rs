@@ -0,0 +1,295 @@
+use crate::widgets::UiContext;
+use bevy::picking::prelude::*;
+use bevy::prelude::*;
+use bevy::ui::{ComputedNode, UiGlobalTransform};
+
+#[derive(EntityEvent)]
+pub struct SliderChangedEvent {
+    #[event_target]
+    pub slider: Entity,
+    pub value: f32,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Slider {
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+    pub label_suffix: String,
+    fill: Entity,
+    handle: Entity,
+    label: Entity,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+struct SliderTrack {
+    slider: Entity,
+}
+
+#[derive(Clone, Debug)]
+pub struct SliderStyle {
+    pub width: Val,
+    pub track_height: f32,
+    pub handle_size: f32,
+    pub track_color: Color,
+    pub fill_color: Color,
+    pub handle_color: Color,
+    pub label_color: Color,
+    pub font_size: f32,
+    pub label_width: f32,
+}
+
+impl Default for SliderStyle {
+    fn default() -> Self {
+        SliderStyle {
+            width: Val::Percent(100.0),
+            track_height: 6.0,
+            handle_size: 16.0,
+            track_color: Color::srgb(0.55, 0.55, 0.55),
+            fill_color: Color::srgb(0.75, 0.75, 0.75),
+            handle_color: Color::WHITE,
+            label_color: Color::BLACK,
+            font_size: 16.0,
+            label_width: 56.0,
+        }
+    }
+}
+
+pub struct SliderBuilder {
+    min: f32,
+    max: f32,
+    step: f32,
+    value: f32,
+    label_suffix: String,
+    style: SliderStyle,
+}
+
+impl SliderBuilder {
+    pub fn new(min: f32, max: f32, value: f32) -> Self {
+        SliderBuilder {
+            min,
+            max,
+            step: 0.0,
+            value,
+            label_suffix: String::new(),
+            style: SliderStyle::default(),
+        }
+    }
+
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn label_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.label_suffix = suffix.into();
+        self
+    }
+
+    pub fn style(mut self, style: SliderStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn spawn(&self, commands: &mut ChildSpawnerCommands, _ctx: &UiContext) -> Entity {
+        let radius = BorderRadius::all(Val::Px(self.style.track_height / 2.0));
+        let root = commands
+            .spawn(Node {
+                width: self.style.width,
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            })
+            .id();
+
+        let mut slider_commands = commands.commands();
+        let track = slider_commands
+            .spawn((
+                Node {
+                    flex_grow: 1.0,
+                    height: Val::Px(self.style.handle_size),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                SliderTrack { slider: root },
+            ))
+            .id();
+        let rail = slider_commands
+            .spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(self.style.track_height),
+                    ..default()
+                },
+                BackgroundColor(self.style.track_color),
+                radius,
+                Pickable::IGNORE,
+            ))
+            .id();
+        let fill = slider_commands
+            .spawn((
+                Node {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(self.style.fill_color),
+                radius,
+                Pickable::IGNORE,
+            ))
+            .id();
+        let handle = slider_commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(self.style.handle_size),
+                    height: Val::Px(self.style.handle_size),
+                    margin: UiRect::left(Val::Px(-self.style.handle_size / 2.0)),
+                    ..default()
+                },
+                BackgroundColor(self.style.handle_color),
+                BorderRadius::all(Val::Percent(50.0)),
+                Pickable::IGNORE,
+            ))
+            .id();
+        let label = slider_commands
+            .spawn((
+                Node {
+                    width: Val::Px(self.style.label_width),
+                    justify_content: JustifyContent::FlexEnd,
+                    ..default()
+                },
+                Text::new(""),
+                TextFont {
+                    font_size: self.style.font_size,
+                    ..default()
+                },
+                TextColor(self.style.label_color),
+            ))
+            .id();
+
+        slider_commands.entity(rail).add_child(fill);
+        slider_commands
+            .entity(track)
+            .add_child(rail)
+            .add_child(handle);
+        slider_commands
+            .entity(root)
+            .add_child(track)
+            .add_child(label)
+            .insert(Slider {
+                value: self.value,
+                min: self.min,
+                max: self.max,
+                step: self.step,
+                label_suffix: self.label_suffix.clone(),
+                fill,
+                handle,
+                label,
+            });
+
+        Slider::register_observers(track, &mut slider_commands);
+        root
+    }
+}
+
+impl Slider {
+    pub fn builder(min: f32, max: f32, value: f32) -> SliderBuilder {
+        SliderBuilder::new(min, max, value)
+    }
+
+    pub fn fraction(&self) -> f32 {
+        let range = self.max - self.min;
+        if range <= f32::EPSILON {
+            return 0.0;
+        }
+        ((self.value - self.min) / range).clamp(0.0, 1.0)
+    }
+
+    fn value_at(&self, fraction: f32) -> f32 {
+        let raw = self.min + (self.max - self.min) * fraction.clamp(0.0, 1.0);
+        let snapped = if self.step > 0.0 {
+            self.min + ((raw - self.min) / self.step).round() * self.step
+        } else {
+            raw
+        };
+        snapped.clamp(self.min, self.max)
+    }
+
+    fn register_observers(track: Entity, commands: &mut Commands) {
+        commands
+            .entity(track)
+            .observe(
+                |trigger: On<Pointer<Press>>,
+                 tracks: Query<(&SliderTrack, &ComputedNode, &UiGlobalTransform)>,
+                 sliders: Query<&mut Slider>,
+                 commands: Commands| {
+                    let position = trigger.event().pointer_location.position;
+                    set_from_pointer(trigger.entity, position, tracks, sliders, commands);
+                },
+            )
+            .observe(
+                |trigger: On<Pointer<Drag>>,
+                 tracks: Query<(&SliderTrack, &ComputedNode, &UiGlobalTransform)>,
+                 sliders: Query<&mut Slider>,
+                 commands: Commands| {
+                    let position = trigger.event().pointer_location.position;
+                    set_from_pointer(trigger.entity, position, tracks, sliders, commands);
+                },
+            );
+    }
+}
+
+fn set_from_pointer(
+    track: Entity,
+    pointer_position: Vec2,
+    tracks: Query<(&SliderTrack, &ComputedNode, &UiGlobalTransform)>,
+    mut sliders: Query<&mut Slider>,
+    mut commands: Commands,
+) {
+    let Ok((track, node, transform)) = tracks.get(track) else {
+        return;
+    };
+    let Ok(mut slider) = sliders.get_mut(track.slider) else {
+        return;
+    };
+    let size = node.size();
+    if size.x <= f32::EPSILON {
+        return;
+    }
+
+    // Pointer positions are logical pixels, node geometry is physical.
+    let physical = pointer_position / node.inverse_scale_factor();
+    let local = transform.inverse().transform_point2(physical);
+    let value = slider.value_at(local.x / size.x + 0.5);
+    if value == slider.value {
+        return;
+    }
+
+    slider.value = value;
+    commands.trigger(SliderChangedEvent {
+        slider: track.slider,
+        value,
+    });
+}
+
+pub fn update_slider_visuals(
+    sliders: Query<&Slider, Changed<Slider>>,
+    mut nodes: Query<&mut Node>,
+    mut texts: Query<&mut Text>,
+) {
+    for slider in &sliders {
+        let percent = slider.fraction() * 100.0;
+        if let Ok(mut node) = nodes.get_mut(slider.fill) {
+            node.width = Val::Percent(percent);
+        }
+        if let Ok(mut node) = nodes.get_mut(slider.handle) {
+            node.left = Val::Percent(percent);
+        }
+        if let Ok(mut text) = texts.get_mut(slider.label) {
+            text.0 = format!("{:.0}{}", slider.value, slider.label_suffix);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{note_at, notes, read_fixture, string_tab, TIME_TOLERANCE};
+use tabs_app::convert::{export_ascii_tab, import_ascii_tab, AsciiTabOptions, ImportedSong};
+use tabs_app::file::song::Techniques;
+
+#[test]
+fn imports_ascii_tab_with_hints() {
+    let song = import_ascii_tab(
+        &read_fixture("ascii_tab/riff.txt"),
+        &AsciiTabOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(song.metadata.title, "Fixture Riff");
+    assert_eq!(song.metadata.artist, "Test Band");
+    assert!((song.metadata.length - 6.0).abs() < TIME_TOLERANCE);
+
+    let arrangement = &song.metadata.arrangements["Lead"];
+    assert_eq!(
+        arrangement.string_semitone_offset,
+        Some(vec![-2, 0, 0, 0, 0, 0])
+    );
+
+    let tab = string_tab(&song);
+    let sections: Vec<(&str, f32, f32)> = tab
+        .sections
+        .iter()
+        .map(|section| (section.name.as_str(), section.start_time, section.end_time))
+        .collect();
+    assert_eq!(sections, [("Intro", 0.0, 4.0), ("Verse", 4.0, 6.0)]);
+
+    let notes = notes(&song);
+    assert_eq!(notes.len(), 13);
+    let at = |time: f32, string: i32| note_at(notes, time, string);
+
+    assert_eq!(at(0.0, 0).techniques, [Techniques::PalmMute]);
+    assert_eq!(at(0.25, 0).techniques, [Techniques::PalmMute]);
+    assert!(at(0.5, 1).techniques.is_empty());
+    assert_eq!(at(0.625, 1).techniques, [Techniques::HammerOn]);
+
+    let chord_note = at(1.0, 2);
+    assert_eq!(chord_note.techniques, [Techniques::Chord]);
+    assert_eq!(
+        tab.chords[chord_note.chord_index as usize].frets,
+        [5, 5, 5, -1, -1, -1]
+    );
+
+    let slide = at(2.0, 2);
+    assert_eq!((slide.fret, slide.slide_to), (7, 9));
+    assert!((slide.sustain - 0.5).abs() < TIME_TOLERANCE);
+    assert_eq!(at(2.5, 2).fret, 9);
+
+    let bend = at(3.0, 3);
+    assert_eq!((bend.max_bend, bend.vibrato), (2.0, 80));
+    assert_eq!(bend.techniques, [Techniques::Bend, Techniques::Vibrato]);
+    assert_eq!(at(3.75, 0).slide_unpitch_to, 0);
+
+    assert_eq!(at(4.0, 5).fret, 12);
+    let pull = at(4.25, 5);
+    assert_eq!(
+        (pull.fret, &pull.techniques[..]),
+        (10, &[Techniques::PullOff][..])
+    );
+
+    // An explicit tempo wins over the file's hint.
+    let options = AsciiTabOptions {
+        bpm: Some(60.0),
+        time_signature: None,
+    };
+    let slow = import_ascii_tab(&read_fixture("ascii_tab/riff.txt"), &options).unwrap();
+    assert!((slow.metadata.length - 12.0).abs() < TIME_TOLERANCE);
+}
+
+#[test]
+fn ascii_tab_round_trips() {
+    let song = import_ascii_tab(
+        &read_fixture("ascii_tab/riff.txt"),
+        &AsciiTabOptions::default(),
+    )
+    .unwrap();
+    let text = export_ascii_tab(&song).unwrap();
+    let again = import_ascii_tab(&text, &AsciiTabOptions::default()).unwrap();
+
+    assert_eq!(song.metadata.title, again.metadata.title);
+    assert_eq!(
+        song.metadata.arrangements["Lead"].string_semitone_offset,
+        again.metadata.arrangements["Lead"].string_semitone_offset
+    );
+    let names = |song: &ImportedSong| {
+        string_tab(song)
+            .sections
+            .iter()
+            .map(|section| (section.name.clone(), section.start_time))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&song), names(&again));
+
+    let (expected, actual) = (notes(&song), notes(&again));
+    assert_eq!(expected.len(), actual.len(), "note count");
+    for (want, got) in expected.iter().zip(actual) {
+        let context = format!("note at {:.3}s on string {}", want.time, want.string);
+        assert!((want.time - got.time).abs() < TIME_TOLERANCE, "{context}");
+        assert_eq!(
+            (want.string, want.fret),
+            (got.string, got.fret),
+            "{context}"
+        );
+        assert_eq!(want.techniques, got.techniques, "{context}");
+        assert_eq!(want.slide_to, got.slide_to, "{context}");
+        assert_eq!(want.slide_unpitch_to, got.slide_unpitch_to, "{context}");
+        assert_eq!(want.max_bend, got.max_bend, "{context}");
+        assert!(
+            (want.sustain - got.sustain).abs() < TIME_TOLERANCE,
+            "{context}"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+// Each test crate only uses some of these.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use tabs_app::convert::ImportedSong;
+use tabs_app::file::song::{StringTab, Tab, TabNote};
+
+pub const TIME_TOLERANCE: f32 = 0.002;
+
+pub fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+pub fn read_fixture(path: &str) -> String {
+    std::fs::read_to_string(fixture(path)).unwrap()
+}
+
+pub fn string_tab(song: &ImportedSong) -> &StringTab {
+    match &song.arrangements[0].tab {
+        Tab::Strings(tab) => tab,
+        Tab::Vocals(_) => panic!("expected a string arrangement"),
+    }
+}
+
+pub fn notes(song: &ImportedSong) -> &[TabNote] {
+    &string_tab(song).note_charts[0].notes
+}
+
+pub fn hardest_notes(tab: &StringTab) -> Vec<TabNote> {
+    let chart = tab
+        .note_charts
+        .iter()
+        .max_by_key(|chart| chart.difficulty)
+        .expect("tab has a chart");
+    let mut notes = chart.notes.clone();
+    notes.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.string.cmp(&b.string)));
+    notes
+}
+
+pub fn note_at(notes: &[TabNote], time: f32, string: i32) -> &TabNote {
+    notes
+        .iter()
+        .find(|note| (note.time - time).abs() < TIME_TOLERANCE && note.string == string)
+        .unwrap_or_else(|| panic!("no note at {time}s on string {string}"))
+}
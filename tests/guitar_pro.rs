@@ -0,0 +1,87 @@
+mod common;
+
+use common::{fixture, note_at, notes, string_tab, TIME_TOLERANCE};
+use tabs_app::convert::{import_guitar_pro, import_guitar_pro_file, ConvertError};
+use tabs_app::file::song::Techniques;
+
+// A Guitar Pro 3 header up to the measure and track counts, with empty song info.
+fn gp3_header(measure_count: i32, track_count: i32) -> Vec<u8> {
+    let version = b"FICHIER GUITAR PRO v3.00";
+    let mut bytes = vec![version.len() as u8];
+    bytes.extend(version);
+    bytes.resize(31, 0);
+    for _ in 0..8 {
+        bytes.extend(1i32.to_le_bytes());
+        bytes.push(0);
+    }
+    bytes.extend(0i32.to_le_bytes()); // notice lines
+    bytes.push(0); // triplet feel
+    bytes.extend(120i32.to_le_bytes());
+    bytes.extend(0i32.to_le_bytes()); // key signature
+    bytes.extend([0; 64 * 12]);
+    bytes.extend(measure_count.to_le_bytes());
+    bytes.extend(track_count.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn imports_a_guitar_pro_3_file() {
+    let song = import_guitar_pro_file(&fixture("guitar_pro/riff.gp3")).unwrap();
+    assert_eq!(song.metadata.title, "Fixture Riff");
+    assert_eq!(song.metadata.artist, "Test Band");
+    assert_eq!(song.metadata.album, "Fixture Album");
+    assert!((song.metadata.length - 6.0).abs() < TIME_TOLERANCE);
+
+    let arrangement = &song.metadata.arrangements["Lead Guitar"];
+    assert_eq!(arrangement.string_count, Some(6));
+    assert_eq!(
+        arrangement.string_semitone_offset,
+        Some(vec![0, 0, 0, 0, 0, 0])
+    );
+
+    let tab = string_tab(&song);
+    let sections: Vec<(&str, f32)> = tab
+        .sections
+        .iter()
+        .map(|section| (section.name.as_str(), section.start_time))
+        .collect();
+    assert_eq!(sections, [("Intro", 0.0), ("Intro", 2.0), ("Verse", 4.0)]);
+
+    // The repeated first measure is played twice before the second.
+    let notes = notes(&song);
+    assert_eq!(notes.len(), 2 * 6 + 2);
+    let at = |time: f32, string: i32| note_at(notes, time, string);
+
+    assert_eq!(at(0.0, 1).fret, 3);
+    assert_eq!(at(0.5, 1).techniques, [Techniques::HammerOn]);
+    assert_eq!(at(2.5, 1).techniques, [Techniques::HammerOn]);
+
+    let chord_note = at(1.0, 2);
+    assert!(chord_note.techniques.contains(&Techniques::Chord));
+    let chord = &tab.chords[chord_note.chord_index as usize];
+    assert_eq!(chord.frets, [3, 5, 5, -1, -1, -1]);
+    assert_eq!(at(3.0, 0).chord_index, chord_note.chord_index);
+
+    let bend = at(1.5, 2);
+    assert_eq!(bend.max_bend, 2.0);
+    assert_eq!(bend.techniques, [Techniques::Bend]);
+
+    let slide = at(4.0, 3);
+    assert_eq!((slide.fret, slide.slide_to), (7, 9));
+    assert!(slide.techniques.contains(&Techniques::Slide));
+    assert!((slide.sustain - 1.0).abs() < TIME_TOLERANCE);
+}
+
+#[test]
+fn truncated_guitar_pro_files_fail_cleanly() {
+    let bytes = std::fs::read(fixture("guitar_pro/riff.gp3")).unwrap();
+    for end in 0..bytes.len() {
+        assert!(import_guitar_pro(&bytes[..end]).is_err(), "cut at {end}");
+    }
+}
+
+#[test]
+fn huge_measure_count_is_not_allocated_up_front() {
+    let result = import_guitar_pro(&gp3_header(i32::MAX, 1));
+    assert!(matches!(result, Err(ConvertError::UnexpectedEof(_))));
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::fixture;
+use tabs_app::file::lint::{lint_song_folder, LintSeverity, SongLintReport};
+
+fn issues(report: &SongLintReport, arrangement: &str) -> Vec<(LintSeverity, String)> {
+    report
+        .issues
+        .iter()
+        .filter(|issue| issue.arrangement.as_deref() == Some(arrangement))
+        .map(|issue| (issue.severity, issue.message.clone()))
+        .collect()
+}
+
+#[test]
+fn clean_song_has_no_issues() {
+    let report = lint_song_folder(&fixture("lint/clean"));
+    assert!(report.issues.is_empty(), "{:#?}", report.issues);
+}
+
+#[test]
+fn reports_broken_string_charts() {
+    let report = lint_song_folder(&fixture("lint/broken"));
+    assert_eq!(report.count(LintSeverity::Error), 3);
+    assert_eq!(report.count(LintSeverity::Warning), 4);
+    assert!(report
+        .issues
+        .iter()
+        .all(|issue| issue.arrangement.is_some()));
+
+    assert_eq!(
+        issues(&report, "Lead"),
+        [
+            (
+                LintSeverity::Warning,
+                "section 'Intro' at 0.00s comes after 'Verse' at 2.00s".to_string()
+            ),
+            (
+                LintSeverity::Warning,
+                "section 'Chorus' overlaps 'Intro' (2.00s < 2.50s)".to_string()
+            ),
+            (
+                LintSeverity::Error,
+                "difficulty 0: 2 note(s) on a string outside the 6 strings, first at 0.50s"
+                    .to_string()
+            ),
+            (
+                LintSeverity::Error,
+                "difficulty 0: 1 note(s) with a chord_index outside the 1 chords, first at 1.00s"
+                    .to_string()
+            ),
+            (
+                LintSeverity::Warning,
+                "difficulty 0: 2 note(s) after the song ends at 4.00s, first at 4.50s".to_string()
+            ),
+            (
+                LintSeverity::Warning,
+                "techniques used but not declared in song.metadata: Bend, Vibrato".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn missing_string_count_uses_the_instrument_default() {
+    let report = lint_song_folder(&fixture("lint/broken"));
+    assert_eq!(
+        issues(&report, "Bass"),
+        [(
+            LintSeverity::Error,
+            "difficulty 0: 1 note(s) on a string outside the 4 strings, first at 1.00s".to_string()
+        )]
+    );
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::{fixture, hardest_notes, note_at, read_fixture, string_tab, TIME_TOLERANCE};
+use tabs_app::convert::{export_music_xml, import_music_xml, ImportedSong};
+use tabs_app::file::song::{StringTab, TabNote, Techniques};
+
+fn round_trip(song: &ImportedSong) -> ImportedSong {
+    let xml = export_music_xml(song).expect("export succeeds");
+    import_music_xml(&xml).expect("exported score imports again")
+}
+
+fn assert_same_notes(expected: &StringTab, actual: &StringTab) {
+    let expected_notes = hardest_notes(expected);
+    let actual_notes = hardest_notes(actual);
+    assert_eq!(expected_notes.len(), actual_notes.len(), "note count");
+
+    for (want, got) in expected_notes.iter().zip(&actual_notes) {
+        let context = format!("note at {:.3}s on string {}", want.time, want.string);
+        assert!(
+            (want.time - got.time).abs() < TIME_TOLERANCE,
+            "{context}: time {}",
+            got.time
+        );
+        assert_eq!(want.string, got.string, "{context}: string");
+        assert_eq!(want.fret, got.fret, "{context}: fret");
+        assert!(
+            (want.sustain - got.sustain).abs() < TIME_TOLERANCE,
+            "{context}: sustain {} vs {}",
+            want.sustain,
+            got.sustain
+        );
+        assert_eq!(want.slide_to, got.slide_to, "{context}: slide_to");
+        assert_eq!(
+            want.slide_unpitch_to, got.slide_unpitch_to,
+            "{context}: slide_unpitch_to"
+        );
+        assert_eq!(want.max_bend, got.max_bend, "{context}: max_bend");
+        assert_eq!(want.vibrato, got.vibrato, "{context}: vibrato");
+        assert_eq!(want.tap, got.tap, "{context}: tap");
+        assert_eq!(want.slap, got.slap, "{context}: slap");
+        assert_eq!(want.pluck, got.pluck, "{context}: pluck");
+        assert_eq!(
+            want.techniques.len(),
+            got.techniques.len(),
+            "{context}: techniques {:?} vs {:?}",
+            want.techniques,
+            got.techniques
+        );
+        for technique in &want.techniques {
+            assert!(
+                got.techniques.contains(technique),
+                "{context}: missing {technique:?}"
+            );
+        }
+
+        let chord = |tab: &StringTab, note: &TabNote| {
+            usize::try_from(note.chord_index)
+                .ok()
+                .map(|index| tab.chords[index].clone())
+                .map(|chord| (chord.name, chord.frets, chord.fingers))
+        };
+        assert_eq!(
+            chord(expected, want),
+            chord(actual, got),
+            "{context}: chord"
+        );
+    }
+}
+
+#[test]
+fn imports_tablature_from_music_xml() {
+    let text = read_fixture("music_xml/riff.musicxml");
+    let song = import_music_xml(&text).unwrap();
+    assert_eq!(song.metadata.title, "Fixture Riff");
+    assert_eq!(song.metadata.artist, "Test Band");
+    assert!((song.metadata.length - 9.6).abs() < TIME_TOLERANCE);
+
+    let arrangement = &song.metadata.arrangements["Lead Guitar"];
+    assert_eq!(arrangement.string_count, Some(6));
+    assert_eq!(
+        arrangement.string_semitone_offset,
+        Some(vec![-2, 0, 0, 0, 0, 0])
+    );
+
+    let tab = string_tab(&song);
+    let sections: Vec<(&str, f32)> = tab
+        .sections
+        .iter()
+        .map(|section| (section.name.as_str(), section.start_time))
+        .collect();
+    assert_eq!(sections, [("Intro", 0.0), ("Intro", 2.4), ("Verse", 4.8)]);
+
+    // The repeated intro is played twice before the verse.
+    let notes = hardest_notes(tab);
+    assert_eq!(notes.len(), 2 * 7 + 5);
+    let at = |time: f32, string: i32| note_at(&notes, time, string);
+
+    assert!(at(0.0, 0).techniques.contains(&Techniques::PalmMute));
+    assert_eq!(at(1.5, 1).techniques, [Techniques::HammerOn]);
+    assert_eq!(at(1.2, 1).sustain, 0.0);
+
+    let chord_note = at(4.2, 2);
+    assert!(chord_note.techniques.contains(&Techniques::Chord));
+    let chord = &tab.chords[chord_note.chord_index as usize];
+    assert_eq!(chord.name, "G5");
+    assert_eq!(chord.frets, [5, 5, 5, -1, -1, -1]);
+    assert_eq!(chord.fingers, [1, 3, 4, -1, -1, -1]);
+
+    let slide = at(4.8, 2);
+    assert_eq!((slide.fret, slide.slide_to), (2, 4));
+    assert!((slide.sustain - 1.2).abs() < TIME_TOLERANCE);
+
+    // The bend is tied over the barline into the third measure.
+    let bend = at(6.6, 1);
+    assert_eq!(bend.max_bend, 2.0);
+    assert!((bend.sustain - 1.2).abs() < TIME_TOLERANCE);
+
+    let harmonic = at(7.8, 5);
+    assert_eq!(
+        harmonic.techniques,
+        [Techniques::Vibrato, Techniques::Harmonic]
+    );
+    assert_eq!(at(9.3, 3).slide_unpitch_to, 1);
+}
+
+#[test]
+fn music_xml_fixture_round_trips() {
+    let text = read_fixture("music_xml/riff.musicxml");
+    let song = import_music_xml(&text).unwrap();
+    let again = round_trip(&song);
+
+    assert_same_notes(string_tab(&song), string_tab(&again));
+    let sections = |song: &ImportedSong| {
+        string_tab(song)
+            .sections
+            .iter()
+            .map(|section| section.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sections(&song), sections(&again));
+}
+
+#[test]
+fn tab_fixture_round_trips_through_music_xml() {
+    let song = ImportedSong::read_from_folder(&fixture("music_xml/lead")).unwrap();
+    let again = round_trip(&song);
+
+    let original = &song.metadata.arrangements["Lead"];
+    let imported = &again.metadata.arrangements["Lead"];
+    assert_eq!(original.capo_fret, imported.capo_fret);
+    assert_eq!(
+        original.string_semitone_offset,
+        imported.string_semitone_offset
+    );
+
+    let (expected, actual) = (string_tab(&song), string_tab(&again));
+    assert_same_notes(expected, actual);
+    for (want, got) in expected.sections.iter().zip(&actual.sections) {
+        assert_eq!(want.name, got.name);
+        assert!((want.start_time - got.start_time).abs() < TIME_TOLERANCE);
+        assert!(
+            (want.end_time - got.end_time).abs() < TIME_TOLERANCE,
+            "{} vs {}",
+            want.end_time,
+            got.end_time
+        );
+    }
+}
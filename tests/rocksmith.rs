@@ -0,0 +1,149 @@
+mod common;
+
+use common::{fixture, hardest_notes, note_at, read_fixture, TIME_TOLERANCE};
+use tabs_app::convert::{
+    import_rocksmith_file, import_rocksmith_folder, import_rocksmith_xml, ConvertError,
+    ImportedSong,
+};
+use tabs_app::file::song::{StringTab, Tab, TabsInstrument, Techniques, VocalTab};
+
+fn tab<'a>(song: &'a ImportedSong, key: &str) -> &'a Tab {
+    &song
+        .arrangements
+        .iter()
+        .find(|arrangement| arrangement.key == key)
+        .unwrap_or_else(|| panic!("no {key} arrangement"))
+        .tab
+}
+
+fn string_tab<'a>(song: &'a ImportedSong, key: &str) -> &'a StringTab {
+    match tab(song, key) {
+        Tab::Strings(tab) => tab,
+        Tab::Vocals(_) => panic!("expected a string arrangement"),
+    }
+}
+
+fn vocal_tab<'a>(song: &'a ImportedSong, key: &str) -> &'a VocalTab {
+    match tab(song, key) {
+        Tab::Vocals(tab) => tab,
+        Tab::Strings(_) => panic!("expected a vocal arrangement"),
+    }
+}
+
+#[test]
+fn imports_a_rocksmith_song_folder() {
+    let song = import_rocksmith_folder(&fixture("rocksmith/song")).unwrap();
+    assert_eq!(song.metadata.title, "Fixture Riff");
+    assert_eq!(song.metadata.artist, "Test Band");
+    assert_eq!(song.metadata.album, "Fixture Album");
+    assert_eq!(song.metadata.year, 2024);
+    // The last lyric runs past the arrangements' song length.
+    assert!((song.metadata.length - 9.25).abs() < TIME_TOLERANCE);
+
+    // The show lights file is skipped.
+    let mut keys: Vec<&str> = song
+        .arrangements
+        .iter()
+        .map(|arrangement| arrangement.key.as_str())
+        .collect();
+    keys.sort();
+    assert_eq!(keys, ["Bass", "Lead", "Vocals"]);
+
+    let lead = &song.metadata.arrangements["Lead"];
+    assert_eq!(lead.instrument, TabsInstrument::Guitar);
+    assert_eq!(lead.string_count, Some(6));
+    assert_eq!(lead.string_semitone_offset, Some(vec![-2, 0, 0, 0, 0, 0]));
+    assert_eq!(lead.capo_fret, None);
+    assert_eq!(lead.file, "lead.tab");
+
+    let bass = &song.metadata.arrangements["Bass"];
+    assert_eq!(bass.instrument, TabsInstrument::Bass);
+    assert_eq!(bass.string_count, Some(4));
+    assert_eq!(bass.string_semitone_offset, Some(vec![-2, 0, 0, 0]));
+    assert_eq!(bass.capo_fret, Some(2));
+    assert_eq!(bass.techniques, [Techniques::Slap]);
+
+    let vocals = &song.metadata.arrangements["Vocals"];
+    assert_eq!(vocals.instrument, TabsInstrument::Vocals);
+}
+
+#[test]
+fn imports_rocksmith_notes_chords_and_sections() {
+    let song = import_rocksmith_folder(&fixture("rocksmith/song")).unwrap();
+    let tab = string_tab(&song, "Lead");
+
+    let sections: Vec<(&str, f32, f32)> = tab
+        .sections
+        .iter()
+        .map(|section| (section.name.as_str(), section.start_time, section.end_time))
+        .collect();
+    assert_eq!(sections, [("intro", 0.0, 4.0), ("verse", 4.0, 8.0)]);
+
+    let difficulties: Vec<i32> = tab
+        .note_charts
+        .iter()
+        .map(|chart| chart.difficulty)
+        .collect();
+    assert_eq!(difficulties, [0, 1]);
+    assert_eq!(tab.chords.len(), 2);
+    assert_eq!(tab.chords[0].name, "D5");
+    assert_eq!(tab.chords[0].frets, [0, 0, -1, -1, -1, -1]);
+
+    // The ignored note is dropped.
+    let notes = hardest_notes(tab);
+    assert_eq!(notes.len(), 10);
+    let at = |time: f32, string: i32| note_at(&notes, time, string);
+
+    assert_eq!(at(0.0, 0).techniques, [Techniques::PalmMute]);
+    assert_eq!(at(0.0, 0).anchor_fret, 1);
+    assert_eq!(at(1.0, 1).techniques, [Techniques::HammerOn]);
+
+    let bend = at(1.5, 2);
+    assert_eq!((bend.max_bend, bend.vibrato), (1.0, 80));
+    assert_eq!(bend.techniques, [Techniques::Bend, Techniques::Vibrato]);
+    assert!((bend.sustain - 1.0).abs() < TIME_TOLERANCE);
+
+    // A chord without chord notes is expanded from its template.
+    for string in 0..2 {
+        let note = at(3.0, string);
+        assert_eq!(note.chord_index, 0);
+        assert_eq!(note.techniques, [Techniques::Chord, Techniques::PalmMute]);
+    }
+    assert_eq!(
+        at(3.5, 1).techniques,
+        [Techniques::Chord, Techniques::Harmonic]
+    );
+
+    let slide = at(4.0, 3);
+    assert_eq!((slide.fret, slide.slide_to), (2, 4));
+    assert_eq!(slide.anchor_fret, 2);
+    assert_eq!(slide.techniques, [Techniques::Slide]);
+
+    // Single notes inside an arpeggio hand shape point at its template.
+    let arpeggio = at(6.0, 1);
+    assert_eq!(arpeggio.chord_index, 1);
+    assert_eq!(arpeggio.techniques, [Techniques::Arpeggio]);
+}
+
+#[test]
+fn imports_rocksmith_vocals_in_time_order() {
+    let song = import_rocksmith_file(&fixture("rocksmith/song/vocals.xml")).unwrap();
+    assert_eq!(song.arrangements.len(), 1);
+
+    let lyrics: Vec<(f32, &str)> = vocal_tab(&song, "Vocals")
+        .vocals
+        .iter()
+        .map(|vocal| (vocal.time, vocal.lyric.as_str()))
+        .collect();
+    assert_eq!(lyrics, [(4.0, "Fix-"), (4.5, "ture"), (8.5, "song+")]);
+    assert!((song.metadata.length - 9.25).abs() < TIME_TOLERANCE);
+}
+
+#[test]
+fn rejects_other_xml_documents() {
+    let text = read_fixture("rocksmith/song/showlights.xml");
+    assert!(matches!(
+        import_rocksmith_xml(&[&text]),
+        Err(ConvertError::UnsupportedFormat(_))
+    ));
+}